@@ -40,7 +40,8 @@ const DEFAULT_SETTINGS: AppSettings = {
   habits: {
     defaultPriority: 'medium',
     defaultReminder: false,
-    defaultReminderTime: '09:00'
+    defaultReminderTime: '09:00',
+    streakGraceDays: 0
   },
   goals: {
     defaultCategory: 'Productivity',
@@ -50,7 +51,13 @@ const DEFAULT_SETTINGS: AppSettings = {
   notifications: {
     habitReminders: true,
     goalDeadlines: true,
-    streakReminders: true
+    streakReminders: true,
+    quietHours: {
+      enabled: false,
+      start: '22:00',
+      end: '07:00',
+      days: []
+    }
   },
   data: {
     autoBackup: true,
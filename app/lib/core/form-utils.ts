@@ -43,7 +43,8 @@ export class HabitFormManager {
       completed: record?.completed ?? false,
       actualAmount: record?.actualAmount ?? 0,
       note: record?.note ?? '',
-      mood: record?.mood ?? null,
+      // The edit form picks a single mood
+      mood: record?.mood[0] ?? null,
       difficulty: record?.difficulty ?? null,
       skipped: record?.skipped ?? false
     };
@@ -78,7 +79,7 @@ export class HabitFormManager {
         onSetHabitCompletion(habit.id, dateString, completed, {
           actualAmount: newAmount,
           note: currentRecord?.note || '',
-          mood: currentRecord?.mood || null,
+          mood: currentRecord?.mood ?? [],
           difficulty: currentRecord?.difficulty || null,
           skipped: false
         });
@@ -91,7 +92,7 @@ export class HabitFormManager {
         const additionalData = {
           actualAmount: newAmount,
           note: currentRecord?.note || '',
-          mood: currentRecord?.mood || null,
+          mood: currentRecord?.mood ?? [],
           difficulty: currentRecord?.difficulty || null,
           skipped: false
         };
@@ -107,7 +108,7 @@ export class HabitFormManager {
           onSetHabitCompletion(habit.id, dateString, true, {
             actualAmount: targetAmount,
             note: currentRecord?.note || '',
-            mood: currentRecord?.mood || null,
+            mood: currentRecord?.mood ?? [],
             difficulty: currentRecord?.difficulty || null,
             skipped: false
           });
@@ -115,7 +116,7 @@ export class HabitFormManager {
           onSetHabitCompletion(habit.id, dateString, false, {
             actualAmount: 0,
             note: currentRecord?.note || '',
-            mood: currentRecord?.mood || null,
+            mood: currentRecord?.mood ?? [],
             difficulty: currentRecord?.difficulty || null,
             skipped: false
           });
@@ -126,7 +127,7 @@ export class HabitFormManager {
         onSetHabitCompletion(habit.id, dateString, true, {
           actualAmount: targetAmount,
           note: currentRecord?.note || '',
-          mood: currentRecord?.mood || null,
+          mood: currentRecord?.mood ?? [],
           difficulty: currentRecord?.difficulty || null,
           skipped: false
         });
@@ -136,7 +137,7 @@ export class HabitFormManager {
         onSetHabitCompletion(habit.id, dateString, false, {
           actualAmount: 0,
           note: currentRecord?.note || '',
-          mood: currentRecord?.mood || null,
+          mood: currentRecord?.mood ?? [],
           difficulty: currentRecord?.difficulty || null,
           skipped: false
         });
@@ -148,7 +149,7 @@ export class HabitFormManager {
         onSetHabitCompletion(habit.id, dateString, false, {
           actualAmount: newSkipped ? 0 : currentAmount,
           note: currentRecord?.note || '',
-          mood: currentRecord?.mood || null,
+          mood: currentRecord?.mood ?? [],
           difficulty: currentRecord?.difficulty || null,
           skipped: newSkipped
         });
@@ -158,7 +159,7 @@ export class HabitFormManager {
         onSetHabitCompletion(habit.id, dateString, editData.completed, {
           actualAmount: editData.actualAmount,
           note: editData.note,
-          mood: editData.mood,
+          mood: editData.mood ? [editData.mood] : [],
           difficulty: editData.difficulty,
           skipped: editData.skipped
         });
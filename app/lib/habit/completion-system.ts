@@ -34,7 +34,7 @@ export class HabitCompletionManager {
       targetAmount: ValidationUtils.validateNumber(data.targetAmount ?? 1, 1, 1),
       completedAt: data.completed ? data.completedAt || new Date().toISOString() : null,
       note: ValidationUtils.sanitizeString(data.note ?? ''),
-      mood: data.mood || null,
+      mood: data.mood ?? [],
       difficulty: data.difficulty || null,
       skipped: data.skipped || false
     };
@@ -181,7 +181,7 @@ export function useHabitCalendar(
       const completionData: Partial<CompletionRecord> = {
         actualAmount: editFormData.actualAmount,
         note: editFormData.note,
-        mood: editFormData.mood,
+        mood: editFormData.mood ? [editFormData.mood] : [],
         difficulty: editFormData.difficulty,
         skipped: editFormData.skipped
       };
@@ -431,11 +431,11 @@ export function useHabits(): UseHabitsReturn {
         ? {
             actualAmount: newAmount,
             note: existingRecord?.note || '',
-            mood: existingRecord?.mood || null,
+            mood: existingRecord?.mood ?? [],
             difficulty: existingRecord?.difficulty || null,
             skipped: false
           }
-        : { actualAmount: newAmount, note: '', mood: null, difficulty: null, skipped: false };
+        : { actualAmount: newAmount, note: '', mood: [], difficulty: null, skipped: false };
 
       await setHabitCompletion(habitId, date, newCompleted, additionalData);
     },
@@ -8,6 +8,7 @@ import type {
   DateString,
   DeleteStrategy,
   NotificationPayload,
+  NotificationDelivery,
   NotificationHistory,
   NotificationSchedule,
   NotificationHistoryRecord,
@@ -20,10 +21,30 @@ interface PasswordStrength {
   feedback: string[];
 }
 
+/** Argon2id costs for a new hash; omitted fields use the defaults */
+interface HashParams {
+  /** Memory size in KiB, at most 262144 (256 MiB) */
+  memoryCost?: number;
+  /** Passes, at most 16 */
+  timeCost?: number;
+  parallelism?: number;
+}
+
 interface AppInfo {
   version: string;
   name: string;
   authors: string;
+  buildTimestamp: string;
+  gitCommit: string;
+}
+
+export interface HealthStatus {
+  ready: boolean;
+  databaseReachable: boolean;
+  schemaVersion: number | null;
+  expectedSchemaVersion: number;
+  settingsPresent: boolean;
+  error: string | null;
 }
 
 // Settings Types
@@ -37,6 +58,8 @@ export interface HabitSettings {
   defaultReminder: boolean;
   defaultReminderTime: string;
   defaultPriority: 'low' | 'medium' | 'high';
+  /** Missed days a streak tolerates in a row before it breaks */
+  streakGraceDays?: number;
 }
 
 export interface GoalSettings {
@@ -48,6 +71,21 @@ export interface GoalSettings {
 export interface DataSettings {
   autoBackup: boolean;
   backupFrequency: 'daily' | 'weekly' | 'monthly';
+  /** Most goals, tasks or habits that can be created; null disables the cap */
+  maxRowsPerTable?: number | null;
+}
+
+export interface SystemSettings {
+  launchOnLogin: boolean;
+  closeBehavior: 'tray' | 'quit' | 'ask';
+  updateChannel: 'stable' | 'beta';
+}
+
+export interface SyncSettings {
+  enabled: boolean;
+  allowLan: boolean;
+  port: number;
+  token: string;
 }
 
 export interface AppSettings {
@@ -56,36 +94,87 @@ export interface AppSettings {
   goals: GoalSettings;
   notifications: NotificationSettings;
   data: DataSettings;
+  system?: SystemSettings;
+  sync?: SyncSettings;
+}
+
+export type ExportEntity =
+  | 'goals'
+  | 'tasks'
+  | 'habits'
+  | 'habit_completions'
+  | 'habit_pauses'
+  | 'habit_frequency_history'
+  | 'attachments'
+  | 'focus_sessions'
+  | 'notification_schedules'
+  | 'notification_history';
+
+export interface ExportOptions {
+  entities?: ExportEntity[];
+  startDate?: string;
+  endDate?: string;
+}
+
+export interface CompletionPage {
+  completions: HabitCompletion[];
+  nextCursor: DateString | null;
+}
+
+export interface ImportSummary {
+  goals: number;
+  tasks: number;
+  habits: number;
+  completions: number;
+  settingsApplied: boolean;
 }
 
 interface AuthAPI {
   hashPassword: (password: string) => Promise<string>;
   verifyPassword: (password: string, hashedPassword: string) => Promise<boolean>;
+  rehashPassword: (password: string, hashedPassword: string, newParams?: HashParams) => Promise<string>;
   checkPasswordStrength: (password: string) => Promise<PasswordStrength>;
 }
 
 interface NotificationsAPI {
-  sendSystemNotification: (payload: NotificationPayload) => Promise<void>;
+  sendSystemNotification: (payload: NotificationPayload) => Promise<NotificationDelivery>;
   checkNotificationPermission: () => Promise<boolean>;
   requestNotificationPermission: () => Promise<boolean>;
   scheduleNotification: (schedule: NotificationSchedule) => Promise<NotificationSchedule>;
   getScheduledNotifications: () => Promise<NotificationSchedule[]>;
   getHabitNotifications: (habitId: string) => Promise<NotificationSchedule[]>;
+  setHabitReminders: (habitId: string, times: string[]) => Promise<NotificationSchedule[]>;
   cancelNotification: (habitId: string) => Promise<boolean>;
+  cancelNotificationById: (id: number) => Promise<boolean>;
   cancelAllNotifications: () => Promise<number>;
   recordNotification: (history: NotificationHistoryRecord) => Promise<NotificationHistoryRecord>;
   getNotificationHistory: (limit?: number) => Promise<NotificationHistory[]>;
   markNotificationOpened: (notificationId: string, actionTaken?: string) => Promise<void>;
   cleanNotificationHistory: (daysToKeep: number) => Promise<number>;
+  cleanupOrphanedSchedules: () => Promise<number>;
+  cleanupOrphanedHistory: () => Promise<number>;
+}
+
+export type GoalSortKey = 'deadline' | 'priority' | 'title' | 'createdAt';
+
+export interface GoalWithTasks {
+  goal: Goal;
+  tasks: Task[];
 }
 
 interface GoalsAPI {
   getAllGoals: () => Promise<Goal[]>;
+  getGoalsSorted: (sortBy: GoalSortKey, ascending: boolean) => Promise<Goal[]>;
   getGoalById: (id: string) => Promise<Goal | null>;
   getGoalsByStatus: (status: string) => Promise<Goal[]>;
-  createGoal: (goal: Goal) => Promise<Goal>;
-  updateGoal: (goal: Goal) => Promise<Goal>;
+  renameGoalCategory: (oldName: string, newName: string) => Promise<number>;
+  getGoalCategories: () => Promise<string[]>;
+  createGoal: (goal: Goal, idempotencyKey?: string) => Promise<Goal>;
+  createGoalWithTasks: (goal: Goal, tasks: Task[], idempotencyKey?: string) => Promise<GoalWithTasks>;
+  /** With `expectedUpdatedAt`, fails with a "Conflict: ..." error if the goal changed since it was loaded */
+  updateGoal: (goal: Goal, expectedUpdatedAt?: string) => Promise<Goal>;
   deleteGoal: (id: string, deleteStrategy?: DeleteStrategy) => Promise<boolean>;
+  undoLastDelete: () => Promise<Goal>;
 }
 
 interface TasksAPI {
@@ -93,19 +182,68 @@ interface TasksAPI {
   getTaskById: (taskId: string) => Promise<Task | null>;
   getTasksByGoalId: (goalId: string) => Promise<Task[]>;
   getTasksByStatus: (done: boolean) => Promise<Task[]>;
+  getTasksDueBetween: (startDate: string, endDate: string, includeDone: boolean) => Promise<Task[]>;
   getSubtasks: (parentTaskId: string) => Promise<Task[]>;
-  createTask: (task: Task) => Promise<Task>;
-  updateTask: (task: Task) => Promise<Task>;
+  createTask: (task: Task, idempotencyKey?: string) => Promise<Task>;
+  updateTask: (task: Task, expectedUpdatedAt?: string) => Promise<Task>;
   deleteTask: (taskId: string) => Promise<boolean>;
   toggleTaskStatus: (taskId: string) => Promise<boolean>;
+  setTasksDone: (ids: string[], done: boolean) => Promise<number>;
+  reassignTasks: (taskIds: string[], newGoalId: string | null) => Promise<number>;
+}
+
+export type AttachmentEntity = 'goal' | 'task';
+export type AttachmentKind = 'url' | 'file';
+
+export interface Attachment {
+  id: number;
+  entityType: AttachmentEntity;
+  entityId: string;
+  kind: AttachmentKind;
+  /** An http(s) URL or an absolute file path */
+  value: string;
+  label: string;
+  createdAt: string;
+}
+
+interface AttachmentsAPI {
+  addAttachment: (
+    entityType: AttachmentEntity,
+    entityId: string,
+    kind: AttachmentKind,
+    value: string,
+    label?: string
+  ) => Promise<Attachment>;
+  removeAttachment: (id: number) => Promise<boolean>;
+  getAttachments: (entityType: AttachmentEntity, entityId: string) => Promise<Attachment[]>;
+}
+
+export interface FocusSession {
+  id: number;
+  taskId: string | null;
+  startedAt: string;
+  /** Null while the session is running */
+  endedAt: string | null;
+  durationSeconds: number | null;
+}
+
+interface FocusSessionsAPI {
+  startFocusSession: (taskId?: string) => Promise<FocusSession>;
+  endFocusSession: (id: number) => Promise<FocusSession>;
+  getFocusSessions: (taskId?: string, startDate?: DateString, endDate?: DateString) => Promise<FocusSession[]>;
+  getTaskTimeSpent: (taskId: string) => Promise<number>;
 }
 
 interface HabitsAPI {
   getAllHabits: () => Promise<Habit[]>;
   getHabitById: (id: string) => Promise<Habit | null>;
   getHabitsByCategory: (category: string) => Promise<Habit[]>;
-  createHabit: (habit: Habit) => Promise<Habit>;
-  updateHabit: (habit: Habit) => Promise<Habit>;
+  renameHabitCategory: (oldName: string, newName: string) => Promise<number>;
+  getHabitCategories: () => Promise<string[]>;
+  /** Recency-weighted share of scheduled days completed; `decay` defaults to 0.9 per day */
+  getConsistencyScore: (habitId: string, windowDays: number, decay?: number) => Promise<number>;
+  createHabit: (habit: Habit, idempotencyKey?: string) => Promise<Habit>;
+  updateHabit: (habit: Habit, expectedUpdatedAt?: string) => Promise<Habit>;
   deleteHabit: (id: string) => Promise<boolean>;
 }
 
@@ -116,33 +254,55 @@ interface HabitCompletionsAPI {
     endDate: DateString | null,
     limit?: number
   ) => Promise<HabitCompletion[]>;
+  getHabitCompletionsPage: (habitId: string, beforeDate?: DateString, limit?: number) => Promise<CompletionPage>;
   getCompletionByDate: (habitId: string, date: DateString) => Promise<HabitCompletion | null>;
+  getCompletionsByDates: (habitId: string, dates: DateString[]) => Promise<HabitCompletion[]>;
   getHabitStreak: (habitId: string) => Promise<number>;
-  createHabitCompletion: (completion: HabitCompletion) => Promise<HabitCompletion>;
+  createHabitCompletion: (completion: HabitCompletion, unit?: string) => Promise<HabitCompletion>;
+  createHabitCompletionsBatch: (completions: HabitCompletion[], unit?: string) => Promise<number>;
   updateHabitCompletion: (completion: HabitCompletion) => Promise<HabitCompletion>;
   deleteHabitCompletion: (id: string) => Promise<boolean>;
+  skipHabits: (habitIds: string[], startDate: DateString, endDate: DateString) => Promise<number>;
+  convertAmount: (value: number, fromUnit: string, toUnit: string) => Promise<number>;
 }
 
 interface SettingsAPI {
   getSettings: () => Promise<AppSettings | null>;
+  getSettingsOrDefault: () => Promise<AppSettings>;
   saveSettings: (settings: AppSettings) => Promise<AppSettings>;
+  patchSettings: (patch: Record<string, any>) => Promise<AppSettings>;
   updateAppearanceSettings: (appearance: AppearanceSettings) => Promise<AppSettings>;
   updateHabitSettings: (habits: HabitSettings) => Promise<AppSettings>;
   updateGoalSettings: (goals: GoalSettings) => Promise<AppSettings>;
   updateNotificationSettings: (notifications: NotificationSettings) => Promise<AppSettings>;
   updateDataSettings: (data: DataSettings) => Promise<AppSettings>;
   resetSettings: (args: { defaultSettings: AppSettings }) => Promise<AppSettings>;
-  exportAllData: () => Promise<string>;
-  importAllData: (jsonData: string) => Promise<string>;
+  exportAllData: (pretty?: boolean) => Promise<string>;
+  exportFiltered: (options: ExportOptions) => Promise<string>;
+  importAllData: (jsonData: string) => Promise<ImportSummary>;
+  exportHabit: (habitId: string) => Promise<string>;
+  importHabit: (jsonData: string) => Promise<ImportSummary>;
   exportSettings: () => Promise<string>;
   importSettings: (jsonData: string) => Promise<AppSettings>;
 }
 
+export interface StorageUsage {
+  totalBytes: number;
+  databaseBytes: number;
+  walBytes: number;
+  backupBytes: number;
+  userConfigBytes: number;
+  logBytes: number;
+  otherBytes: number;
+}
+
 interface UpdatersAPI {
   getAppVersion: () => Promise<string>;
   getAppInfo: () => Promise<AppInfo>;
+  healthCheck: () => Promise<HealthStatus>;
   getAppDataDir: () => Promise<string>;
   getAppLogDir: () => Promise<string>;
+  getStorageUsage: () => Promise<StorageUsage>;
   isDevMode: () => Promise<boolean>;
 }
 
@@ -154,6 +314,14 @@ interface UserDataAPI {
   getField: (field: string) => Promise<any>;
   delete: () => Promise<void>;
   exists: () => Promise<boolean>;
+  setLastView: (view: string, viewState?: Record<string, any> | null) => Promise<void>;
+  getLastView: () => Promise<LastView>;
+}
+
+export interface LastView {
+  view: string;
+  /** Scroll position, selection or other view-specific state */
+  viewState?: Record<string, any> | null;
 }
 
 interface TauriAPI {
@@ -161,6 +329,8 @@ interface TauriAPI {
   userData: UserDataAPI;
   goals: GoalsAPI;
   tasks: TasksAPI;
+  attachments: AttachmentsAPI;
+  focusSessions: FocusSessionsAPI;
   habits: HabitsAPI;
   habitCompletions: HabitCompletionsAPI;
   settings: SettingsAPI;
@@ -172,6 +342,8 @@ const tauriAPI: TauriAPI = {
   auth: {
     hashPassword: (password) => invoke('hash_password', { password }),
     verifyPassword: (password, hashedPassword) => invoke('verify_password', { password, hashedPassword }),
+    rehashPassword: (password, hashedPassword, newParams) =>
+      invoke('rehash_password', { password, hashedPassword, newParams }),
     checkPasswordStrength: (password) => invoke('check_password_strength', { password })
   },
 
@@ -182,60 +354,103 @@ const tauriAPI: TauriAPI = {
     updateBatch: (updates) => invoke('update_user_data_batch', { updates }),
     getField: (field) => invoke('get_user_data_field', { field }),
     delete: () => invoke('delete_user_data'),
-    exists: () => invoke('user_data_exists')
+    exists: () => invoke('user_data_exists'),
+    setLastView: (view, viewState) => invoke('set_last_view', { view, viewState }),
+    getLastView: () => invoke('get_last_view')
   },
 
   goals: {
-    createGoal: (goal) => invoke('create_goal', { goal }),
-    updateGoal: (goal) => invoke('update_goal', { goal }),
+    createGoal: (goal, idempotencyKey) => invoke('create_goal', { goal, idempotencyKey }),
+    createGoalWithTasks: (goal, tasks, idempotencyKey) =>
+      invoke('create_goal_with_tasks', { goal, tasks, idempotencyKey }),
+    updateGoal: (goal, expectedUpdatedAt) => invoke('update_goal', { goal, expectedUpdatedAt }),
     deleteGoal: (id, deleteStrategy) => invoke('delete_goal', { id, deleteStrategy }),
+    undoLastDelete: () => invoke('undo_last_delete'),
     getAllGoals: () => invoke('get_all_goals'),
+    getGoalsSorted: (sortBy, ascending) => invoke('get_goals_sorted', { sortBy, ascending }),
     getGoalById: (id) => invoke('get_goal_by_id', { id }),
-    getGoalsByStatus: (status) => invoke('get_goals_by_status', { status })
+    getGoalsByStatus: (status) => invoke('get_goals_by_status', { status }),
+    renameGoalCategory: (oldName, newName) => invoke('rename_goal_category', { old: oldName, new: newName }),
+    getGoalCategories: () => invoke('get_goal_categories')
   },
 
   tasks: {
-    createTask: (task) => invoke('create_task', { task }),
-    updateTask: (task) => invoke('update_task', { task }),
+    createTask: (task, idempotencyKey) => invoke('create_task', { task, idempotencyKey }),
+    updateTask: (task, expectedUpdatedAt) => invoke('update_task', { task, expectedUpdatedAt }),
     deleteTask: (id) => invoke('delete_task', { id }),
     getAllTasks: () => invoke('get_all_tasks'),
     getTaskById: (id) => invoke('get_task_by_id', { id }),
     getTasksByGoalId: (goalId) => invoke('get_tasks_by_goal_id', { goalId }),
     getTasksByStatus: (done) => invoke('get_tasks_by_status', { done }),
+    getTasksDueBetween: (startDate, endDate, includeDone) =>
+      invoke('get_tasks_due_between', { startDate, endDate, includeDone }),
     getSubtasks: (parentTaskId) => invoke('get_subtasks', { parentTaskId }),
-    toggleTaskStatus: (id) => invoke('toggle_task_status', { id })
+    toggleTaskStatus: (id) => invoke('toggle_task_status', { id }),
+    setTasksDone: (ids, done) => invoke('set_tasks_done', { ids, done }),
+    reassignTasks: (taskIds, newGoalId) => invoke('reassign_tasks', { taskIds, newGoalId })
+  },
+
+  attachments: {
+    addAttachment: (entityType, entityId, kind, value, label) =>
+      invoke('add_attachment', { entityType, entityId, kind, value, label }),
+    removeAttachment: (id) => invoke('remove_attachment', { id }),
+    getAttachments: (entityType, entityId) => invoke('get_attachments', { entityType, entityId })
+  },
+
+  focusSessions: {
+    startFocusSession: (taskId) => invoke('start_focus_session', { taskId }),
+    endFocusSession: (id) => invoke('end_focus_session', { id }),
+    getFocusSessions: (taskId, startDate, endDate) => invoke('get_focus_sessions', { taskId, startDate, endDate }),
+    getTaskTimeSpent: (taskId) => invoke('get_task_time_spent', { taskId })
   },
 
   habits: {
-    createHabit: (habit) => invoke('create_habit', { habit }),
-    updateHabit: (habit) => invoke('update_habit', { habit }),
+    createHabit: (habit, idempotencyKey) => invoke('create_habit', { habit, idempotencyKey }),
+    updateHabit: (habit, expectedUpdatedAt) => invoke('update_habit', { habit, expectedUpdatedAt }),
     deleteHabit: (id) => invoke('delete_habit', { id }),
     getAllHabits: () => invoke('get_all_habits'),
     getHabitById: (id) => invoke('get_habit_by_id', { id }),
-    getHabitsByCategory: (category) => invoke('get_habits_by_category', { category })
+    getHabitsByCategory: (category) => invoke('get_habits_by_category', { category }),
+    renameHabitCategory: (oldName, newName) => invoke('rename_habit_category', { old: oldName, new: newName }),
+    getHabitCategories: () => invoke('get_habit_categories'),
+    getConsistencyScore: (habitId, windowDays, decay) =>
+      invoke('get_consistency_score', { habitId, windowDays, decay })
   },
 
   habitCompletions: {
-    createHabitCompletion: (completion) => invoke('create_habit_completion', { completion }),
+    createHabitCompletion: (completion, unit) =>
+      invoke('create_habit_completion', { completion, unit: unit ?? null }),
+    createHabitCompletionsBatch: (completions, unit) =>
+      invoke('create_habit_completions_batch', { completions, unit: unit ?? null }),
     updateHabitCompletion: (completion) => invoke('update_habit_completion', { completion }),
     deleteHabitCompletion: (id) => invoke('delete_habit_completion', { id }),
+    skipHabits: (habitIds, startDate, endDate) => invoke('skip_habits', { habitIds, startDate, endDate }),
     getHabitCompletions: (habitId, startDate, endDate, limit) =>
       invoke('get_habit_completions', { habitId, startDate, endDate, limit }),
+    getHabitCompletionsPage: (habitId, beforeDate, limit) =>
+      invoke('get_habit_completions_page', { habitId, beforeDate: beforeDate ?? null, limit }),
     getCompletionByDate: (habitId, date) => invoke('get_completion_by_date', { habitId, date }),
-    getHabitStreak: (habitId) => invoke('get_habit_streak', { habitId })
+    getCompletionsByDates: (habitId, dates) => invoke('get_completions_by_dates', { habitId, dates }),
+    getHabitStreak: (habitId) => invoke('get_habit_streak', { habitId }),
+    convertAmount: (value, fromUnit, toUnit) => invoke('convert_amount', { value, fromUnit, toUnit })
   },
 
   settings: {
     getSettings: () => invoke('get_settings'),
+    getSettingsOrDefault: () => invoke('get_settings_or_default'),
     saveSettings: (settings) => invoke('save_settings', { settings }),
+    patchSettings: (patch) => invoke('patch_settings', { patch }),
     updateAppearanceSettings: (appearance) => invoke('update_appearance_settings', { appearance }),
     updateHabitSettings: (habits) => invoke('update_habit_settings', { habits }),
     updateGoalSettings: (goals) => invoke('update_goal_settings', { goals }),
     updateNotificationSettings: (notifications) => invoke('update_notification_settings', { notifications }),
     updateDataSettings: (data) => invoke('update_data_settings', { data }),
     resetSettings: (args) => invoke('reset_settings', args),
-    exportAllData: () => invoke('export_all_data'),
+    exportAllData: (pretty) => invoke('export_all_data', { pretty: pretty ?? null }),
+    exportFiltered: (options) => invoke('export_filtered', { options }),
     importAllData: (jsonData) => invoke('import_all_data', { jsonData }),
+    exportHabit: (habitId) => invoke('export_habit', { habitId }),
+    importHabit: (jsonData) => invoke('import_habit', { jsonData }),
     exportSettings: () => invoke('export_settings'),
     importSettings: (jsonData) => invoke('import_settings', { jsonData })
   },
@@ -245,13 +460,17 @@ const tauriAPI: TauriAPI = {
     scheduleNotification: (schedule) => invoke('schedule_notification', { schedule }),
     getScheduledNotifications: () => invoke('get_scheduled_notifications'),
     getHabitNotifications: (habitId) => invoke('get_habit_notifications', { habitId }),
+    setHabitReminders: (habitId, times) => invoke('set_habit_reminders', { habitId, times }),
     cancelNotification: (habitId) => invoke('cancel_notification', { habitId }),
+    cancelNotificationById: (id) => invoke('cancel_notification_by_id', { id }),
     cancelAllNotifications: () => invoke('cancel_all_notifications'),
     recordNotification: (history) => invoke('record_notification', { history }),
     getNotificationHistory: (limit) => invoke('get_notification_history', { limit: limit ?? null }),
     markNotificationOpened: (notificationId, actionTaken) =>
       invoke('mark_notification_opened', { notificationId, actionTaken: actionTaken ?? null }),
     cleanNotificationHistory: (daysToKeep) => invoke('clean_notification_history', { daysToKeep }),
+    cleanupOrphanedSchedules: () => invoke('cleanup_orphaned_schedules'),
+    cleanupOrphanedHistory: () => invoke('cleanup_orphaned_history'),
     checkNotificationPermission: () => invoke('check_notification_permission'),
     requestNotificationPermission: () => invoke('request_notification_permission')
   },
@@ -259,8 +478,10 @@ const tauriAPI: TauriAPI = {
   updater: {
     getAppVersion: () => invoke('get_app_version'),
     getAppInfo: () => invoke('get_app_info'),
+    healthCheck: () => invoke('health_check'),
     getAppDataDir: () => invoke('get_app_data_dir'),
     getAppLogDir: () => invoke('get_app_log_dir'),
+    getStorageUsage: () => invoke('get_storage_usage'),
     isDevMode: () => invoke('is_dev_mode')
   }
 };
@@ -102,6 +102,8 @@ export type HabitFrequencyWithSummary = HabitFrequency & {
 export interface HabitReminder {
   enabled: boolean;
   time: TimeString;
+  /** Every daily reminder time, earliest first. Change them with setHabitReminders. */
+  times?: TimeString[];
 }
 
 // ============================================================================
@@ -123,9 +125,19 @@ export interface NotificationPayload {
   icon?: string;
   actions?: NotificationAction[];
   data?: Record<string, any>;
+  /** Recurring reminders are skipped during quiet hours instead of deferred */
+  isRecurring?: boolean;
 }
 
+export type NotificationDelivery =
+  | { status: 'shown' }
+  | { status: 'paused' }
+  | { status: 'skipped' }
+  | { status: 'deferred'; until: string };
+
 export interface NotificationSchedule {
+  /** Row id, assigned once the schedule is stored */
+  id?: number | null;
   habitId: string;
   habitName: string;
   scheduledTime: string;
@@ -168,6 +180,16 @@ export interface NotificationSettings {
   habitReminders: boolean;
   streakReminders: boolean;
   goalDeadlines?: boolean;
+  quietHours?: QuietHours;
+}
+
+export interface QuietHours {
+  enabled: boolean;
+  /** HH:MM, local time. A start later than the end runs past midnight. */
+  start: string;
+  end: string;
+  /** Lowercase weekday names the window starts on; empty means every day */
+  days: string[];
 }
 
 // ============================================================================
@@ -190,6 +212,8 @@ export interface Habit {
   reminder: HabitReminder;
   createdAt: Timestamp;
   updatedAt: Timestamp;
+  /** Share of the target (0-1) that counts as completed, 1 when omitted */
+  completionThreshold?: number;
 }
 
 export interface HabitFormData {
@@ -240,7 +264,7 @@ export interface HabitCompletion {
   targetAmount: number;
   completedAt: Timestamp | null;
   note: string;
-  mood: MoodValue | null;
+  mood: MoodValue[];
   difficulty: DifficultyValue | null;
   skipped: boolean;
   createdAt: Timestamp;
@@ -253,7 +277,7 @@ export interface CompletionRecord {
   targetAmount: number;
   completedAt: Timestamp | null;
   note: string;
-  mood: MoodValue | null;
+  mood: MoodValue[];
   difficulty: DifficultyValue | null;
   skipped: boolean;
 }
@@ -280,6 +304,7 @@ export interface Goal {
   linkedHabits: string[];
   createdAt: Timestamp;
   updatedAt: Timestamp;
+  completedAt?: Timestamp | null;
 }
 
 export interface GoalFormData {
@@ -167,7 +167,7 @@ export default function LoomraApp() {
     await habitsCtx.setHabitCompletion(habitId, date, true, {
       actualAmount: 1,
       note: '',
-      mood: null,
+      mood: [],
       difficulty: null,
       skipped: false
     });
@@ -178,7 +178,7 @@ export default function LoomraApp() {
     await habitsCtx.setHabitCompletion(habitId, date, false, {
       actualAmount: 0,
       note: '',
-      mood: null,
+      mood: [],
       difficulty: null,
       skipped: true
     });
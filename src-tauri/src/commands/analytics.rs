@@ -0,0 +1,323 @@
+use crate::commands::settings::{configured_now, load_settings_from_db};
+use crate::database::{with_connection, AppState};
+use crate::timing::CommandTimer;
+use chrono::Duration;
+use rusqlite::params;
+use serde::Serialize;
+
+/// Numeric scale for difficulty values, used for averaging
+const DIFFICULTY_SCORE_SQL: &str = "CASE difficulty
+    WHEN 'very-easy' THEN 1
+    WHEN 'easy' THEN 2
+    WHEN 'medium' THEN 3
+    WHEN 'hard' THEN 4
+    WHEN 'very-hard' THEN 5
+END";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoodCount {
+    pub mood: String,
+    pub count: i64,
+    pub completed: i64,
+    pub completion_rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DifficultyCount {
+    pub difficulty: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DifficultyPoint {
+    pub week: String,
+    pub average_difficulty: f64,
+    pub entries: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MoodAnalytics {
+    pub mood_distribution: Vec<MoodCount>,
+    pub difficulty_distribution: Vec<DifficultyCount>,
+    pub average_difficulty: Option<f64>,
+    pub difficulty_over_time: Vec<DifficultyPoint>,
+    pub overall_completion_rate: f64,
+    pub insights: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategorySummary {
+    pub category: String,
+    pub habit_count: i64,
+    /// Days logged across the category's habits within the window, skipped days excluded
+    pub logged_days: i64,
+    pub completed_days: i64,
+    pub completion_rate: f64,
+}
+
+/// A completed task, logged habit completion or finished goal in the activity feed
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityItem {
+    /// One of "task", "habit" or "goal"
+    pub activity_type: String,
+    /// Id of the task, habit completion or goal
+    pub id: String,
+    /// Task or goal title, or the habit's name for completions
+    pub title: String,
+    pub timestamp: String,
+}
+
+/// Ratio helper that avoids dividing by zero
+fn rate(part: i64, total: i64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+/// Analyze logged mood and difficulty values, optionally for a single habit
+#[tauri::command]
+pub async fn get_mood_trends(
+    state: tauri::State<'_, AppState>,
+    habit_id: Option<String>,
+    start_date: String,
+    end_date: String,
+) -> Result<MoodAnalytics, String> {
+    let _timer = CommandTimer::start("get_mood_trends");
+    with_connection(&state, |db| {
+        // Overall completion rate across all logged days in the range. Skipped days are
+        // intentional rest days, so they're left out rather than counted as misses.
+        let (total_days, completed_days): (i64, i64) = db
+            .query_row(
+                "SELECT COUNT(*), COALESCE(SUM(completed), 0)
+                 FROM habit_completions
+                 WHERE (?1 IS NULL OR habit_id = ?1) AND date BETWEEN ?2 AND ?3 AND skipped = 0",
+                params![habit_id, start_date, end_date],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| format!("Failed to query completion totals: {}", e))?;
+
+        let overall_completion_rate = rate(completed_days, total_days);
+
+        // Mood distribution with per-mood completion rate. Each tag of a day counts on its own,
+        // and moods saved before tags existed are a bare string rather than a JSON array.
+        let mut stmt = db
+            .prepare(
+                "SELECT tag.value, COUNT(*), COALESCE(SUM(c.completed), 0)
+                 FROM habit_completions c,
+                      json_each(CASE WHEN json_valid(c.mood) AND json_type(c.mood) = 'array'
+                                     THEN c.mood ELSE json_array(c.mood) END) tag
+                 WHERE (?1 IS NULL OR c.habit_id = ?1) AND c.date BETWEEN ?2 AND ?3
+                   AND c.mood IS NOT NULL AND c.mood != '' AND tag.value != ''
+                 GROUP BY tag.value
+                 ORDER BY COUNT(*) DESC",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let mood_distribution = stmt
+            .query_map(params![habit_id, start_date, end_date], |row| {
+                let count: i64 = row.get(1)?;
+                let completed: i64 = row.get(2)?;
+                Ok(MoodCount {
+                    mood: row.get(0)?,
+                    count,
+                    completed,
+                    completion_rate: rate(completed, count),
+                })
+            })
+            .map_err(|e| format!("Failed to query mood distribution: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect mood distribution: {}", e))?;
+
+        // Difficulty distribution
+        let mut stmt = db
+            .prepare(
+                "SELECT difficulty, COUNT(*)
+                 FROM habit_completions
+                 WHERE (?1 IS NULL OR habit_id = ?1) AND date BETWEEN ?2 AND ?3
+                   AND difficulty IS NOT NULL AND difficulty != ''
+                 GROUP BY difficulty
+                 ORDER BY COUNT(*) DESC",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let difficulty_distribution = stmt
+            .query_map(params![habit_id, start_date, end_date], |row| {
+                Ok(DifficultyCount {
+                    difficulty: row.get(0)?,
+                    count: row.get(1)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query difficulty distribution: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect difficulty distribution: {}", e))?;
+
+        // Average difficulty overall and per week
+        let average_difficulty: Option<f64> = db
+            .query_row(
+                &format!(
+                    "SELECT AVG({})
+                     FROM habit_completions
+                     WHERE (?1 IS NULL OR habit_id = ?1) AND date BETWEEN ?2 AND ?3",
+                    DIFFICULTY_SCORE_SQL
+                ),
+                params![habit_id, start_date, end_date],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Failed to query average difficulty: {}", e))?;
+
+        let mut stmt = db
+            .prepare(&format!(
+                "SELECT strftime('%Y-W%W', date) AS week, AVG({score}), COUNT({score})
+                 FROM habit_completions
+                 WHERE (?1 IS NULL OR habit_id = ?1) AND date BETWEEN ?2 AND ?3
+                 GROUP BY week
+                 HAVING COUNT({score}) > 0
+                 ORDER BY week ASC",
+                score = DIFFICULTY_SCORE_SQL
+            ))
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let difficulty_over_time = stmt
+            .query_map(params![habit_id, start_date, end_date], |row| {
+                Ok(DifficultyPoint {
+                    week: row.get(0)?,
+                    average_difficulty: row.get(1)?,
+                    entries: row.get(2)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query difficulty trend: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect difficulty trend: {}", e))?;
+
+        // Simple correlation hints: compare each mood's completion rate to the overall rate
+        let insights = mood_distribution
+            .iter()
+            .filter(|m| m.count >= 3)
+            .filter_map(|m| {
+                let diff = m.completion_rate - overall_completion_rate;
+                if diff.abs() < 0.1 {
+                    return None;
+                }
+                let direction = if diff > 0.0 { "higher" } else { "lower" };
+                Some(format!(
+                    "Completion rate is {} on days you logged '{}' mood ({:.0}% vs {:.0}% overall)",
+                    direction,
+                    m.mood,
+                    m.completion_rate * 100.0,
+                    overall_completion_rate * 100.0
+                ))
+            })
+            .collect();
+
+        Ok(MoodAnalytics {
+            mood_distribution,
+            difficulty_distribution,
+            average_difficulty,
+            difficulty_over_time,
+            overall_completion_rate,
+            insights,
+        })
+    })
+}
+
+/// Count habits per category with their combined completion rate over the last `recent_days` days
+#[tauri::command]
+pub async fn get_category_summary(
+    state: tauri::State<'_, AppState>,
+    recent_days: i32,
+) -> Result<Vec<CategorySummary>, String> {
+    let _timer = CommandTimer::start("get_category_summary");
+    let settings = load_settings_from_db(&state)?;
+    let today = configured_now(settings.as_ref()).date();
+    let window_start = today - Duration::days(recent_days.clamp(1, 3650) as i64 - 1);
+
+    with_connection(&state, |db| {
+        // Grouping walks idx_habits_category. Skipped days are left out like in get_mood_trends.
+        let mut stmt = db
+            .prepare(
+                "SELECT h.category, COUNT(DISTINCT h.id), COUNT(c.id), COALESCE(SUM(c.completed), 0)
+                 FROM habits h
+                 LEFT JOIN habit_completions c
+                    ON c.habit_id = h.id AND c.date BETWEEN ?1 AND ?2 AND c.skipped = 0
+                 GROUP BY h.category
+                 ORDER BY h.category ASC",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let summaries = stmt
+            .query_map(
+                params![
+                    window_start.format("%Y-%m-%d").to_string(),
+                    today.format("%Y-%m-%d").to_string()
+                ],
+                |row| {
+                    let logged_days: i64 = row.get(2)?;
+                    let completed_days: i64 = row.get(3)?;
+
+                    Ok(CategorySummary {
+                        category: row.get(0)?,
+                        habit_count: row.get(1)?,
+                        logged_days,
+                        completed_days,
+                        completion_rate: rate(completed_days, logged_days),
+                    })
+                },
+            )
+            .map_err(|e| format!("Failed to query category summary: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect category summary: {}", e))?;
+
+        Ok(summaries)
+    })
+}
+
+/// Get the most recent completed tasks, habit completions and finished goals as one timeline, newest first
+#[tauri::command]
+pub async fn get_recent_activity(
+    state: tauri::State<'_, AppState>,
+    limit: i32,
+) -> Result<Vec<ActivityItem>, String> {
+    let _timer = CommandTimer::start("get_recent_activity");
+    let limit = limit.clamp(1, 1000);
+
+    with_connection(&state, |db| {
+        // Tasks have no completion time, so updated_at stands in for when they were marked done
+        let mut stmt = db
+            .prepare(
+                "SELECT 'task', id, title, updated_at FROM tasks WHERE done = 1
+                 UNION ALL
+                 SELECT 'habit', c.id, h.name, c.completed_at
+                 FROM habit_completions c
+                 JOIN habits h ON h.id = c.habit_id
+                 WHERE c.completed = 1 AND c.completed_at IS NOT NULL
+                 UNION ALL
+                 SELECT 'goal', id, title, completed_at FROM goals WHERE completed_at IS NOT NULL
+                 ORDER BY 4 DESC
+                 LIMIT ?1",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let activity = stmt
+            .query_map(params![limit], |row| {
+                Ok(ActivityItem {
+                    activity_type: row.get(0)?,
+                    id: row.get(1)?,
+                    title: row.get(2)?,
+                    timestamp: row.get(3)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query recent activity: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect recent activity: {}", e))?;
+
+        Ok(activity)
+    })
+}
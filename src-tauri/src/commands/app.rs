@@ -1,12 +1,120 @@
+use crate::database::{schema_version, AppState, SCHEMA_VERSION};
 use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
 use tauri::{AppHandle, Manager};
 
+/// Base name of the log file written to the app log directory
+pub const LOG_FILE_NAME: &str = "loomra";
+
+/// How long the health check waits for a connection, so a stuck pool is reported quickly
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AppInfo {
     pub version: String,
     pub name: String,
     pub authors: String,
+    /// When this binary was built, as an RFC 3339 timestamp
+    pub build_timestamp: String,
+    /// Short hash of the commit it was built from, "unknown" outside a git checkout
+    pub git_commit: String,
+}
+
+/// Whether the backend is ready for the UI, and what's wrong if it isn't
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthStatus {
+    /// Every check passed
+    pub ready: bool,
+    /// A connection could be checked out and answered a query
+    pub database_reachable: bool,
+    /// Schema version stored in the database, None when it couldn't be read
+    pub schema_version: Option<i32>,
+    /// Schema version this build expects
+    pub expected_schema_version: i32,
+    pub settings_present: bool,
+    /// The first check that failed
+    pub error: Option<String>,
+}
+
+/// Bytes on disk used by the app, by kind of file
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageUsage {
+    pub total_bytes: u64,
+    /// Database files of every profile
+    pub database_bytes: u64,
+    /// Write-ahead log and shared memory files next to the databases
+    pub wal_bytes: u64,
+    pub backup_bytes: u64,
+    pub user_config_bytes: u64,
+    pub log_bytes: u64,
+    pub other_bytes: u64,
+}
+
+impl StorageUsage {
+    /// Count a file from the app data directory under its category
+    fn add_data_file(&mut self, path: &Path, size: u64) {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let in_backup_dir = path
+            .parent()
+            .and_then(Path::file_name)
+            .is_some_and(|dir| dir.to_string_lossy().to_lowercase().contains("backup"));
+
+        let category = if in_backup_dir || name.contains("backup") {
+            &mut self.backup_bytes
+        } else if name.ends_with(".db-wal") || name.ends_with(".db-shm") {
+            &mut self.wal_bytes
+        } else if name.ends_with(".db") {
+            &mut self.database_bytes
+        } else if name == "user-config.json" {
+            &mut self.user_config_bytes
+        } else {
+            &mut self.other_bytes
+        };
+
+        *category += size;
+        self.total_bytes += size;
+    }
+}
+
+/// Call `visit` with the path and size of every file under `dir`, leaving out `skip`.
+/// Entries that can't be read are logged and skipped.
+fn walk_files(dir: &Path, skip: Option<&Path>, visit: &mut impl FnMut(&Path, u64)) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::warn!("Skipping unreadable directory {}: {}", dir.display(), e);
+            return;
+        }
+    };
+
+    for entry in entries {
+        let path = match entry {
+            Ok(entry) => entry.path(),
+            Err(e) => {
+                log::warn!("Skipping unreadable entry in {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+
+        if skip == Some(path.as_path()) {
+            continue;
+        }
+
+        match fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.is_dir() => walk_files(&path, skip, visit),
+            Ok(metadata) if metadata.is_file() => visit(&path, metadata.len()),
+            Ok(_) => {}
+            Err(e) => log::warn!("Skipping unreadable file {}: {}", path.display(), e),
+        }
+    }
 }
 
 /// Get the application version
@@ -15,6 +123,16 @@ pub async fn get_app_version(app_handle: AppHandle) -> Result<String, String> {
     Ok(app_handle.package_info().version.to_string())
 }
 
+/// Build time recorded by build.rs, in seconds since the Unix epoch
+fn build_timestamp() -> String {
+    env!("LOOMRA_BUILD_TIMESTAMP")
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+        .map(|time| time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+        .unwrap_or_default()
+}
+
 /// Get comprehensive application information
 #[tauri::command]
 pub async fn get_app_info(app_handle: AppHandle) -> Result<AppInfo, String> {
@@ -24,9 +142,82 @@ pub async fn get_app_info(app_handle: AppHandle) -> Result<AppInfo, String> {
         version: package_info.version.to_string(),
         name: package_info.name.clone(),
         authors: package_info.authors.to_string(),
+        build_timestamp: build_timestamp(),
+        git_commit: env!("LOOMRA_GIT_COMMIT").to_string(),
     })
 }
 
+/// Check the database behind `state`, which is None when it failed to initialize
+pub(crate) fn check_health(state: Option<&AppState>) -> HealthStatus {
+    let mut status = HealthStatus {
+        ready: false,
+        database_reachable: false,
+        schema_version: None,
+        expected_schema_version: SCHEMA_VERSION,
+        settings_present: false,
+        error: None,
+    };
+
+    let Some(state) = state else {
+        status.error = Some("Database is not initialized".to_string());
+        return status;
+    };
+
+    let conn = match state.db.get_timeout(HEALTH_CHECK_TIMEOUT) {
+        Ok(conn) => conn,
+        Err(e) => {
+            status.error = Some(format!("Failed to get database connection: {}", e));
+            return status;
+        }
+    };
+
+    if let Err(e) = conn.query_row("SELECT 1", [], |row| row.get::<_, i32>(0)) {
+        status.error = Some(format!("Database is not responding: {}", e));
+        return status;
+    }
+    status.database_reachable = true;
+
+    match schema_version(&conn) {
+        Ok(version) => status.schema_version = Some(version),
+        Err(e) => {
+            status.error = Some(format!("Failed to read schema version: {}", e));
+            return status;
+        }
+    }
+    if status.schema_version != Some(SCHEMA_VERSION) {
+        status.error = Some(format!(
+            "Database schema is at version {}, expected {}",
+            status.schema_version.unwrap_or_default(),
+            SCHEMA_VERSION
+        ));
+        return status;
+    }
+
+    match conn.query_row("SELECT EXISTS(SELECT 1 FROM settings WHERE id = 1)", [], |row| {
+        row.get::<_, bool>(0)
+    }) {
+        Ok(present) => status.settings_present = present,
+        Err(e) => {
+            status.error = Some(format!("Failed to read settings: {}", e));
+            return status;
+        }
+    }
+    if !status.settings_present {
+        status.error = Some("Settings have not been created".to_string());
+        return status;
+    }
+
+    status.ready = true;
+    status
+}
+
+/// Check that the database is reachable, migrated and has settings, for the UI to poll at startup
+#[tauri::command]
+pub async fn health_check(app_handle: AppHandle) -> Result<HealthStatus, String> {
+    let state = app_handle.try_state::<AppState>();
+    Ok(check_health(state.as_deref()))
+}
+
 /// Get the application data directory path
 #[tauri::command]
 pub async fn get_app_data_dir(app_handle: AppHandle) -> Result<String, String> {
@@ -55,8 +246,69 @@ pub async fn get_app_log_dir(app_handle: AppHandle) -> Result<String, String> {
         .map(|s| s.to_string())
 }
 
+/// Read the last `lines` lines of the application log file
+#[tauri::command]
+pub async fn get_recent_logs(app_handle: AppHandle, lines: usize) -> Result<Vec<String>, String> {
+    let log_path = app_handle
+        .path()
+        .app_log_dir()
+        .map_err(|e| format!("Failed to get app log directory: {}", e))?
+        .join(format!("{}.log", LOG_FILE_NAME));
+
+    if !log_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let contents = fs::read_to_string(&log_path)
+        .map_err(|e| format!("Failed to read log file: {}", e))?;
+
+    let all_lines: Vec<&str> = contents.lines().collect();
+    let start = all_lines.len().saturating_sub(lines);
+
+    Ok(all_lines[start..].iter().map(|line| line.to_string()).collect())
+}
+
+/// Exit the application, e.g. after the user confirms a close prompt
+#[tauri::command]
+pub async fn quit_app(app_handle: AppHandle) -> Result<(), String> {
+    app_handle.exit(0);
+    Ok(())
+}
+
+/// Restart the application, e.g. to apply an installed update
+#[tauri::command]
+pub async fn restart_app(app_handle: AppHandle) -> Result<(), String> {
+    app_handle.restart()
+}
+
 /// Check if the application is running in development mode
 #[tauri::command]
 pub async fn is_dev_mode() -> Result<bool, String> {
     Ok(cfg!(debug_assertions))
+}
+
+/// Get the disk space used by the app data and log directories, broken down by kind of file
+#[tauri::command]
+pub async fn get_storage_usage(app_handle: AppHandle) -> Result<StorageUsage, String> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to get app data directory: {}", e))?;
+    let app_log_dir = app_handle
+        .path()
+        .app_log_dir()
+        .map_err(|e| format!("Failed to get app log directory: {}", e))?;
+
+    let mut usage = StorageUsage::default();
+
+    // The log directory can sit inside the data directory, so it's only counted once
+    walk_files(&app_data_dir, Some(&app_log_dir), &mut |path, size| {
+        usage.add_data_file(path, size)
+    });
+    walk_files(&app_log_dir, None, &mut |_, size| {
+        usage.log_bytes += size;
+        usage.total_bytes += size;
+    });
+
+    Ok(usage)
 }
\ No newline at end of file
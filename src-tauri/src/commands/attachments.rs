@@ -0,0 +1,214 @@
+use crate::database::{with_connection, AppState, DatabaseError};
+use crate::timing::CommandTimer;
+use crate::validation::{validate_attachment_path, validate_attachment_url};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+/// What an attachment belongs to
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentEntity {
+    Goal,
+    Task,
+}
+
+impl AttachmentEntity {
+    /// Value stored in attachments.entity_type
+    fn as_str(self) -> &'static str {
+        match self {
+            AttachmentEntity::Goal => "goal",
+            AttachmentEntity::Task => "task",
+        }
+    }
+
+    fn table(self) -> &'static str {
+        match self {
+            AttachmentEntity::Goal => "goals",
+            AttachmentEntity::Task => "tasks",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            AttachmentEntity::Goal => "Goal",
+            AttachmentEntity::Task => "Task",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentKind {
+    Url,
+    File,
+}
+
+impl AttachmentKind {
+    /// Value stored in attachments.kind
+    fn as_str(self) -> &'static str {
+        match self {
+            AttachmentKind::Url => "url",
+            AttachmentKind::File => "file",
+        }
+    }
+}
+
+/// A link or local file referenced from a goal or task
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Attachment {
+    pub id: i64,
+    /// "goal" or "task"
+    pub entity_type: String,
+    pub entity_id: String,
+    /// "url" or "file"
+    pub kind: String,
+    pub value: String,
+    pub label: String,
+    pub created_at: String,
+}
+
+impl Attachment {
+    /// Map a database row to an Attachment struct
+    pub(crate) fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            entity_type: row.get(1)?,
+            entity_id: row.get(2)?,
+            kind: row.get(3)?,
+            value: row.get(4)?,
+            label: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    }
+
+    /// Insert with its original id, skipping it if that id is taken
+    pub(crate) fn restore(&self, conn: &Connection) -> Result<(), String> {
+        conn.execute(
+            "INSERT OR IGNORE INTO attachments (id, entity_type, entity_id, kind, value, label, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                self.id,
+                self.entity_type,
+                self.entity_id,
+                self.kind,
+                self.value,
+                self.label,
+                self.created_at,
+            ],
+        )
+        .map_err(|e| format!("Failed to restore attachment: {}", e))?;
+
+        Ok(())
+    }
+}
+
+/// Get the attachments of a goal or task, oldest first
+pub(crate) fn get_attachments_for(
+    conn: &Connection,
+    entity_type: &str,
+    entity_id: &str,
+) -> Result<Vec<Attachment>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT * FROM attachments WHERE entity_type = ?1 AND entity_id = ?2
+             ORDER BY created_at ASC, id ASC",
+        )
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let attachments = stmt
+        .query_map(params![entity_type, entity_id], Attachment::from_row)
+        .map_err(|e| format!("Failed to query attachments: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect attachments: {}", e))?;
+
+    Ok(attachments)
+}
+
+/// Remove attachments whose goal or task no longer exists.
+/// Attachments have no foreign key, so deletes call this in the same transaction.
+pub(crate) fn delete_orphaned_attachments(conn: &Connection) -> Result<usize, String> {
+    conn.execute(
+        "DELETE FROM attachments
+         WHERE (entity_type = 'goal' AND entity_id NOT IN (SELECT id FROM goals))
+            OR (entity_type = 'task' AND entity_id NOT IN (SELECT id FROM tasks))",
+        [],
+    )
+    .map_err(|e| format!("Failed to delete attachments: {}", e))
+}
+
+/// Attach a URL or an absolute path to an existing file to a goal or task
+#[tauri::command]
+pub async fn add_attachment(
+    state: tauri::State<'_, AppState>,
+    entity_type: AttachmentEntity,
+    entity_id: String,
+    kind: AttachmentKind,
+    value: String,
+    label: Option<String>,
+) -> Result<Attachment, String> {
+    let value = value.trim().to_string();
+    match kind {
+        AttachmentKind::Url => validate_attachment_url(&value)?,
+        AttachmentKind::File => validate_attachment_path(&value)?,
+    }
+    let label = label.map(|label| label.trim().to_string()).unwrap_or_default();
+
+    with_connection(&state, |db| {
+        let exists = db
+            .query_row(
+                &format!("SELECT 1 FROM {} WHERE id = ?1", entity_type.table()),
+                params![entity_id],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to query {}: {}", entity_type.as_str(), e))?
+            .is_some();
+
+        if !exists {
+            return Err(DatabaseError::not_found(entity_type.label(), &entity_id).into());
+        }
+
+        db.execute(
+            "INSERT INTO attachments (entity_type, entity_id, kind, value, label)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![entity_type.as_str(), entity_id, kind.as_str(), value, label],
+        )
+        .map_err(|e| format!("Failed to add attachment: {}", e))?;
+
+        db.query_row(
+            "SELECT * FROM attachments WHERE id = ?1",
+            params![db.last_insert_rowid()],
+            Attachment::from_row,
+        )
+        .map_err(|e| format!("Failed to query attachment: {}", e))
+    })
+}
+
+/// Remove an attachment. Returns false if it didn't exist.
+#[tauri::command]
+pub async fn remove_attachment(
+    state: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<bool, String> {
+    with_connection(&state, |db| {
+        let rows_affected = db
+            .execute("DELETE FROM attachments WHERE id = ?1", params![id])
+            .map_err(|e| format!("Failed to remove attachment: {}", e))?;
+
+        Ok(rows_affected > 0)
+    })
+}
+
+/// Get the attachments of a goal or task, oldest first
+#[tauri::command]
+pub async fn get_attachments(
+    state: tauri::State<'_, AppState>,
+    entity_type: AttachmentEntity,
+    entity_id: String,
+) -> Result<Vec<Attachment>, String> {
+    let _timer = CommandTimer::start("get_attachments");
+    with_connection(&state, |db| {
+        get_attachments_for(db, entity_type.as_str(), &entity_id)
+    })
+}
@@ -1,6 +1,6 @@
 use argon2::{
     password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
+    Algorithm, Argon2, Params, Version,
 };
 
 /// Custom error type for authentication operations
@@ -72,6 +72,85 @@ pub async fn verify_password(
     Ok(is_valid)
 }
 
+/// Argon2id cost parameters for a new hash. Omitted fields use the library defaults,
+/// which is what `hash_password` hashes with.
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HashParams {
+    /// Memory size in KiB
+    pub memory_cost: Option<u32>,
+    /// Number of passes
+    pub time_cost: Option<u32>,
+    /// Degree of parallelism
+    pub parallelism: Option<u32>,
+}
+
+/// Most memory a hash may use, in KiB (256 MiB). Argon2 itself accepts up to about 4 GiB.
+pub(crate) const MAX_MEMORY_COST: u32 = 256 * 1024;
+
+/// Most passes a hash may take, so a bad value can't stall the app
+pub(crate) const MAX_TIME_COST: u32 = 16;
+
+/// An Argon2id hasher with the given memory cost (KiB), passes and parallelism.
+/// Costs above `MAX_MEMORY_COST` or `MAX_TIME_COST` are refused.
+pub(crate) fn argon2id(memory_cost: u32, time_cost: u32, parallelism: u32) -> Result<Argon2<'static>, String> {
+    if memory_cost > MAX_MEMORY_COST {
+        return Err(format!("Memory cost must be at most {} KiB", MAX_MEMORY_COST));
+    }
+    if time_cost > MAX_TIME_COST {
+        return Err(format!("Time cost must be at most {}", MAX_TIME_COST));
+    }
+
+    let params = Params::new(memory_cost, time_cost, parallelism, None)
+        .map_err(|e| format!("Invalid Argon2 parameters: {}", e))?;
+
+    Ok(Argon2::new(Algorithm::Argon2id, Version::V0x13, params))
+}
+
+impl HashParams {
+    fn hasher(self) -> Result<Argon2<'static>, AuthError> {
+        argon2id(
+            self.memory_cost.unwrap_or(Params::DEFAULT_M_COST),
+            self.time_cost.unwrap_or(Params::DEFAULT_T_COST),
+            self.parallelism.unwrap_or(Params::DEFAULT_P_COST),
+        )
+        .map_err(AuthError::HashingError)
+    }
+}
+
+/// Verify a password against its stored hash and hash it again with `new_params`,
+/// so hashes made with older costs can be upgraded. The caller stores the returned hash.
+#[tauri::command]
+pub async fn rehash_password(
+    password: String,
+    hashed_password: String,
+    new_params: Option<HashParams>,
+) -> Result<String, String> {
+    if password.is_empty() {
+        return Err("Password cannot be empty".to_string());
+    }
+
+    Ok(rehash(&password, &hashed_password, new_params.unwrap_or_default())?)
+}
+
+fn rehash(password: &str, hashed_password: &str, new_params: HashParams) -> Result<String, AuthError> {
+    let parsed_hash = PasswordHash::new(hashed_password)
+        .map_err(|_| AuthError::InvalidHashFormat)?;
+
+    // The stored hash carries its own parameters, so the default hasher can check it
+    Argon2::default()
+        .verify_password(password.as_bytes(), &parsed_hash)
+        .map_err(|_| AuthError::VerificationError("password does not match".to_string()))?;
+
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = new_params
+        .hasher()?
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| AuthError::HashingError(e.to_string()))?;
+
+    Ok(hash.to_string())
+}
+
 /// Check password strength and return feedback
 #[tauri::command]
 pub async fn check_password_strength(password: String) -> Result<PasswordStrength, String> {
@@ -139,4 +218,52 @@ pub struct PasswordStrength {
     pub strength: String,
     pub score: i32,
     pub feedback: Vec<String>,
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSWORD: &str = "correct horse battery";
+
+    fn default_hash() -> String {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(PASSWORD.as_bytes(), &salt)
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn rehash_verifies_and_records_new_params() {
+        let params = HashParams {
+            memory_cost: Some(8 * 1024),
+            time_cost: Some(3),
+            parallelism: Some(2),
+        };
+
+        let new_hash = rehash(PASSWORD, &default_hash(), params).unwrap();
+        let parsed = PasswordHash::new(&new_hash).unwrap();
+
+        assert!(Argon2::default().verify_password(PASSWORD.as_bytes(), &parsed).is_ok());
+        assert_eq!(parsed.algorithm.as_str(), "argon2id");
+        assert_eq!(parsed.params.get_decimal("m"), Some(8 * 1024));
+        assert_eq!(parsed.params.get_decimal("t"), Some(3));
+        assert_eq!(parsed.params.get_decimal("p"), Some(2));
+    }
+
+    #[test]
+    fn rehash_rejects_wrong_password() {
+        let result = rehash("not the password", &default_hash(), HashParams::default());
+        assert!(matches!(result, Err(AuthError::VerificationError(_))));
+    }
+
+    #[test]
+    fn rehash_caps_memory_cost() {
+        let params = HashParams {
+            memory_cost: Some(MAX_MEMORY_COST + 1),
+            ..HashParams::default()
+        };
+        assert!(matches!(rehash(PASSWORD, &default_hash(), params), Err(AuthError::HashingError(_))));
+    }
+}
@@ -0,0 +1,215 @@
+use crate::commands::settings::{configured_day_start, load_settings_from_db};
+use crate::database::{with_connection, AppState, DatabaseError};
+use crate::timing::CommandTimer;
+use crate::validation::ValidationError;
+use chrono::{DateTime, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+/// A stretch of focused work, optionally on a task.
+/// `ended_at` and `duration_seconds` stay empty while the session is running.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusSession {
+    pub id: i64,
+    /// Cleared when the task is deleted, so the time stays on record
+    pub task_id: Option<String>,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub duration_seconds: Option<i64>,
+}
+
+impl FocusSession {
+    /// Map a database row to a FocusSession struct
+    pub(crate) fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            started_at: row.get(2)?,
+            ended_at: row.get(3)?,
+            duration_seconds: row.get(4)?,
+        })
+    }
+
+    fn find(conn: &Connection, id: i64) -> Result<Option<Self>, String> {
+        conn.query_row(
+            "SELECT * FROM focus_sessions WHERE id = ?1",
+            params![id],
+            Self::from_row,
+        )
+        .optional()
+        .map_err(|e| format!("Failed to query focus session: {}", e))
+    }
+}
+
+fn session_timestamp() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Whole seconds between two RFC 3339 timestamps, never negative
+fn seconds_between(started_at: &str, ended_at: &str) -> Result<i64, String> {
+    let parse = |timestamp: &str| {
+        DateTime::parse_from_rfc3339(timestamp)
+            .map_err(|e| format!("Invalid focus session timestamp '{}': {}", timestamp, e))
+    };
+
+    let seconds = (parse(ended_at)? - parse(started_at)?).num_seconds();
+    Ok(seconds.max(0))
+}
+
+/// Clear the task of sessions whose task no longer exists, keeping their time on record.
+/// Deletes call this in the same transaction rather than rely on the foreign key action.
+pub(crate) fn detach_orphaned_focus_sessions(conn: &Connection) -> Result<usize, String> {
+    conn.execute(
+        "UPDATE focus_sessions SET task_id = NULL
+         WHERE task_id IS NOT NULL AND task_id NOT IN (SELECT id FROM tasks)",
+        [],
+    )
+    .map_err(|e| format!("Failed to update focus sessions: {}", e))
+}
+
+/// Ids of the sessions timed on a task
+pub(crate) fn get_focus_session_ids_for_task(conn: &Connection, task_id: &str) -> Result<Vec<i64>, String> {
+    let mut stmt = conn
+        .prepare_cached("SELECT id FROM focus_sessions WHERE task_id = ?1 ORDER BY id")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let ids = stmt
+        .query_map(params![task_id], |row| row.get(0))
+        .map_err(|e| format!("Failed to query focus sessions: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect focus sessions: {}", e))?;
+
+    Ok(ids)
+}
+
+/// Start timing a focus session, on `task_id` if given
+#[tauri::command]
+pub async fn start_focus_session(
+    state: tauri::State<'_, AppState>,
+    task_id: Option<String>,
+) -> Result<FocusSession, String> {
+    with_connection(&state, |db| {
+        if let Some(task_id) = &task_id {
+            let task_exists = db
+                .query_row("SELECT 1 FROM tasks WHERE id = ?1", params![task_id], |_| Ok(()))
+                .optional()
+                .map_err(|e| format!("Failed to query task: {}", e))?
+                .is_some();
+
+            if !task_exists {
+                return Err(DatabaseError::not_found("Task", task_id).into());
+            }
+        }
+
+        db.execute(
+            "INSERT INTO focus_sessions (task_id, started_at) VALUES (?1, ?2)",
+            params![task_id, session_timestamp()],
+        )
+        .map_err(|e| format!("Failed to start focus session: {}", e))?;
+
+        FocusSession::find(db, db.last_insert_rowid())?
+            .ok_or_else(|| "Failed to query focus session".to_string())
+    })
+}
+
+/// Stop a running focus session and record how long it lasted
+#[tauri::command]
+pub async fn end_focus_session(
+    state: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<FocusSession, String> {
+    with_connection(&state, |db| {
+        let session = FocusSession::find(db, id)?
+            .ok_or_else(|| DatabaseError::not_found("Focus session", &id.to_string()))?;
+
+        if session.ended_at.is_some() {
+            return Err(format!("Focus session {} has already ended", id));
+        }
+
+        let ended_at = session_timestamp();
+        let duration_seconds = seconds_between(&session.started_at, &ended_at)?;
+
+        db.execute(
+            "UPDATE focus_sessions SET ended_at = ?1, duration_seconds = ?2 WHERE id = ?3",
+            params![ended_at, duration_seconds, id],
+        )
+        .map_err(|e| format!("Failed to end focus session: {}", e))?;
+
+        Ok(FocusSession {
+            ended_at: Some(ended_at),
+            duration_seconds: Some(duration_seconds),
+            ..session
+        })
+    })
+}
+
+/// Get focus sessions, newest first, optionally only those on `task_id` and those
+/// started between two YYYY-MM-DD dates (inclusive) in the configured timezone
+#[tauri::command]
+pub async fn get_focus_sessions(
+    state: tauri::State<'_, AppState>,
+    task_id: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+) -> Result<Vec<FocusSession>, String> {
+    let _timer = CommandTimer::start("get_focus_sessions");
+    let parse = |date: &Option<String>| {
+        date.as_deref()
+            .map(|date| {
+                NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                    .map_err(|_| ValidationError::InvalidDate(date.to_string()))
+            })
+            .transpose()
+    };
+    let (start, end) = (parse(&start_date)?, parse(&end_date)?);
+    if let (Some(start), Some(end)) = (start, end) {
+        if start > end {
+            return Err("Start date must not be after end date".to_string());
+        }
+    }
+
+    // Day bounds as instants, the end being when the day after `end_date` begins
+    let settings = load_settings_from_db(&state)?;
+    let from = start.map(|start| configured_day_start(settings.as_ref(), start).to_rfc3339());
+    let until = end
+        .and_then(|end| end.succ_opt())
+        .map(|end| configured_day_start(settings.as_ref(), end).to_rfc3339());
+
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare(
+                "SELECT * FROM focus_sessions
+                 WHERE (?1 IS NULL OR task_id = ?1)
+                   AND (?2 IS NULL OR julianday(started_at) >= julianday(?2))
+                   AND (?3 IS NULL OR julianday(started_at) < julianday(?3))
+                 ORDER BY started_at DESC",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let sessions = stmt
+            .query_map(params![task_id, from, until], FocusSession::from_row)
+            .map_err(|e| format!("Failed to query focus sessions: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect focus sessions: {}", e))?;
+
+        Ok(sessions)
+    })
+}
+
+/// Total seconds of finished focus sessions on a task. A running session isn't counted.
+#[tauri::command]
+pub async fn get_task_time_spent(
+    state: tauri::State<'_, AppState>,
+    task_id: String,
+) -> Result<i64, String> {
+    let _timer = CommandTimer::start("get_task_time_spent");
+    with_connection(&state, |db| {
+        db.query_row(
+            "SELECT COALESCE(SUM(duration_seconds), 0) FROM focus_sessions WHERE task_id = ?1",
+            params![task_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to sum focus sessions: {}", e))
+    })
+}
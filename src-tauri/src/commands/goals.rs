@@ -1,6 +1,20 @@
-use crate::database::AppState;
-use rusqlite::{params, OptionalExtension, Row, Transaction};
+use crate::commands::attachments::delete_orphaned_attachments;
+use crate::commands::focus_sessions::detach_orphaned_focus_sessions;
+use crate::commands::idempotency::{find_idempotent_id, remember_idempotent_id};
+use crate::commands::settings::{configured_now, configured_row_limit, load_settings_from_db};
+use crate::commands::tasks::Task;
+use crate::commands::undo::{capture_goal_snapshot, DeletedGoals};
+use crate::database::{
+    distinct_categories, ensure_below_row_limit, rename_category, with_connection, AppState,
+    DatabaseError,
+};
+use crate::events::{emit_data_changed, Action, Entity};
+use crate::timing::CommandTimer;
+use crate::validation::{normalize_color, validate_icon};
+use rusqlite::{params, Connection, OptionalExtension, Row, Transaction};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tauri::AppHandle;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -17,18 +31,65 @@ pub struct Goal {
     pub deadline: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// Set by the backend when the status becomes "completed" and cleared when it changes again
+    #[serde(default)]
+    pub completed_at: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoalWithTasks {
+    pub goal: Goal,
+    pub tasks: Vec<Task>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum DeleteStrategy {
     Cascade,
+    // The frontend calls this strategy "unlink"
+    #[serde(alias = "unlink")]
     Nullify,
 }
 
+/// Field `get_goals_sorted` orders by
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum GoalSortKey {
+    Deadline,
+    Priority,
+    Title,
+    CreatedAt,
+}
+
+/// Ranks priorities low to high. Anything else ranks NULL so it can be sorted last.
+const PRIORITY_RANK_SQL: &str = "CASE priority WHEN 'low' THEN 1 WHEN 'medium' THEN 2 WHEN 'high' THEN 3 END";
+
+impl GoalSortKey {
+    /// ORDER BY clause for this key, built only from fixed SQL fragments.
+    /// Missing deadlines and unknown priorities come last in either direction, and ties go
+    /// to the newest goal.
+    fn order_by(self, ascending: bool) -> String {
+        let direction = if ascending { "ASC" } else { "DESC" };
+
+        match self {
+            GoalSortKey::Deadline => {
+                format!("deadline IS NULL, deadline {}, created_at DESC", direction)
+            }
+            GoalSortKey::Priority => format!(
+                "{rank} IS NULL, {rank} {direction}, created_at DESC",
+                rank = PRIORITY_RANK_SQL,
+                direction = direction
+            ),
+            GoalSortKey::Title => format!("title COLLATE NOCASE {}, created_at DESC", direction),
+            GoalSortKey::CreatedAt => format!("created_at {}", direction),
+        }
+    }
+}
+
 impl Goal {
     /// Map a database row to a Goal struct
-    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+    pub(crate) fn from_row(row: &Row) -> rusqlite::Result<Self> {
         Ok(Self {
             id: row.get(0)?,
             title: row.get(1)?,
@@ -42,101 +103,244 @@ impl Goal {
             deadline: row.get(9)?,
             created_at: row.get(10)?,
             updated_at: row.get(11)?,
+            completed_at: row.get(12)?,
         })
     }
+
+    /// Insert this goal as a new row
+    pub(crate) fn insert(&self, conn: &Connection) -> Result<(), String> {
+        conn.execute(
+            "INSERT INTO goals (
+                id, title, description, notes, category, priority,
+                status, color, icon, deadline, created_at, updated_at, completed_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                self.id,
+                self.title,
+                self.description,
+                self.notes,
+                self.category,
+                self.priority,
+                self.status,
+                self.color,
+                self.icon,
+                self.deadline,
+                self.created_at,
+                self.updated_at,
+                self.completed_at,
+            ],
+        )
+        .map_err(|e| format!("Failed to create goal: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Look up a goal by id
+    pub(crate) fn find(conn: &Connection, id: &str) -> Result<Option<Self>, String> {
+        conn.query_row("SELECT * FROM goals WHERE id = ?1", params![id], Self::from_row)
+            .optional()
+            .map_err(|e| format!("Failed to query goal: {}", e))
+    }
 }
 
+const COMPLETED_STATUS: &str = "completed";
+
+fn completion_timestamp() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Create a goal. Retrying with the same `idempotency_key` within a day returns the
+/// goal the first call created instead of adding another.
 #[tauri::command]
 pub async fn create_goal(
     state: tauri::State<'_, AppState>,
-    goal: Goal,
+    app_handle: AppHandle,
+    mut goal: Goal,
+    idempotency_key: Option<String>,
 ) -> Result<Goal, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    db.execute(
-        "INSERT INTO goals (
-            id, title, description, notes, category, priority,
-            status, color, icon, deadline, created_at, updated_at
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
-        params![
-            goal.id,
-            goal.title,
-            goal.description,
-            goal.notes,
-            goal.category,
-            goal.priority,
-            goal.status,
-            goal.color,
-            goal.icon,
-            goal.deadline,
-            goal.created_at,
-            goal.updated_at,
-        ],
-    )
-    .map_err(|e| format!("Failed to create goal: {}", e))?;
-
-    Ok(goal)
+    goal.color = normalize_color(&goal.color)?;
+    validate_icon(&goal.icon)?;
+
+    goal.completed_at = (goal.status == COMPLETED_STATUS).then(completion_timestamp);
+    let row_limit = configured_row_limit(load_settings_from_db(&state)?.as_ref());
+    let key = idempotency_key.as_deref();
+
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        if let Some(id) = find_idempotent_id(&tx, key, "goal")? {
+            if let Some(existing) = Goal::find(&tx, &id)? {
+                return Ok(existing);
+            }
+        }
+
+        ensure_below_row_limit(&tx, "goals", row_limit)?;
+        goal.insert(&tx)?;
+        remember_idempotent_id(&tx, key, "goal", &goal.id)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        emit_data_changed(&app_handle, Entity::Goal, Action::Create, &goal.id);
+
+        Ok(goal)
+    })
+}
+
+/// Create a goal together with its tasks, which are all assigned to the goal.
+/// Nothing is saved unless every row is. Retrying with the same `idempotency_key`
+/// within a day returns the goal the first call created and its current tasks.
+#[tauri::command]
+pub async fn create_goal_with_tasks(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    mut goal: Goal,
+    mut tasks: Vec<Task>,
+    idempotency_key: Option<String>,
+) -> Result<GoalWithTasks, String> {
+    goal.color = normalize_color(&goal.color)?;
+    validate_icon(&goal.icon)?;
+
+    goal.completed_at = (goal.status == COMPLETED_STATUS).then(completion_timestamp);
+    let row_limit = configured_row_limit(load_settings_from_db(&state)?.as_ref());
+    let key = idempotency_key.as_deref();
+
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        if let Some(id) = find_idempotent_id(&tx, key, "goal_with_tasks")? {
+            if let Some(existing) = load_goal_with_tasks(&tx, &id)? {
+                return Ok(existing);
+            }
+        }
+
+        // Subtasks may come before their parent task in the list
+        tx.pragma_update(None, "defer_foreign_keys", "ON")
+            .map_err(|e| format!("Failed to defer foreign keys: {}", e))?;
+
+        ensure_below_row_limit(&tx, "goals", row_limit)?;
+        goal.insert(&tx)?;
+
+        for task in &mut tasks {
+            task.goal_id = Some(goal.id.clone());
+            ensure_below_row_limit(&tx, "tasks", row_limit)?;
+            task.insert(&tx)?;
+        }
+
+        remember_idempotent_id(&tx, key, "goal_with_tasks", &goal.id)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        emit_data_changed(&app_handle, Entity::Goal, Action::Create, &goal.id);
+        for task in &tasks {
+            emit_data_changed(&app_handle, Entity::Task, Action::Create, &task.id);
+        }
+
+        Ok(GoalWithTasks { goal, tasks })
+    })
 }
 
 #[tauri::command]
 pub async fn update_goal(
     state: tauri::State<'_, AppState>,
-    goal: Goal,
+    app_handle: AppHandle,
+    mut goal: Goal,
+    expected_updated_at: Option<String>,
 ) -> Result<Goal, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    let rows = db.execute(
-        "UPDATE goals SET
-            title = ?1, description = ?2, notes = ?3, category = ?4,
-            priority = ?5, status = ?6, color = ?7, icon = ?8,
-            deadline = ?9, updated_at = ?10
-        WHERE id = ?11",
-        params![
-            goal.title,
-            goal.description,
-            goal.notes,
-            goal.category,
-            goal.priority,
-            goal.status,
-            goal.color,
-            goal.icon,
-            goal.deadline,
-            goal.updated_at,
-            goal.id,
-        ],
-    )
-    .map_err(|e| format!("Failed to update goal: {}", e))?;
-
-    if rows == 0 {
-        return Err(format!("Goal with id '{}' not found", goal.id));
-    }
-
-    Ok(goal)
+    goal.color = normalize_color(&goal.color)?;
+    validate_icon(&goal.icon)?;
+
+    with_connection(&state, |db| {
+        // Staying completed keeps the original timestamp, any other status clears it
+        goal.completed_at = db
+            .query_row(
+                "UPDATE goals SET
+                    title = ?1, description = ?2, notes = ?3, category = ?4,
+                    priority = ?5, status = ?6, color = ?7, icon = ?8,
+                    deadline = ?9, updated_at = ?10,
+                    completed_at = CASE
+                        WHEN ?6 != ?12 THEN NULL
+                        WHEN status = ?12 THEN COALESCE(completed_at, ?13)
+                        ELSE ?13
+                    END
+                WHERE id = ?11 AND (?14 IS NULL OR updated_at = ?14)
+                RETURNING completed_at",
+                params![
+                    goal.title,
+                    goal.description,
+                    goal.notes,
+                    goal.category,
+                    goal.priority,
+                    goal.status,
+                    goal.color,
+                    goal.icon,
+                    goal.deadline,
+                    goal.updated_at,
+                    goal.id,
+                    COMPLETED_STATUS,
+                    completion_timestamp(),
+                    expected_updated_at,
+                ],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to update goal: {}", e))?
+            .ok_or_else(|| {
+                let expected = expected_updated_at.as_deref();
+                DatabaseError::update_missed(db, "goals", "Goal", &goal.id, expected)
+            })?;
+
+        emit_data_changed(&app_handle, Entity::Goal, Action::Update, &goal.id);
+
+        Ok(goal)
+    })
 }
 
+/// Delete a goal. Until the app restarts, `undo_last_delete` can bring it back.
 #[tauri::command]
 pub async fn delete_goal(
     state: tauri::State<'_, AppState>,
+    deleted_goals: tauri::State<'_, DeletedGoals>,
+    app_handle: AppHandle,
     id: String,
-    delete_strategy: Option<String>,
+    delete_strategy: Option<DeleteStrategy>,
 ) -> Result<bool, String> {
-    let mut db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    with_connection(&state, |db| {
+        let strategy = delete_strategy.unwrap_or(DeleteStrategy::Nullify);
 
-    // Parse delete strategy
-    let strategy = match delete_strategy.as_deref() {
-        Some("cascade") => DeleteStrategy::Cascade,
-        _ => DeleteStrategy::Nullify,
-    };
+        // Use transaction for atomic operations
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        // Kept for undo_last_delete
+        let snapshot = capture_goal_snapshot(&tx, &id, strategy)?;
+        let deleted = delete_goal_tx(&tx, &id, strategy)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        if deleted {
+            if let Some(snapshot) = snapshot {
+                deleted_goals.push(snapshot);
+            }
+            emit_data_changed(&app_handle, Entity::Goal, Action::Delete, &id);
+        }
 
-    // Use transaction for atomic operations
-    let tx = db.transaction()
-        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+        Ok(deleted)
+    })
+}
 
+/// Delete a goal and handle its tasks and habit links (within transaction)
+pub(crate) fn delete_goal_tx(
+    tx: &Transaction,
+    id: &str,
+    strategy: DeleteStrategy,
+) -> Result<bool, String> {
     // Remove goal from habits' linked_goals
-    update_habit_linked_goals_tx(&tx, &id)?;
+    relink_habit_goals_tx(tx, id, None)?;
 
     // Handle associated tasks based on strategy
     match strategy {
@@ -155,86 +359,368 @@ pub async fn delete_goal(
         .execute("DELETE FROM goals WHERE id = ?1", params![id])
         .map_err(|e| format!("Failed to delete goal: {}", e))?;
 
-    tx.commit()
-        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    // The goal's attachments, plus those of any tasks that went with it
+    delete_orphaned_attachments(tx)?;
+    detach_orphaned_focus_sessions(tx)?;
 
     Ok(rows_affected > 0)
 }
 
-/// Remove a goal ID from all habits' linked_goals arrays (within transaction)
-fn update_habit_linked_goals_tx(
-    tx: &Transaction,
+/// Calculate goal progress as the rounded percentage of completed actionable tasks.
+/// Parent tasks are excluded so only leaf tasks count, matching the frontend.
+pub(crate) fn calculate_goal_progress(conn: &Connection, goal_id: &str) -> Result<i32, String> {
+    let (total, completed): (i64, i64) = conn
+        .query_row(
+            "SELECT COUNT(*), COALESCE(SUM(t.done), 0)
+             FROM tasks t
+             WHERE t.goal_id = ?1
+               AND NOT EXISTS (SELECT 1 FROM tasks c WHERE c.parent_task_id = t.id)",
+            params![goal_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("Failed to calculate goal progress: {}", e))?;
+
+    if total == 0 {
+        return Ok(0);
+    }
+
+    Ok(((completed as f64 / total as f64) * 100.0).round() as i32)
+}
+
+/// Recursive CTE selecting the ids of a goal's tasks and all of their subtasks,
+/// i.e. every row a cascade delete removes
+pub(crate) const GOAL_TASK_TREE_CTE: &str = "WITH RECURSIVE task_tree(id) AS (
+        SELECT id FROM tasks WHERE goal_id = ?1
+        UNION
+        SELECT t.id FROM tasks t INNER JOIN task_tree tt ON t.parent_task_id = tt.id
+    )";
+
+/// A habit whose linked_goals array references a given goal
+#[derive(Debug, Clone)]
+pub(crate) struct LinkedHabit {
+    pub id: String,
+    pub name: String,
+    pub linked_goals: Vec<String>,
+}
+
+/// Find all habits whose linked_goals array contains the goal ID, via the habit_goals index
+pub(crate) fn find_habits_linked_to_goal(
+    conn: &Connection,
     goal_id: &str,
-) -> Result<(), String> {
-    let mut stmt = tx
-        .prepare("SELECT id, linked_goals FROM habits")
+) -> Result<Vec<LinkedHabit>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT h.id, h.name, h.linked_goals
+             FROM habit_goals hg
+             INNER JOIN habits h ON h.id = hg.habit_id
+             WHERE hg.goal_id = ?1
+             ORDER BY h.created_at DESC",
+        )
         .map_err(|e| format!("Failed to query habits: {}", e))?;
 
-    let habits: Vec<(String, String)> = stmt
-        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+    let habits = stmt
+        .query_map(params![goal_id], |row| {
+            let linked_goals_str: String = row.get(2)?;
+            Ok(LinkedHabit {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                linked_goals: serde_json::from_str(&linked_goals_str).unwrap_or_default(),
+            })
+        })
         .map_err(|e| format!("Failed to map habit rows: {}", e))?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| format!("Failed to collect habits: {}", e))?;
 
-    drop(stmt); // Drop the statement before executing updates
-
-    for (habit_id, linked_goals_str) in habits {
-        if let Ok(mut linked_goals) = serde_json::from_str::<Vec<String>>(&linked_goals_str) {
-            if linked_goals.contains(&goal_id.to_string()) {
-                linked_goals.retain(|g| g != goal_id);
-                let updated_json = serde_json::to_string(&linked_goals)
-                    .map_err(|e| format!("Failed to serialize linked goals: {}", e))?;
+    Ok(habits)
+}
 
-                tx.execute(
-                    "UPDATE habits SET linked_goals = ?1 WHERE id = ?2",
-                    params![updated_json, habit_id],
-                )
-                .map_err(|e| format!("Failed to update habit linked goals: {}", e))?;
+/// Remove a goal ID from all habits' linked_goals arrays, optionally putting a replacement
+/// goal in its place (within transaction). Returns the number of habits updated.
+/// The habit_goals triggers update the join table from the rewritten arrays.
+fn relink_habit_goals_tx(
+    tx: &Transaction,
+    goal_id: &str,
+    replacement: Option<&str>,
+) -> Result<usize, String> {
+    let habits = find_habits_linked_to_goal(tx, goal_id)?;
+    let updated = habits.len();
+
+    for habit in habits {
+        let mut linked_goals = habit.linked_goals;
+        let position = linked_goals.iter().position(|g| g == goal_id);
+        linked_goals.retain(|g| g != goal_id);
+
+        if let (Some(replacement), Some(position)) = (replacement, position) {
+            if !linked_goals.iter().any(|g| g == replacement) {
+                linked_goals.insert(position.min(linked_goals.len()), replacement.to_string());
             }
         }
+
+        let updated_json = serde_json::to_string(&linked_goals)
+            .map_err(|e| format!("Failed to serialize linked goals: {}", e))?;
+
+        tx.execute(
+            "UPDATE habits SET linked_goals = ?1 WHERE id = ?2",
+            params![updated_json, habit.id],
+        )
+        .map_err(|e| format!("Failed to update habit linked goals: {}", e))?;
     }
 
-    Ok(())
+    Ok(updated)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnlinkedHabit {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletionPreview {
+    pub goal_exists: bool,
+    pub tasks_deleted: i64,
+    pub tasks_nullified: i64,
+    pub unlinked_habits: Vec<UnlinkedHabit>,
+}
+
+/// Report what deleting a goal with the given strategy would change, without mutating anything
+#[tauri::command]
+pub async fn preview_goal_deletion(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    delete_strategy: Option<DeleteStrategy>,
+) -> Result<DeletionPreview, String> {
+    with_connection(&state, |db| {
+        let strategy = delete_strategy.unwrap_or(DeleteStrategy::Nullify);
+
+        let goal_exists = db
+            .query_row("SELECT 1 FROM goals WHERE id = ?1", params![id], |_| Ok(()))
+            .optional()
+            .map_err(|e| format!("Failed to query goal: {}", e))?
+            .is_some();
+
+        let (tasks_deleted, tasks_nullified) = match strategy {
+            DeleteStrategy::Cascade => {
+                let count: i64 = db
+                    .query_row(
+                        &format!("{} SELECT COUNT(*) FROM task_tree", GOAL_TASK_TREE_CTE),
+                        params![id],
+                        |row| row.get(0),
+                    )
+                    .map_err(|e| format!("Failed to count associated tasks: {}", e))?;
+                (count, 0)
+            }
+            DeleteStrategy::Nullify => {
+                let count: i64 = db
+                    .query_row(
+                        "SELECT COUNT(*) FROM tasks WHERE goal_id = ?1",
+                        params![id],
+                        |row| row.get(0),
+                    )
+                    .map_err(|e| format!("Failed to count associated tasks: {}", e))?;
+                (0, count)
+            }
+        };
+
+        let unlinked_habits = find_habits_linked_to_goal(db, &id)?
+            .into_iter()
+            .map(|habit| UnlinkedHabit { id: habit.id, name: habit.name })
+            .collect();
+
+        Ok(DeletionPreview {
+            goal_exists,
+            tasks_deleted,
+            tasks_nullified,
+            unlinked_habits,
+        })
+    })
 }
 
 #[tauri::command]
 pub async fn get_all_goals(
     state: tauri::State<'_, AppState>,
 ) -> Result<Vec<Goal>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    let _timer = CommandTimer::start("get_all_goals");
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare("SELECT * FROM goals ORDER BY created_at DESC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let goals = stmt
+            .query_map([], Goal::from_row)
+            .map_err(|e| format!("Failed to query goals: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect goals: {}", e))?;
+
+        Ok(goals)
+    })
+}
+
+/// Get all goals ordered by deadline, priority (low to high when ascending), title or creation time
+#[tauri::command]
+pub async fn get_goals_sorted(
+    state: tauri::State<'_, AppState>,
+    sort_by: GoalSortKey,
+    ascending: bool,
+) -> Result<Vec<Goal>, String> {
+    let _timer = CommandTimer::start("get_goals_sorted");
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare(&format!("SELECT * FROM goals ORDER BY {}", sort_by.order_by(ascending)))
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let goals = stmt
+            .query_map([], Goal::from_row)
+            .map_err(|e| format!("Failed to query goals: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect goals: {}", e))?;
+
+        Ok(goals)
+    })
+}
 
-    let mut stmt = db
-        .prepare("SELECT * FROM goals ORDER BY created_at DESC")
+#[tauri::command]
+pub async fn get_goal_by_id(
+    state: tauri::State<'_, AppState>,
+    id: String,
+) -> Result<Option<Goal>, String> {
+    let _timer = CommandTimer::start("get_goal_by_id");
+    with_connection(&state, |db| Goal::find(db, &id))
+}
+
+fn load_goal_with_tasks(conn: &Connection, id: &str) -> Result<Option<GoalWithTasks>, String> {
+    let goal = match Goal::find(conn, id)? {
+        Some(goal) => goal,
+        None => return Ok(None),
+    };
+
+    // Matches idx_tasks_goal_done (goal_id, done, due_date)
+    let mut stmt = conn
+        .prepare("SELECT * FROM tasks WHERE goal_id = ?1 ORDER BY done ASC, due_date ASC")
         .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    let goals = stmt
-        .query_map([], Goal::from_row)
-        .map_err(|e| format!("Failed to query goals: {}", e))?
+    let tasks = stmt
+        .query_map(params![id], Task::from_row)
+        .map_err(|e| format!("Failed to query tasks: {}", e))?
         .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to collect goals: {}", e))?;
+        .map_err(|e| format!("Failed to collect tasks: {}", e))?;
 
-    Ok(goals)
+    Ok(Some(GoalWithTasks { goal, tasks }))
 }
 
+/// Get a goal and its tasks on a single connection
 #[tauri::command]
-pub async fn get_goal_by_id(
+pub async fn get_goal_with_tasks(
     state: tauri::State<'_, AppState>,
     id: String,
-) -> Result<Option<Goal>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+) -> Result<Option<GoalWithTasks>, String> {
+    let _timer = CommandTimer::start("get_goal_with_tasks");
+    with_connection(&state, |db| load_goal_with_tasks(db, &id))
+}
 
-    let goal = db
-        .query_row(
-            "SELECT * FROM goals WHERE id = ?1",
-            params![id],
-            Goal::from_row,
-        )
-        .optional()
-        .map_err(|e| format!("Failed to query goal: {}", e))?;
+/// Warning window used before settings exist, matching the frontend default
+pub(crate) const DEFAULT_DEADLINE_WARNING_DAYS: u32 = 30;
 
-    Ok(goal)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GoalStats {
+    pub total: i64,
+    pub by_status: BTreeMap<String, i64>,
+    pub by_priority: BTreeMap<String, i64>,
+    /// Unfinished goals due within the deadline warning window, including today
+    pub upcoming_deadlines: i64,
+    /// Unfinished goals whose deadline has passed
+    pub overdue: i64,
+}
+
+/// Count goals grouped by a column
+fn count_goals_by(conn: &Connection, column: &str) -> Result<BTreeMap<String, i64>, String> {
+    let mut stmt = conn
+        .prepare(&format!("SELECT {column}, COUNT(*) FROM goals GROUP BY {column}", column = column))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let counts = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+        .map_err(|e| format!("Failed to count goals: {}", e))?
+        .collect::<Result<BTreeMap<_, _>, _>>()
+        .map_err(|e| format!("Failed to collect goal counts: {}", e))?;
+
+    Ok(counts)
+}
+
+/// Get goal counts for the dashboard without loading every goal
+#[tauri::command]
+pub async fn get_goal_statistics(
+    state: tauri::State<'_, AppState>,
+) -> Result<GoalStats, String> {
+    let _timer = CommandTimer::start("get_goal_statistics");
+    let settings = load_settings_from_db(&state)?;
+    let warning_days = settings
+        .as_ref()
+        .map(|settings| settings.goals.deadline_warning_days)
+        .unwrap_or(DEFAULT_DEADLINE_WARNING_DAYS);
+    let today = configured_now(settings.as_ref()).date();
+
+    with_connection(&state, |db| {
+        // Served by idx_goals_status and idx_goals_priority
+        let by_status = count_goals_by(db, "status")?;
+        let by_priority = count_goals_by(db, "priority")?;
+        let total = by_status.values().sum();
+
+        let warning_end = today + chrono::Duration::days(warning_days as i64);
+
+        // Deadlines may be full ISO timestamps, so compare local calendar days like the frontend does
+        let (overdue, upcoming_deadlines): (i64, i64) = db
+            .query_row(
+                "SELECT
+                    COALESCE(SUM(date(deadline, 'localtime') < ?1), 0),
+                    COALESCE(SUM(date(deadline, 'localtime') BETWEEN ?1 AND ?2), 0)
+                 FROM goals
+                 WHERE deadline IS NOT NULL AND status != 'completed'",
+                params![
+                    today.format("%Y-%m-%d").to_string(),
+                    warning_end.format("%Y-%m-%d").to_string(),
+                ],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| format!("Failed to count goal deadlines: {}", e))?;
+
+        Ok(GoalStats {
+            total,
+            by_status,
+            by_priority,
+            upcoming_deadlines,
+            overdue,
+        })
+    })
+}
+
+/// Rename a goal category on every goal that uses it, returning how many changed
+#[tauri::command]
+pub async fn rename_goal_category(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    old: String,
+    new: String,
+) -> Result<usize, String> {
+    with_connection(&state, |db| {
+        let ids = rename_category(db, "goals", &old, &new)?;
+
+        for id in &ids {
+            emit_data_changed(&app_handle, Entity::Goal, Action::Update, id);
+        }
+
+        Ok(ids.len())
+    })
+}
+
+/// Get every category used by a goal, sorted, for the category picker to offer
+#[tauri::command]
+pub async fn get_goal_categories(state: tauri::State<'_, AppState>) -> Result<Vec<String>, String> {
+    let _timer = CommandTimer::start("get_goal_categories");
+    with_connection(&state, |db| distinct_categories(db, "goals"))
 }
 
 #[tauri::command]
@@ -242,18 +728,213 @@ pub async fn get_goals_by_status(
     state: tauri::State<'_, AppState>,
     status: String,
 ) -> Result<Vec<Goal>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    let _timer = CommandTimer::start("get_goals_by_status");
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare("SELECT * FROM goals WHERE status = ?1 ORDER BY created_at DESC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let goals = stmt
+            .query_map(params![status], Goal::from_row)
+            .map_err(|e| format!("Failed to query goals: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect goals: {}", e))?;
+
+        Ok(goals)
+    })
+}
+
+/// Get goals completed between two YYYY-MM-DD dates (inclusive, local time), most recent first
+#[tauri::command]
+pub async fn get_goals_completed_between(
+    state: tauri::State<'_, AppState>,
+    start: String,
+    end: String,
+) -> Result<Vec<Goal>, String> {
+    let _timer = CommandTimer::start("get_goals_completed_between");
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare(
+                "SELECT * FROM goals
+                 WHERE completed_at IS NOT NULL AND date(completed_at, 'localtime') BETWEEN ?1 AND ?2
+                 ORDER BY completed_at DESC",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let goals = stmt
+            .query_map(params![start, end], Goal::from_row)
+            .map_err(|e| format!("Failed to query goals: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect goals: {}", e))?;
+
+        Ok(goals)
+    })
+}
 
-    let mut stmt = db
-        .prepare("SELECT * FROM goals WHERE status = ?1 ORDER BY created_at DESC")
-        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+/// Get active goals not updated in the last `days` days and with no recently completed tasks,
+/// oldest first
+#[tauri::command]
+pub async fn get_stale_goals(
+    state: tauri::State<'_, AppState>,
+    days: i32,
+) -> Result<Vec<Goal>, String> {
+    let _timer = CommandTimer::start("get_stale_goals");
+    if days < 0 {
+        return Err("Days must not be negative".to_string());
+    }
 
-    let goals = stmt
-        .query_map(params![status], Goal::from_row)
-        .map_err(|e| format!("Failed to query goals: {}", e))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to collect goals: {}", e))?;
+    with_connection(&state, |db| {
+        // Subtasks may only carry a parent_task_id, so resolve every task to its goal first
+        let mut stmt = db
+            .prepare(
+                "WITH RECURSIVE goal_tasks(id, goal_id, done, updated_at) AS (
+                    SELECT id, goal_id, done, updated_at FROM tasks WHERE goal_id IS NOT NULL
+                    UNION ALL
+                    SELECT t.id, gt.goal_id, t.done, t.updated_at
+                    FROM tasks t
+                    INNER JOIN goal_tasks gt ON t.parent_task_id = gt.id
+                    WHERE t.goal_id IS NULL
+                )
+                SELECT g.* FROM goals g
+                WHERE g.status = 'active'
+                  AND datetime(g.updated_at) < datetime('now', ?1)
+                  AND NOT EXISTS (
+                    SELECT 1 FROM goal_tasks gt
+                    WHERE gt.goal_id = g.id
+                      AND gt.done = 1
+                      AND datetime(gt.updated_at) >= datetime('now', ?1)
+                  )
+                ORDER BY datetime(g.updated_at) ASC",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let goals = stmt
+            .query_map(params![format!("-{} days", days)], Goal::from_row)
+            .map_err(|e| format!("Failed to query stale goals: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect stale goals: {}", e))?;
+
+        Ok(goals)
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateGroup {
+    pub normalized_title: String,
+    pub goals: Vec<Goal>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MergeSummary {
+    pub goals_merged: usize,
+    pub tasks_moved: usize,
+    pub habits_relinked: usize,
+}
+
+/// Find goals sharing a title after trimming and lowercasing, oldest goal first in each group
+#[tauri::command]
+pub async fn find_duplicate_goals(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<DuplicateGroup>, String> {
+    let _timer = CommandTimer::start("find_duplicate_goals");
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare("SELECT * FROM goals ORDER BY created_at ASC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let goals = stmt
+            .query_map([], Goal::from_row)
+            .map_err(|e| format!("Failed to query goals: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect goals: {}", e))?;
+
+        let mut groups: BTreeMap<String, Vec<Goal>> = BTreeMap::new();
+        for goal in goals {
+            groups
+                .entry(goal.title.trim().to_lowercase())
+                .or_default()
+                .push(goal);
+        }
 
-    Ok(goals)
-}
\ No newline at end of file
+        Ok(groups
+            .into_iter()
+            .filter(|(_, goals)| goals.len() > 1)
+            .map(|(normalized_title, goals)| DuplicateGroup { normalized_title, goals })
+            .collect())
+    })
+}
+
+/// Merge goals into `keep_id`: move their tasks and habit links over, then delete them
+#[tauri::command]
+pub async fn merge_goals(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    keep_id: String,
+    merge_ids: Vec<String>,
+) -> Result<MergeSummary, String> {
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        let keep_exists = tx
+            .query_row("SELECT 1 FROM goals WHERE id = ?1", params![keep_id], |_| Ok(()))
+            .optional()
+            .map_err(|e| format!("Failed to query goal: {}", e))?
+            .is_some();
+
+        if !keep_exists {
+            return Err(DatabaseError::not_found("Goal", &keep_id).into());
+        }
+
+        let mut summary = MergeSummary {
+            goals_merged: 0,
+            tasks_moved: 0,
+            habits_relinked: 0,
+        };
+
+        let mut seen = Vec::new();
+        for merge_id in merge_ids {
+            if merge_id == keep_id || seen.contains(&merge_id) {
+                continue;
+            }
+
+            summary.tasks_moved += tx
+                .execute(
+                    "UPDATE tasks SET goal_id = ?1 WHERE goal_id = ?2",
+                    params![keep_id, merge_id],
+                )
+                .map_err(|e| format!("Failed to move tasks: {}", e))?;
+
+            summary.habits_relinked += relink_habit_goals_tx(&tx, &merge_id, Some(&keep_id))?;
+
+            tx.execute(
+                "UPDATE attachments SET entity_id = ?1 WHERE entity_type = 'goal' AND entity_id = ?2",
+                params![keep_id, merge_id],
+            )
+            .map_err(|e| format!("Failed to move attachments: {}", e))?;
+
+            let rows_affected = tx
+                .execute("DELETE FROM goals WHERE id = ?1", params![merge_id])
+                .map_err(|e| format!("Failed to delete goal: {}", e))?;
+
+            if rows_affected == 0 {
+                return Err(DatabaseError::not_found("Goal", &merge_id).into());
+            }
+
+            summary.goals_merged += 1;
+            seen.push(merge_id);
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        for merged_id in &seen {
+            emit_data_changed(&app_handle, Entity::Goal, Action::Delete, merged_id);
+        }
+        emit_data_changed(&app_handle, Entity::Goal, Action::Update, &keep_id);
+
+        Ok(summary)
+    })
+}
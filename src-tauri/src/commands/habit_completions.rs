@@ -1,6 +1,19 @@
-use crate::database::AppState;
-use rusqlite::{params, OptionalExtension, Row};
-use serde::{Deserialize, Serialize};
+use crate::commands::habit_frequency_history::{frequency_on, get_frequency_history};
+use crate::commands::habit_pauses::get_pauses_for_habit;
+use crate::commands::habit_schedule::is_scheduled_with;
+use crate::commands::habits::Habit;
+use crate::commands::settings::{configured_now, configured_streak_grace_days, load_settings_from_db};
+use crate::database::{with_connection, AppState, DatabaseError};
+use crate::events::{emit_data_changed, Action, Entity};
+use crate::timing::CommandTimer;
+use crate::tray;
+use crate::units;
+use crate::validation::{validate_completion_date, ValidationError};
+use chrono::{Duration, NaiveDate};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Row, Transaction, TransactionBehavior};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::HashMap;
+use tauri::AppHandle;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -13,16 +26,87 @@ pub struct HabitCompletion {
     pub target_amount: f64,
     pub completed_at: Option<String>,
     pub note: String,
-    pub mood: Option<String>,
+    /// Mood tags such as "tired" or "motivated"
+    #[serde(default, deserialize_with = "deserialize_moods")]
+    pub mood: Vec<String>,
     pub difficulty: Option<String>,
     pub skipped: bool,
     pub created_at: String,
     pub updated_at: String,
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AmountProgress {
+    pub habit_id: String,
+    pub date: String,
+    pub actual_amount: f64,
+    pub target_amount: f64,
+    pub remaining: f64,
+    pub percentage: f64,
+}
+
+/// One page of a habit's completions, newest first
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompletionPage {
+    pub completions: Vec<HabitCompletion>,
+    /// Pass as `before_date` to fetch the next page. None once the oldest completion is reached.
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HabitStreakEntry {
+    pub habit_id: String,
+    pub habit_name: String,
+    pub longest_streak: i32,
+    pub current_streak: i32,
+}
+
+/// Accept a list of moods, a single mood from older clients, or null
+pub(crate) fn deserialize_moods<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Moods {
+        List(Vec<String>),
+        Single(String),
+    }
+
+    Ok(match Option::<Moods>::deserialize(deserializer)? {
+        Some(Moods::List(moods)) => moods,
+        Some(Moods::Single(mood)) if !mood.is_empty() => vec![mood],
+        _ => Vec::new(),
+    })
+}
+
+/// Read the mood column, which holds a JSON array or a single mood saved before tags existed
+pub(crate) fn moods_from_column(value: Option<String>) -> Vec<String> {
+    match value {
+        Some(value) if !value.is_empty() => {
+            serde_json::from_str(&value).unwrap_or_else(|_| vec![value])
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Store moods as a JSON array, or NULL when there are none
+pub(crate) fn moods_to_column(moods: &[String]) -> Option<String> {
+    let moods: Vec<&str> = moods
+        .iter()
+        .map(|mood| mood.as_str())
+        .filter(|mood| !mood.is_empty())
+        .collect();
+
+    (!moods.is_empty()).then(|| serde_json::Value::from(moods).to_string())
+}
+
 impl HabitCompletion {
     /// Map a database row to a HabitCompletion struct
-    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+    pub(crate) fn from_row(row: &Row) -> rusqlite::Result<Self> {
         Ok(Self {
             id: row.get(0)?,
             habit_id: row.get(1)?,
@@ -32,109 +116,517 @@ impl HabitCompletion {
             target_amount: row.get(5)?,
             completed_at: row.get(6)?,
             note: row.get(7)?,
-            mood: row.get(8)?,
+            mood: moods_from_column(row.get(8)?),
             difficulty: row.get(9)?,
             skipped: row.get::<_, i32>(10)? != 0,
             created_at: row.get(11)?,
             updated_at: row.get(12)?,
         })
     }
+
+    /// Insert this completion, updating the existing row for the same habit and date
+    pub(crate) fn upsert(&self, conn: &Connection) -> Result<(), String> {
+        // Cached so batch writes prepare the statement once
+        let mut stmt = conn.prepare_cached(
+            "INSERT INTO habit_completions (
+                id, habit_id, date, completed, actual_amount,
+                target_amount, completed_at, note, mood, difficulty,
+                skipped, created_at, updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+            ON CONFLICT(habit_id, date) DO UPDATE SET
+                completed = excluded.completed,
+                actual_amount = excluded.actual_amount,
+                target_amount = excluded.target_amount,
+                completed_at = excluded.completed_at,
+                note = excluded.note,
+                mood = excluded.mood,
+                difficulty = excluded.difficulty,
+                skipped = excluded.skipped,
+                updated_at = excluded.updated_at",
+        )
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        stmt.execute(params![
+            self.id,
+            self.habit_id,
+            self.date,
+            self.completed as i32,
+            self.actual_amount,
+            self.target_amount,
+            self.completed_at,
+            self.note,
+            moods_to_column(&self.mood),
+            self.difficulty,
+            self.skipped as i32,
+            self.created_at,
+            self.updated_at,
+        ])
+        .map_err(|e| format!("Failed to create habit completion: {}", e))?;
+
+        Ok(())
+    }
+}
+
+/// Completions may be dated slightly ahead of the configured day to absorb timezone edges
+const FUTURE_COMPLETION_GRACE_HOURS: i64 = 3;
+
+/// Completions per page when `get_habit_completions_page` isn't given a limit
+const DEFAULT_COMPLETION_PAGE_SIZE: i32 = 100;
+
+/// Most completions a single query returns
+const MAX_COMPLETIONS_PER_QUERY: i32 = 1000;
+
+/// Longest date range `skip_habits` accepts
+const MAX_SKIP_DAYS: i64 = 366;
+
+/// Latest completion date accepted right now, in the configured timezone
+fn latest_completion_date(state: &tauri::State<AppState>) -> Result<NaiveDate, String> {
+    let settings = load_settings_from_db(state)?;
+    let now = configured_now(settings.as_ref()) + Duration::hours(FUTURE_COMPLETION_GRACE_HOURS);
+    Ok(now.date())
+}
+
+/// Reject completions dated in the future or before their habit's start date
+fn validate_completion(
+    conn: &Connection,
+    completion: &HabitCompletion,
+    latest_allowed: NaiveDate,
+) -> Result<(), String> {
+    validate_habit_date(conn, &completion.habit_id, &completion.date, latest_allowed)
 }
 
+/// Reject a day that is malformed, in the future or before the habit's start date
+fn validate_habit_date(
+    conn: &Connection,
+    habit_id: &str,
+    date: &str,
+    latest_allowed: NaiveDate,
+) -> Result<(), String> {
+    let start_date: String = conn
+        .prepare_cached("SELECT start_date FROM habits WHERE id = ?1")
+        .and_then(|mut stmt| stmt.query_row(params![habit_id], |row| row.get(0)).optional())
+        .map_err(|e| format!("Failed to query habit: {}", e))?
+        .ok_or_else(|| DatabaseError::not_found("Habit", habit_id))?;
+
+    Ok(validate_completion_date(date, &start_date, latest_allowed)?)
+}
+
+/// Convert an amount logged in `unit` to the habit's own unit.
+/// Without a unit the amount is taken to be in the habit's unit already.
+fn amount_in_habit_unit(
+    conn: &Connection,
+    habit_id: &str,
+    amount: f64,
+    unit: Option<&str>,
+) -> Result<f64, String> {
+    let Some(unit) = unit else {
+        return Ok(amount);
+    };
+
+    let habit_unit: String = conn
+        .query_row("SELECT unit FROM habits WHERE id = ?1", params![habit_id], |row| row.get(0))
+        .optional()
+        .map_err(|e| format!("Failed to query habit: {}", e))?
+        .ok_or_else(|| DatabaseError::not_found("Habit", habit_id))?;
+
+    Ok(units::convert_amount(amount, unit, &habit_unit)?)
+}
+
+/// A habit's target amount and the share of it that counts as completed
+fn habit_target_and_threshold(conn: &Connection, habit_id: &str) -> Result<(f64, f64), String> {
+    conn.query_row(
+        "SELECT target_amount, completion_threshold FROM habits WHERE id = ?1",
+        params![habit_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+    .map_err(|e| format!("Failed to query habit: {}", e))?
+    .ok_or_else(|| DatabaseError::not_found("Habit", habit_id).into())
+}
+
+/// Derive `completed` and `completed_at` from the logged amount instead of trusting the client.
+/// Skipped days never count as completed.
+fn settle_completion(conn: &Connection, completion: &mut HabitCompletion) -> Result<(), String> {
+    let (_, threshold) = habit_target_and_threshold(conn, &completion.habit_id)?;
+
+    completion.completed = !completion.skipped
+        && completion.actual_amount >= completion.target_amount * threshold;
+    completion.completed_at = match (completion.completed, completion.completed_at.take()) {
+        (true, Some(completed_at)) => Some(completed_at),
+        (true, None) => Some(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+        (false, _) => None,
+    };
+
+    Ok(())
+}
+
+/// Record a completion and its follow-on effects (within transaction).
+/// Anything that must commit together with the completion belongs here.
+pub(crate) fn create_habit_completion_tx(
+    tx: &Transaction,
+    completion: &HabitCompletion,
+) -> Result<(), String> {
+    completion.upsert(tx)
+}
+
+/// Save a completion. An `actual_amount` logged in another `unit` is converted to the habit's unit first,
+/// then `completed` is worked out from it.
 #[tauri::command]
 pub async fn create_habit_completion(
     state: tauri::State<'_, AppState>,
-    completion: HabitCompletion,
+    app_handle: AppHandle,
+    mut completion: HabitCompletion,
+    unit: Option<String>,
 ) -> Result<HabitCompletion, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    db.execute(
-        "INSERT INTO habit_completions (
-            id, habit_id, date, completed, actual_amount,
-            target_amount, completed_at, note, mood, difficulty,
-            skipped, created_at, updated_at
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
-        ON CONFLICT(habit_id, date) DO UPDATE SET
-            completed = excluded.completed,
-            actual_amount = excluded.actual_amount,
-            target_amount = excluded.target_amount,
-            completed_at = excluded.completed_at,
-            note = excluded.note,
-            mood = excluded.mood,
-            difficulty = excluded.difficulty,
-            skipped = excluded.skipped,
-            updated_at = excluded.updated_at",
-        params![
-            completion.id,
-            completion.habit_id,
-            completion.date,
-            completion.completed as i32,
-            completion.actual_amount,
-            completion.target_amount,
-            completion.completed_at,
-            completion.note,
-            completion.mood,
-            completion.difficulty,
-            completion.skipped as i32,
-            completion.created_at,
-            completion.updated_at,
-        ],
-    )
-    .map_err(|e| format!("Failed to create habit completion: {}", e))?;
+    let latest_allowed = latest_completion_date(&state)?;
 
-    Ok(completion)
+    with_connection(&state, |db| {
+        validate_completion(db, &completion, latest_allowed)?;
+        completion.actual_amount =
+            amount_in_habit_unit(db, &completion.habit_id, completion.actual_amount, unit.as_deref())?;
+        settle_completion(db, &mut completion)?;
+
+        // Use transaction for atomic operations
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        create_habit_completion_tx(&tx, &completion)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        tray::refresh_status(&app_handle);
+        emit_data_changed(&app_handle, Entity::Completion, Action::Create, &completion.id);
+
+        Ok(completion)
+    })
+}
+
+/// Upsert many completions at once, e.g. when logging days retroactively.
+/// Amounts logged in another `unit` are converted like in `create_habit_completion`.
+/// All rows are written atomically. Returns the number written.
+#[tauri::command]
+pub async fn create_habit_completions_batch(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    mut completions: Vec<HabitCompletion>,
+    unit: Option<String>,
+) -> Result<usize, String> {
+    let latest_allowed = latest_completion_date(&state)?;
+
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        for completion in &mut completions {
+            validate_completion(&tx, completion, latest_allowed)?;
+            completion.actual_amount =
+                amount_in_habit_unit(&tx, &completion.habit_id, completion.actual_amount, unit.as_deref())?;
+            settle_completion(&tx, completion)?;
+            create_habit_completion_tx(&tx, completion)?;
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        tray::refresh_status(&app_handle);
+        for completion in &completions {
+            emit_data_changed(&app_handle, Entity::Completion, Action::Create, &completion.id);
+        }
+
+        Ok(completions.len())
+    })
+}
+
+/// Mark habits as skipped on every day from `start_date` to `end_date` (YYYY-MM-DD, inclusive),
+/// e.g. for a planned break. Unlike a pause the days are recorded, so they stay neutral for
+/// streaks. Days before a habit started and days already completed are left alone.
+/// Returns the number of completion rows written.
+#[tauri::command]
+pub async fn skip_habits(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    habit_ids: Vec<String>,
+    start_date: String,
+    end_date: String,
+) -> Result<usize, String> {
+    let start = NaiveDate::parse_from_str(&start_date, "%Y-%m-%d")
+        .map_err(|_| ValidationError::InvalidDate(start_date.clone()))?;
+    let end = NaiveDate::parse_from_str(&end_date, "%Y-%m-%d")
+        .map_err(|_| ValidationError::InvalidDate(end_date.clone()))?;
+    if start > end {
+        return Err("Start date must not be after end date".to_string());
+    }
+    if (end - start).num_days() >= MAX_SKIP_DAYS {
+        return Err(format!("Cannot skip more than {} days at once", MAX_SKIP_DAYS));
+    }
+
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let mut written = Vec::new();
+        let mut seen = Vec::new();
+
+        for habit_id in habit_ids {
+            if seen.contains(&habit_id) {
+                continue;
+            }
+
+            let (target_amount, habit_start): (f64, String) = tx
+                .query_row(
+                    "SELECT target_amount, start_date FROM habits WHERE id = ?1",
+                    params![habit_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .optional()
+                .map_err(|e| format!("Failed to query habit: {}", e))?
+                .ok_or_else(|| DatabaseError::not_found("Habit", &habit_id))?;
+
+            let habit_start = habit_start.get(..10).unwrap_or(&habit_start);
+            let first_day = NaiveDate::parse_from_str(habit_start, "%Y-%m-%d")
+                .map_or(start, |habit_start| habit_start.max(start));
+
+            let mut stmt = tx
+                .prepare_cached(
+                    "INSERT INTO habit_completions (
+                        id, habit_id, date, completed, actual_amount, target_amount,
+                        skipped, created_at, updated_at
+                    ) VALUES (?1, ?2, ?3, 0, 0.0, ?4, 1, ?5, ?5)
+                    ON CONFLICT(habit_id, date) DO UPDATE SET
+                        skipped = 1,
+                        updated_at = excluded.updated_at
+                    WHERE completed = 0 AND skipped = 0
+                    RETURNING id",
+                )
+                .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+            for day in first_day.iter_days().take_while(|day| *day <= end) {
+                let date = day.format("%Y-%m-%d").to_string();
+                // Same id scheme the frontend uses for completions
+                let id = format!("{}-{}", habit_id, date);
+
+                // An existing row keeps its own id. Nothing comes back when it was left alone.
+                let written_id: Option<String> = stmt
+                    .query_row(params![id, habit_id, date, target_amount, now], |row| row.get(0))
+                    .optional()
+                    .map_err(|e| format!("Failed to skip habit: {}", e))?;
+
+                written.extend(written_id);
+            }
+
+            seen.push(habit_id);
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        if !written.is_empty() {
+            tray::refresh_status(&app_handle);
+        }
+        for id in &written {
+            emit_data_changed(&app_handle, Entity::Completion, Action::Update, id);
+        }
+
+        Ok(written.len())
+    })
 }
 
 #[tauri::command]
 pub async fn update_habit_completion(
     state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
     completion: HabitCompletion,
 ) -> Result<HabitCompletion, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    let rows = db.execute(
-        "UPDATE habit_completions SET
-            completed = ?1, actual_amount = ?2, target_amount = ?3,
-            completed_at = ?4, note = ?5, mood = ?6, difficulty = ?7,
-            skipped = ?8, updated_at = ?9
-        WHERE id = ?10",
-        params![
-            completion.completed as i32,
-            completion.actual_amount,
-            completion.target_amount,
-            completion.completed_at,
-            completion.note,
-            completion.mood,
-            completion.difficulty,
-            completion.skipped as i32,
-            completion.updated_at,
-            completion.id,
-        ],
-    )
-    .map_err(|e| format!("Failed to update habit completion: {}", e))?;
+    with_connection(&state, |db| {
+        let rows = db.execute(
+            "UPDATE habit_completions SET
+                completed = ?1, actual_amount = ?2, target_amount = ?3,
+                completed_at = ?4, note = ?5, mood = ?6, difficulty = ?7,
+                skipped = ?8, updated_at = ?9
+            WHERE id = ?10",
+            params![
+                completion.completed as i32,
+                completion.actual_amount,
+                completion.target_amount,
+                completion.completed_at,
+                completion.note,
+                moods_to_column(&completion.mood),
+                completion.difficulty,
+                completion.skipped as i32,
+                completion.updated_at,
+                completion.id,
+            ],
+        )
+        .map_err(|e| format!("Failed to update habit completion: {}", e))?;
+
+        if rows == 0 {
+            return Err(DatabaseError::not_found("Habit completion", &completion.id).into());
+        }
 
-    if rows == 0 {
-        return Err(format!("Habit completion with id '{}' not found", completion.id));
+        tray::refresh_status(&app_handle);
+        emit_data_changed(&app_handle, Entity::Completion, Action::Update, &completion.id);
+
+        Ok(completion)
+    })
+}
+
+/// Add `delta` to a day's logged amount, creating the completion if needed.
+/// A `delta` in another `unit` is converted to the habit's unit first.
+/// `date` is checked like a new completion's: YYYY-MM-DD, not in the future and not before the habit started.
+#[tauri::command]
+pub async fn log_habit_amount(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    habit_id: String,
+    date: String,
+    delta: f64,
+    unit: Option<String>,
+) -> Result<HabitCompletion, String> {
+    if !delta.is_finite() {
+        return Err("Amount must be a finite number".to_string());
     }
+    let latest_allowed = latest_completion_date(&state)?;
+
+    with_connection(&state, |db| {
+        // Take the write lock up front so concurrent increments can't read the same amount
+        let tx = db.transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        validate_habit_date(&tx, &habit_id, &date, latest_allowed)?;
+
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        let delta = amount_in_habit_unit(&tx, &habit_id, delta, unit.as_deref())?;
+        let (habit_target, threshold) = habit_target_and_threshold(&tx, &habit_id)?;
+
+        let existing = tx
+            .query_row(
+                "SELECT * FROM habit_completions WHERE habit_id = ?1 AND date = ?2",
+                params![habit_id, date],
+                HabitCompletion::from_row,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to query habit completion: {}", e))?;
+
+        let action = if existing.is_some() { Action::Update } else { Action::Create };
+        let mut completion = match existing {
+            Some(completion) => completion,
+            // Same id scheme the frontend uses for completions
+            None => HabitCompletion {
+                id: format!("{}-{}", habit_id, date),
+                habit_id: habit_id.clone(),
+                date: date.clone(),
+                completed: false,
+                actual_amount: 0.0,
+                target_amount: habit_target,
+                completed_at: None,
+                note: String::new(),
+                mood: Vec::new(),
+                difficulty: None,
+                skipped: false,
+                created_at: now.clone(),
+                updated_at: now.clone(),
+            },
+        };
 
-    Ok(completion)
+        let was_completed = completion.completed;
+        completion.actual_amount = (completion.actual_amount + delta).max(0.0);
+        completion.completed = completion.actual_amount >= completion.target_amount * threshold;
+        completion.completed_at = match (was_completed, completion.completed) {
+            (false, true) => Some(now.clone()),
+            (_, false) => None,
+            (true, true) => completion.completed_at,
+        };
+        completion.updated_at = now;
+
+        completion.upsert(&tx)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        tray::refresh_status(&app_handle);
+        emit_data_changed(&app_handle, Entity::Completion, action, &completion.id);
+
+        Ok(completion)
+    })
+}
+
+/// Convert an amount between units of the same kind, e.g. milliliters to liters
+#[tauri::command]
+pub async fn convert_amount(value: f64, from_unit: String, to_unit: String) -> Result<f64, String> {
+    Ok(units::convert_amount(value, &from_unit, &to_unit)?)
 }
 
 #[tauri::command]
 pub async fn delete_habit_completion(
     state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
     id: String,
 ) -> Result<bool, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    with_connection(&state, |db| {
+        let rows_affected = db
+            .execute("DELETE FROM habit_completions WHERE id = ?1", params![id])
+            .map_err(|e| format!("Failed to delete habit completion: {}", e))?;
 
-    let rows_affected = db
-        .execute("DELETE FROM habit_completions WHERE id = ?1", params![id])
-        .map_err(|e| format!("Failed to delete habit completion: {}", e))?;
+        tray::refresh_status(&app_handle);
+        if rows_affected > 0 {
+            emit_data_changed(&app_handle, Entity::Completion, Action::Delete, &id);
+        }
 
-    Ok(rows_affected > 0)
+        Ok(rows_affected > 0)
+    })
+}
+
+/// Set completions on or after `from_date` to the habit's current target and recompute
+/// whether each one is completed. Skipped days are left as they are. Earlier history is
+/// never touched, so this only runs when explicitly requested. Returns how many changed.
+#[tauri::command]
+pub async fn sync_completion_targets(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    habit_id: String,
+    from_date: String,
+) -> Result<usize, String> {
+    NaiveDate::parse_from_str(&from_date, "%Y-%m-%d")
+        .map_err(|_| ValidationError::InvalidDate(from_date.clone()))?;
+
+    with_connection(&state, |db| {
+        let (target_amount, threshold) = habit_target_and_threshold(db, &habit_id)?;
+
+        let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+        let mut stmt = db
+            .prepare(
+                "UPDATE habit_completions SET
+                    target_amount = ?3,
+                    completed = CASE WHEN skipped = 1 THEN completed ELSE actual_amount >= ?3 * ?5 END,
+                    completed_at = CASE
+                        WHEN skipped = 1 THEN completed_at
+                        WHEN actual_amount >= ?3 * ?5 THEN COALESCE(completed_at, ?4)
+                        ELSE NULL
+                    END,
+                    updated_at = ?4
+                 WHERE habit_id = ?1 AND date >= ?2 AND target_amount != ?3
+                 RETURNING id",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let updated_ids = stmt
+            .query_map(params![habit_id, from_date, target_amount, now, threshold], |row| {
+                row.get::<_, String>(0)
+            })
+            .map_err(|e| format!("Failed to update completion targets: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to update completion targets: {}", e))?;
+
+        tray::refresh_status(&app_handle);
+        for id in &updated_ids {
+            emit_data_changed(&app_handle, Entity::Completion, Action::Update, id);
+        }
+
+        Ok(updated_ids.len())
+    })
 }
 
 #[tauri::command]
@@ -145,83 +637,136 @@ pub async fn get_habit_completions(
     end_date: Option<String>,
     limit: Option<i32>,
 ) -> Result<Vec<HabitCompletion>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    let limit_clause = limit
-        .map(|l| format!(" LIMIT {}", l.min(1000)))
-        .unwrap_or_default();
-
-    match (&start_date, &end_date) {
-        (Some(start), Some(end)) => {
-            let query = format!(
-                "SELECT * FROM habit_completions WHERE habit_id = ?1 AND date BETWEEN ?2 AND ?3 ORDER BY date DESC{}",
-                limit_clause
-            );
-            let mut stmt = db
-                .prepare(&query)
-                .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    let _timer = CommandTimer::start("get_habit_completions");
+    with_connection(&state, |db| {
+        let limit_clause = limit
+            .map(|l| format!(" LIMIT {}", l.min(MAX_COMPLETIONS_PER_QUERY)))
+            .unwrap_or_default();
 
-            let completions: Vec<HabitCompletion> = stmt
-                .query_map(params![&habit_id, start, end], HabitCompletion::from_row)
-                .map_err(|e| format!("Failed to query habit completions: {}", e))?
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
+        match (&start_date, &end_date) {
+            (Some(start), Some(end)) => {
+                let query = format!(
+                    "SELECT * FROM habit_completions WHERE habit_id = ?1 AND date BETWEEN ?2 AND ?3 ORDER BY date DESC{}",
+                    limit_clause
+                );
+                let mut stmt = db
+                    .prepare(&query)
+                    .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-            Ok(completions)
-        }
-        (Some(start), None) => {
-            let query = format!(
-                "SELECT * FROM habit_completions WHERE habit_id = ?1 AND date >= ?2 ORDER BY date DESC{}",
-                limit_clause
-            );
-            let mut stmt = db
-                .prepare(&query)
-                .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+                let completions: Vec<HabitCompletion> = stmt
+                    .query_map(params![&habit_id, start, end], HabitCompletion::from_row)
+                    .map_err(|e| format!("Failed to query habit completions: {}", e))?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
 
-            let completions: Vec<HabitCompletion> = stmt
-                .query_map(params![&habit_id, start], HabitCompletion::from_row)
-                .map_err(|e| format!("Failed to query habit completions: {}", e))?
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
+                Ok(completions)
+            }
+            (Some(start), None) => {
+                let query = format!(
+                    "SELECT * FROM habit_completions WHERE habit_id = ?1 AND date >= ?2 ORDER BY date DESC{}",
+                    limit_clause
+                );
+                let mut stmt = db
+                    .prepare(&query)
+                    .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-            Ok(completions)
-        }
-        (None, Some(end)) => {
-            let query = format!(
-                "SELECT * FROM habit_completions WHERE habit_id = ?1 AND date <= ?2 ORDER BY date DESC{}",
-                limit_clause
-            );
-            let mut stmt = db
-                .prepare(&query)
-                .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+                let completions: Vec<HabitCompletion> = stmt
+                    .query_map(params![&habit_id, start], HabitCompletion::from_row)
+                    .map_err(|e| format!("Failed to query habit completions: {}", e))?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
 
-            let completions: Vec<HabitCompletion> = stmt
-                .query_map(params![&habit_id, end], HabitCompletion::from_row)
-                .map_err(|e| format!("Failed to query habit completions: {}", e))?
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
+                Ok(completions)
+            }
+            (None, Some(end)) => {
+                let query = format!(
+                    "SELECT * FROM habit_completions WHERE habit_id = ?1 AND date <= ?2 ORDER BY date DESC{}",
+                    limit_clause
+                );
+                let mut stmt = db
+                    .prepare(&query)
+                    .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-            Ok(completions)
-        }
-        (None, None) => {
-            let query = format!(
-                "SELECT * FROM habit_completions WHERE habit_id = ?1 ORDER BY date DESC{}",
-                limit_clause
-            );
-            let mut stmt = db
-                .prepare(&query)
-                .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+                let completions: Vec<HabitCompletion> = stmt
+                    .query_map(params![&habit_id, end], HabitCompletion::from_row)
+                    .map_err(|e| format!("Failed to query habit completions: {}", e))?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
 
-            let completions: Vec<HabitCompletion> = stmt
-                .query_map(params![&habit_id], HabitCompletion::from_row)
-                .map_err(|e| format!("Failed to query habit completions: {}", e))?
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
+                Ok(completions)
+            }
+            (None, None) => {
+                let query = format!(
+                    "SELECT * FROM habit_completions WHERE habit_id = ?1 ORDER BY date DESC{}",
+                    limit_clause
+                );
+                let mut stmt = db
+                    .prepare(&query)
+                    .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-            Ok(completions)
+                let completions: Vec<HabitCompletion> = stmt
+                    .query_map(params![&habit_id], HabitCompletion::from_row)
+                    .map_err(|e| format!("Failed to query habit completions: {}", e))?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
+
+                Ok(completions)
+            }
         }
+    })
+}
+
+/// Page through a habit's completions from newest to oldest. Each page holds the completions
+/// dated before `before_date`, or the newest ones when it's None.
+#[tauri::command]
+pub async fn get_habit_completions_page(
+    state: tauri::State<'_, AppState>,
+    habit_id: String,
+    before_date: Option<String>,
+    limit: Option<i32>,
+) -> Result<CompletionPage, String> {
+    let _timer = CommandTimer::start("get_habit_completions_page");
+    if let Some(date) = &before_date {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| ValidationError::InvalidDate(date.clone()))?;
     }
+    let limit = limit
+        .unwrap_or(DEFAULT_COMPLETION_PAGE_SIZE)
+        .clamp(1, MAX_COMPLETIONS_PER_QUERY);
+
+    with_connection(&state, |db| {
+        // Separate statements keep the date bound on idx_habit_completions_habit_date
+        let completions = match &before_date {
+            Some(before) => db
+                .prepare_cached(
+                    "SELECT * FROM habit_completions WHERE habit_id = ?1 AND date < ?2
+                     ORDER BY date DESC LIMIT ?3",
+                )
+                .and_then(|mut stmt| {
+                    stmt.query_map(params![habit_id, before, limit], HabitCompletion::from_row)?
+                        .collect::<Result<Vec<_>, _>>()
+                }),
+            None => db
+                .prepare_cached(
+                    "SELECT * FROM habit_completions WHERE habit_id = ?1
+                     ORDER BY date DESC LIMIT ?2",
+                )
+                .and_then(|mut stmt| {
+                    stmt.query_map(params![habit_id, limit], HabitCompletion::from_row)?
+                        .collect::<Result<Vec<_>, _>>()
+                }),
+        }
+        .map_err(|e| format!("Failed to query habit completions: {}", e))?;
+
+        // A short page means there is nothing older left
+        let next_cursor = if completions.len() == limit as usize {
+            completions.last().map(|completion| completion.date.clone())
+        } else {
+            None
+        };
+
+        Ok(CompletionPage { completions, next_cursor })
+    })
 }
 
 #[tauri::command]
@@ -230,19 +775,286 @@ pub async fn get_completion_by_date(
     habit_id: String,
     date: String,
 ) -> Result<Option<HabitCompletion>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    let completion = db
-        .query_row(
-            "SELECT * FROM habit_completions WHERE habit_id = ?1 AND date = ?2",
-            params![habit_id, date],
-            HabitCompletion::from_row,
-        )
-        .optional()
-        .map_err(|e| format!("Failed to query habit completion: {}", e))?;
+    let _timer = CommandTimer::start("get_completion_by_date");
+    with_connection(&state, |db| {
+        let completion = db
+            .query_row(
+                "SELECT * FROM habit_completions WHERE habit_id = ?1 AND date = ?2",
+                params![habit_id, date],
+                HabitCompletion::from_row,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to query habit completion: {}", e))?;
+
+        Ok(completion)
+    })
+}
+
+/// Get a habit's completions on any of the given dates. Dates without a completion are left out.
+#[tauri::command]
+pub async fn get_completions_by_dates(
+    state: tauri::State<'_, AppState>,
+    habit_id: String,
+    dates: Vec<String>,
+) -> Result<Vec<HabitCompletion>, String> {
+    let _timer = CommandTimer::start("get_completions_by_dates");
+    if dates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    with_connection(&state, |db| {
+        // The habit id takes ?1, dates are bound from ?2 onwards
+        let placeholders = (0..dates.len())
+            .map(|i| format!("?{}", i + 2))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let query = format!(
+            "SELECT * FROM habit_completions
+             WHERE habit_id = ?1 AND date IN ({})
+             ORDER BY date ASC",
+            placeholders
+        );
+
+        let mut stmt = db
+            .prepare(&query)
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let bindings = std::iter::once(&habit_id).chain(dates.iter());
+
+        let completions = stmt
+            .query_map(params_from_iter(bindings), HabitCompletion::from_row)
+            .map_err(|e| format!("Failed to query habit completions: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
+
+        Ok(completions)
+    })
+}
+
+#[tauri::command]
+pub async fn get_completions_for_date(
+    state: tauri::State<'_, AppState>,
+    date: String,
+) -> Result<Vec<HabitCompletion>, String> {
+    let _timer = CommandTimer::start("get_completions_for_date");
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare("SELECT * FROM habit_completions WHERE date = ?1 ORDER BY habit_id ASC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let completions = stmt
+            .query_map(params![date], HabitCompletion::from_row)
+            .map_err(|e| format!("Failed to query habit completions: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
+
+        Ok(completions)
+    })
+}
+
+#[tauri::command]
+pub async fn get_completions_aggregate(
+    state: tauri::State<'_, AppState>,
+    habit_ids: Vec<String>,
+    start_date: String,
+    end_date: String,
+) -> Result<HashMap<String, Vec<HabitCompletion>>, String> {
+    let _timer = CommandTimer::start("get_completions_aggregate");
+    let mut grouped: HashMap<String, Vec<HabitCompletion>> = habit_ids
+        .iter()
+        .map(|id| (id.clone(), Vec::new()))
+        .collect();
+
+    if habit_ids.is_empty() {
+        return Ok(grouped);
+    }
+
+    with_connection(&state, |db| {
+        // Dates take ?1 and ?2, habit ids are bound from ?3 onwards
+        let placeholders = (0..habit_ids.len())
+            .map(|i| format!("?{}", i + 3))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        let query = format!(
+            "SELECT * FROM habit_completions
+             WHERE date BETWEEN ?1 AND ?2 AND habit_id IN ({})
+             ORDER BY habit_id, date ASC",
+            placeholders
+        );
+
+        let mut stmt = db
+            .prepare(&query)
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let bindings = [&start_date, &end_date].into_iter().chain(habit_ids.iter());
+
+        let completions = stmt
+            .query_map(params_from_iter(bindings), HabitCompletion::from_row)
+            .map_err(|e| format!("Failed to query habit completions: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
 
-    Ok(completion)
+        for completion in completions {
+            grouped
+                .entry(completion.habit_id.clone())
+                .or_default()
+                .push(completion);
+        }
+
+        Ok(grouped)
+    })
+}
+
+/// Get a habit's completions for one calendar month
+#[tauri::command]
+pub async fn get_completions_for_month(
+    state: tauri::State<'_, AppState>,
+    habit_id: String,
+    year: i32,
+    month: u32,
+) -> Result<Vec<HabitCompletion>, String> {
+    let _timer = CommandTimer::start("get_completions_for_month");
+    let first_day = NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or_else(|| format!("Invalid month {}-{}", year, month))?;
+
+    // Day before the first of the next month handles month lengths and leap years
+    let next_month = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    };
+    let last_day = next_month
+        .and_then(|date| date.pred_opt())
+        .ok_or_else(|| format!("Invalid month {}-{}", year, month))?;
+
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare(
+                "SELECT * FROM habit_completions
+                 WHERE habit_id = ?1 AND date BETWEEN ?2 AND ?3
+                 ORDER BY date ASC",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let completions = stmt
+            .query_map(
+                params![
+                    habit_id,
+                    first_day.format("%Y-%m-%d").to_string(),
+                    last_day.format("%Y-%m-%d").to_string()
+                ],
+                HabitCompletion::from_row,
+            )
+            .map_err(|e| format!("Failed to query habit completions: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
+
+        Ok(completions)
+    })
+}
+
+/// Search completion notes, newest first, optionally within a single habit
+#[tauri::command]
+pub async fn search_completion_notes(
+    state: tauri::State<'_, AppState>,
+    query: String,
+    habit_id: Option<String>,
+    limit: Option<i32>,
+) -> Result<Vec<HabitCompletion>, String> {
+    let _timer = CommandTimer::start("search_completion_notes");
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    with_connection(&state, |db| {
+        // Match the query literally, not as a LIKE pattern
+        let pattern = format!(
+            "%{}%",
+            query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+        );
+        let limit = limit.unwrap_or(50).clamp(1, 1000);
+
+        let mut stmt = db
+            .prepare(
+                "SELECT * FROM habit_completions
+                 WHERE note LIKE ?1 ESCAPE '\\' AND (?2 IS NULL OR habit_id = ?2)
+                 ORDER BY date DESC
+                 LIMIT ?3",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let completions = stmt
+            .query_map(params![pattern, habit_id, limit], HabitCompletion::from_row)
+            .map_err(|e| format!("Failed to search habit completions: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
+
+        Ok(completions)
+    })
+}
+
+/// Get how much of a habit's target amount is logged for a date (defaults to today in the configured timezone)
+#[tauri::command]
+pub async fn get_today_progress(
+    state: tauri::State<'_, AppState>,
+    habit_id: String,
+    date: Option<String>,
+) -> Result<AmountProgress, String> {
+    let _timer = CommandTimer::start("get_today_progress");
+    let date = match date {
+        Some(date) => date,
+        None => configured_now(load_settings_from_db(&state)?.as_ref())
+            .date()
+            .format("%Y-%m-%d")
+            .to_string(),
+    };
+
+    with_connection(&state, |db| {
+        let logged: Option<(f64, f64)> = db
+            .query_row(
+                "SELECT actual_amount, target_amount FROM habit_completions
+                 WHERE habit_id = ?1 AND date = ?2",
+                params![habit_id, date],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to query habit completion: {}", e))?;
+
+        // Nothing logged yet: 0 of the habit's current target
+        let (actual_amount, target_amount) = match logged {
+            Some(amounts) => amounts,
+            None => {
+                let target: f64 = db
+                    .query_row(
+                        "SELECT target_amount FROM habits WHERE id = ?1",
+                        params![habit_id],
+                        |row| row.get(0),
+                    )
+                    .optional()
+                    .map_err(|e| format!("Failed to query habit: {}", e))?
+                    .ok_or_else(|| DatabaseError::not_found("Habit", &habit_id))?;
+                (0.0, target)
+            }
+        };
+
+        let percentage = if target_amount > 0.0 {
+            (actual_amount / target_amount * 100.0).min(100.0)
+        } else {
+            0.0
+        };
+
+        Ok(AmountProgress {
+            habit_id,
+            date,
+            actual_amount,
+            target_amount,
+            remaining: (target_amount - actual_amount).max(0.0),
+            percentage,
+        })
+    })
 }
 
 #[tauri::command]
@@ -250,39 +1062,334 @@ pub async fn get_habit_streak(
     state: tauri::State<'_, AppState>,
     habit_id: String,
 ) -> Result<i32, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    // Optimized streak calculation using recursive CTE
-    let streak: i32 = db
-        .query_row(
-            "WITH RECURSIVE
-            latest_completion AS (
-                SELECT date, completed
-                FROM habit_completions
-                WHERE habit_id = ?1
-                ORDER BY date DESC
-                LIMIT 1
-            ),
-            streak_dates(current_date, days) AS (
-                SELECT date, 1
-                FROM latest_completion
-                WHERE completed = 1
-
-                UNION ALL
-
-                SELECT hc.date, sd.days + 1
-                FROM habit_completions hc
-                INNER JOIN streak_dates sd
-                    ON date(hc.date, '+1 day') = sd.current_date
-                WHERE hc.habit_id = ?1
-                    AND hc.completed = 1
-            )
-            SELECT COALESCE(MAX(days), 0) FROM streak_dates",
-            params![habit_id],
-            |row| row.get(0),
+    let _timer = CommandTimer::start("get_habit_streak");
+    let grace_days = configured_streak_grace_days(load_settings_from_db(&state)?.as_ref());
+
+    with_connection(&state, |db| {
+        Ok(calculate_current_streak(db, &habit_id, grace_days))
+    })
+}
+
+/// Get the current streak of every habit, keyed by habit ID
+#[tauri::command]
+pub async fn get_all_streaks(
+    state: tauri::State<'_, AppState>,
+) -> Result<HashMap<String, i32>, String> {
+    let _timer = CommandTimer::start("get_all_streaks");
+    let grace_days = configured_streak_grace_days(load_settings_from_db(&state)?.as_ref());
+
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare("SELECT id FROM habits")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let habit_ids = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("Failed to query habits: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect habits: {}", e))?;
+
+        Ok(habit_ids
+            .into_iter()
+            .map(|id| {
+                let streak = calculate_current_streak(db, &id, grace_days);
+                (id, streak)
+            })
+            .collect())
+    })
+}
+
+/// Get every habit's longest-ever streak, best first, with ties broken by habit name
+#[tauri::command]
+pub async fn get_streak_leaderboard(
+    state: tauri::State<'_, AppState>,
+    limit: Option<i32>,
+) -> Result<Vec<HabitStreakEntry>, String> {
+    let _timer = CommandTimer::start("get_streak_leaderboard");
+    let grace_days = configured_streak_grace_days(load_settings_from_db(&state)?.as_ref());
+
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare("SELECT id, name FROM habits")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let habits = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+            .map_err(|e| format!("Failed to query habits: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect habits: {}", e))?;
+
+        let mut entries = habits
+            .into_iter()
+            .map(|(habit_id, habit_name)| {
+                let (current_streak, longest_streak) = calculate_streaks(db, &habit_id, grace_days)?;
+
+                Ok(HabitStreakEntry {
+                    habit_id,
+                    habit_name,
+                    longest_streak,
+                    current_streak,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        entries.sort_by(|a, b| {
+            b.longest_streak
+                .cmp(&a.longest_streak)
+                .then_with(|| a.habit_name.cmp(&b.habit_name))
+        });
+
+        if let Some(limit) = limit {
+            entries.truncate(limit.max(0) as usize);
+        }
+
+        Ok(entries)
+    })
+}
+
+/// Calculate the current streak for a habit. Paused, skipped and unscheduled days are
+/// neutral: they neither break the streak nor add to it. Up to `grace_days` missed days in a row
+/// are tolerated too, though they don't add to it either.
+pub(crate) fn calculate_current_streak(conn: &Connection, habit_id: &str, grace_days: u32) -> i32 {
+    calculate_streaks(conn, habit_id, grace_days).map_or(0, |(current, _)| current)
+}
+
+/// Calculate the current and longest streak for a habit, with the same rules as
+/// `calculate_current_streak`
+pub(crate) fn calculate_streaks(
+    conn: &Connection,
+    habit_id: &str,
+    grace_days: u32,
+) -> Result<(i32, i32), String> {
+    let pauses = get_pauses_for_habit(conn, habit_id)?;
+    let habit = Habit::find(conn, habit_id)?;
+    let frequency_history = get_frequency_history(conn, habit_id)?;
+
+    // Paused days and days the frequency in effect didn't schedule, such as Tuesday for a
+    // Mon/Wed/Fri habit, neither extend nor break a run
+    let is_rest_day = |date: NaiveDate| {
+        let date_str = date.format("%Y-%m-%d").to_string();
+        let unscheduled = habit.as_ref().is_some_and(|habit| {
+            let frequency = frequency_on(habit, &frequency_history, &date_str);
+            !is_scheduled_with(habit, frequency, date)
+        });
+
+        unscheduled || pauses.iter().any(|pause| pause.covers(&date_str))
+    };
+
+    // Cached so computing streaks for every habit reuses the same statement
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT date, completed, skipped FROM habit_completions
+             WHERE habit_id = ?1
+             ORDER BY date DESC",
         )
-        .unwrap_or(0);
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let rows = stmt
+        .query_map(params![habit_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i32>(1)? != 0,
+                row.get::<_, i32>(2)? != 0,
+            ))
+        })
+        .map_err(|e| format!("Failed to query habit completions: {}", e))?;
+
+    // Walk back from the latest completion. A run continues while each row is the day
+    // before the previous one, or misses no more than `grace_days` days in a row since the
+    // last completed day. The first run is the current streak.
+    let mut current: Option<i32> = None;
+    let mut longest = 0;
+    let mut run = 0;
+    let mut missed = 0;
+    let mut expected: Option<NaiveDate> = None;
+
+    for row in rows {
+        let (date, completed, skipped) = row.map_err(|e| format!("Failed to read habit completion: {}", e))?;
+        let Ok(date) = NaiveDate::parse_from_str(&date, "%Y-%m-%d") else {
+            break;
+        };
+
+        // Days with no row between this one and the previous are misses, unless rest days
+        if let Some(mut day) = expected {
+            while day > date && missed <= grace_days {
+                if !is_rest_day(day) {
+                    missed += 1;
+                }
+                day -= Duration::days(1);
+            }
+            if missed > grace_days {
+                current.get_or_insert(run);
+                longest = longest.max(run);
+                run = 0;
+                missed = 0;
+            }
+        }
+
+        if skipped {
+            // An intentional rest day bridges the run without extending it
+            expected = Some(date - Duration::days(1));
+        } else if completed {
+            run += 1;
+            missed = 0;
+            expected = Some(date - Duration::days(1));
+        } else if is_rest_day(date) {
+            expected = Some(date - Duration::days(1));
+        } else if missed < grace_days {
+            missed += 1;
+            expected = Some(date - Duration::days(1));
+        } else {
+            current.get_or_insert(run);
+            longest = longest.max(run);
+            run = 0;
+            missed = 0;
+            expected = None;
+        }
+    }
 
-    Ok(streak)
-}
\ No newline at end of file
+    Ok((current.unwrap_or(run), longest.max(run)))
+}
+
+/// Count completed days for a habit within an inclusive date range
+pub(crate) fn count_completed_in_range(
+    conn: &Connection,
+    habit_id: &str,
+    start_date: &str,
+    end_date: &str,
+) -> Result<i64, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM habit_completions
+         WHERE habit_id = ?1 AND completed = 1 AND date BETWEEN ?2 AND ?3",
+        params![habit_id, start_date, end_date],
+        |row| row.get(0),
+    )
+    .map_err(|e| format!("Failed to count habit completions: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{insert_test_habit, test_connection, ALL_WEEKDAYS};
+
+    fn log_day(conn: &Connection, habit_id: &str, date: &str, completed: bool, skipped: bool) {
+        conn.execute(
+            "INSERT INTO habit_completions (id, habit_id, date, completed, skipped, created_at, updated_at)
+             VALUES (?1 || ?2, ?1, ?2, ?3, ?4, ?2, ?2)",
+            params![habit_id, date, completed, skipped],
+        )
+        .unwrap();
+    }
+
+    fn complete_days(conn: &Connection, habit_id: &str, dates: &[&str]) {
+        for date in dates {
+            log_day(conn, habit_id, date, true, false);
+        }
+    }
+
+    #[test]
+    fn one_day_gap_is_within_a_grace_of_one() {
+        let conn = test_connection();
+        insert_test_habit(&conn, "h", &ALL_WEEKDAYS);
+        complete_days(&conn, "h", &["2024-01-01", "2024-01-02", "2024-01-03", "2024-01-05", "2024-01-06"]);
+
+        assert_eq!(calculate_streaks(&conn, "h", 1).unwrap(), (5, 5));
+        assert_eq!(calculate_streaks(&conn, "h", 0).unwrap(), (2, 3));
+    }
+
+    #[test]
+    fn two_day_gap_breaks_a_grace_of_one() {
+        let conn = test_connection();
+        insert_test_habit(&conn, "h", &ALL_WEEKDAYS);
+        complete_days(&conn, "h", &["2024-01-01", "2024-01-02", "2024-01-03", "2024-01-06", "2024-01-07"]);
+
+        assert_eq!(calculate_streaks(&conn, "h", 1).unwrap(), (2, 3));
+    }
+
+    #[test]
+    fn missed_rows_count_towards_the_grace() {
+        let conn = test_connection();
+        insert_test_habit(&conn, "h", &ALL_WEEKDAYS);
+        complete_days(&conn, "h", &["2024-01-01", "2024-01-02"]);
+        log_day(&conn, "h", "2024-01-03", false, false);
+        complete_days(&conn, "h", &["2024-01-04"]);
+        log_day(&conn, "h", "2024-01-05", false, false);
+        log_day(&conn, "h", "2024-01-06", false, false);
+        complete_days(&conn, "h", &["2024-01-07"]);
+
+        assert_eq!(calculate_streaks(&conn, "h", 1).unwrap(), (1, 3));
+    }
+
+    fn claimed_completion(actual_amount: f64, target_amount: f64) -> HabitCompletion {
+        HabitCompletion {
+            id: "h-2024-01-01".to_string(),
+            habit_id: "h".to_string(),
+            date: "2024-01-01".to_string(),
+            completed: true,
+            actual_amount,
+            target_amount,
+            completed_at: Some("2024-01-01T08:00:00.000Z".to_string()),
+            note: String::new(),
+            mood: Vec::new(),
+            difficulty: None,
+            skipped: false,
+            created_at: "2024-01-01T08:00:00.000Z".to_string(),
+            updated_at: "2024-01-01T08:00:00.000Z".to_string(),
+        }
+    }
+
+    #[test]
+    fn settled_completion_follows_the_amount_not_the_client() {
+        let conn = test_connection();
+        insert_test_habit(&conn, "h", &ALL_WEEKDAYS);
+        conn.execute("UPDATE habits SET completion_threshold = 0.5 WHERE id = 'h'", []).unwrap();
+
+        let mut short = claimed_completion(0.5, 2.0);
+        settle_completion(&conn, &mut short).unwrap();
+        assert!(!short.completed);
+        assert_eq!(short.completed_at, None);
+
+        let mut reached = claimed_completion(1.0, 2.0);
+        settle_completion(&conn, &mut reached).unwrap();
+        assert!(reached.completed);
+        assert_eq!(reached.completed_at.as_deref(), Some("2024-01-01T08:00:00.000Z"));
+
+        let mut skipped = HabitCompletion { skipped: true, ..claimed_completion(2.0, 2.0) };
+        settle_completion(&conn, &mut skipped).unwrap();
+        assert!(!skipped.completed);
+
+        let mut unclaimed = HabitCompletion { completed: false, completed_at: None, ..claimed_completion(2.0, 2.0) };
+        settle_completion(&conn, &mut unclaimed).unwrap();
+        assert!(unclaimed.completed);
+        assert!(unclaimed.completed_at.is_some());
+    }
+
+    #[test]
+    fn amount_in_another_unit_is_settled_after_converting() {
+        let conn = test_connection();
+        insert_test_habit(&conn, "h", &ALL_WEEKDAYS);
+        conn.execute("UPDATE habits SET unit = 'l', target_amount = 2.0 WHERE id = 'h'", []).unwrap();
+
+        // 500 ml claimed as completed against a 2 l target
+        let mut completion = claimed_completion(500.0, 2.0);
+        completion.actual_amount =
+            amount_in_habit_unit(&conn, "h", completion.actual_amount, Some("ml")).unwrap();
+        settle_completion(&conn, &mut completion).unwrap();
+
+        assert_eq!(completion.actual_amount, 0.5);
+        assert!(!completion.completed);
+    }
+
+    #[test]
+    fn logged_day_must_be_valid_and_within_the_habit() {
+        let conn = test_connection();
+        insert_test_habit(&conn, "h", &ALL_WEEKDAYS);
+        let latest_allowed = NaiveDate::from_ymd_opt(2024, 1, 10).unwrap();
+
+        assert!(validate_habit_date(&conn, "h", "2024-01-05", latest_allowed).is_ok());
+        assert!(validate_habit_date(&conn, "h", "2024/01/05", latest_allowed).is_err());
+        assert!(validate_habit_date(&conn, "h", "2024-01-11", latest_allowed).is_err());
+        assert!(validate_habit_date(&conn, "h", "2023-12-31", latest_allowed).is_err());
+        assert!(validate_habit_date(&conn, "missing", "2024-01-05", latest_allowed).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+use crate::commands::habits::{Frequency, Habit};
+use crate::database::{with_connection, AppState};
+use crate::timing::CommandTimer;
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A frequency a habit followed, starting on `effective_from`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrequencyChange {
+    pub id: i64,
+    pub habit_id: String,
+    pub frequency: Frequency,
+    pub effective_from: String,
+    pub created_at: String,
+}
+
+impl FrequencyChange {
+    /// Map a database row to a FrequencyChange struct
+    pub(crate) fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let frequency_value_str: String = row.get(3)?;
+
+        Ok(Self {
+            id: row.get(0)?,
+            habit_id: row.get(1)?,
+            frequency: Frequency {
+                freq_type: row.get(2)?,
+                value: serde_json::from_str(&frequency_value_str).unwrap_or(Value::Null),
+            },
+            effective_from: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    }
+
+    /// Insert with its original id, skipping it if that id or day is taken
+    pub(crate) fn restore(&self, conn: &Connection) -> Result<(), String> {
+        let frequency_value = serde_json::to_string(&self.frequency.value)
+            .map_err(|e| format!("Failed to serialize frequency value: {}", e))?;
+
+        conn.execute(
+            "INSERT OR IGNORE INTO habit_frequency_history
+                (id, habit_id, frequency_type, frequency_value, effective_from, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                self.id,
+                self.habit_id,
+                self.frequency.freq_type,
+                frequency_value,
+                self.effective_from,
+                self.created_at,
+            ],
+        )
+        .map_err(|e| format!("Failed to restore frequency change: {}", e))?;
+
+        Ok(())
+    }
+}
+
+fn upsert_frequency(
+    conn: &Connection,
+    habit_id: &str,
+    frequency: &Frequency,
+    effective_from: &str,
+) -> Result<(), String> {
+    let frequency_value = serde_json::to_string(&frequency.value)
+        .map_err(|e| format!("Failed to serialize frequency value: {}", e))?;
+
+    // A second change on the same day replaces the first
+    conn.execute(
+        "INSERT INTO habit_frequency_history (habit_id, frequency_type, frequency_value, effective_from)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(habit_id, effective_from) DO UPDATE SET
+            frequency_type = excluded.frequency_type,
+            frequency_value = excluded.frequency_value",
+        params![habit_id, frequency.freq_type, frequency_value, effective_from],
+    )
+    .map_err(|e| format!("Failed to record frequency change: {}", e))?;
+
+    Ok(())
+}
+
+/// Record that a habit switched from `previous` to its current frequency on `effective_from` (YYYY-MM-DD).
+/// The first change also stores `previous` from the habit's start date, so earlier days keep it.
+pub(crate) fn record_frequency_change(
+    conn: &Connection,
+    habit: &Habit,
+    previous: &Frequency,
+    effective_from: &str,
+) -> Result<(), String> {
+    let has_history = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM habit_frequency_history WHERE habit_id = ?1)",
+            params![habit.id],
+            |row| row.get::<_, bool>(0),
+        )
+        .map_err(|e| format!("Failed to query frequency history: {}", e))?;
+
+    if !has_history {
+        let start_date = habit.start_date.get(..10).unwrap_or(&habit.start_date);
+        upsert_frequency(conn, &habit.id, previous, start_date)?;
+    }
+
+    upsert_frequency(conn, &habit.id, &habit.frequency, effective_from)
+}
+
+/// Get a habit's frequency changes, oldest first
+pub(crate) fn get_frequency_history(
+    conn: &Connection,
+    habit_id: &str,
+) -> Result<Vec<FrequencyChange>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT * FROM habit_frequency_history WHERE habit_id = ?1 ORDER BY effective_from ASC",
+        )
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let history = stmt
+        .query_map(params![habit_id], FrequencyChange::from_row)
+        .map_err(|e| format!("Failed to query frequency history: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect frequency history: {}", e))?;
+
+    Ok(history)
+}
+
+/// The frequency a habit followed on a YYYY-MM-DD date.
+/// From the latest change onwards that's the habit's own frequency, and days before the
+/// first recorded change use the earliest known one.
+pub(crate) fn frequency_on<'a>(
+    habit: &'a Habit,
+    history: &'a [FrequencyChange],
+    date: &str,
+) -> &'a Frequency {
+    match history.iter().rposition(|change| change.effective_from.as_str() <= date) {
+        Some(index) if index + 1 < history.len() => &history[index].frequency,
+        Some(_) => &habit.frequency,
+        None => history.first().map_or(&habit.frequency, |change| &change.frequency),
+    }
+}
+
+/// Get the frequencies a habit has followed, oldest first. Empty if it never changed.
+#[tauri::command]
+pub async fn get_habit_frequency_history(
+    state: tauri::State<'_, AppState>,
+    habit_id: String,
+) -> Result<Vec<FrequencyChange>, String> {
+    let _timer = CommandTimer::start("get_habit_frequency_history");
+    with_connection(&state, |db| {
+        get_frequency_history(db, &habit_id)
+    })
+}
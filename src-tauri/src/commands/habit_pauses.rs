@@ -0,0 +1,184 @@
+use crate::commands::settings::{configured_now, load_settings_from_db};
+use crate::database::{with_connection, AppState, DatabaseError};
+use crate::timing::CommandTimer;
+use chrono::{Duration, NaiveDate};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HabitPause {
+    pub id: i64,
+    pub habit_id: String,
+    pub start_date: String,
+    pub end_date: Option<String>,
+    pub created_at: String,
+}
+
+impl HabitPause {
+    /// Map a database row to a HabitPause struct
+    pub(crate) fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        Ok(Self {
+            id: row.get(0)?,
+            habit_id: row.get(1)?,
+            start_date: row.get(2)?,
+            end_date: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    }
+
+    /// Whether this pause covers a YYYY-MM-DD date
+    pub(crate) fn covers(&self, date: &str) -> bool {
+        self.start_date.as_str() <= date
+            && self.end_date.as_deref().is_none_or(|end| date <= end)
+    }
+}
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date '{}', expected YYYY-MM-DD", date))
+}
+
+/// Get all pause periods for a habit, oldest first
+pub(crate) fn get_pauses_for_habit(
+    conn: &Connection,
+    habit_id: &str,
+) -> Result<Vec<HabitPause>, String> {
+    let mut stmt = conn
+        .prepare_cached("SELECT * FROM habit_pauses WHERE habit_id = ?1 ORDER BY start_date ASC")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let pauses = stmt
+        .query_map(params![habit_id], HabitPause::from_row)
+        .map_err(|e| format!("Failed to query habit pauses: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect habit pauses: {}", e))?;
+
+    Ok(pauses)
+}
+
+/// Whether a habit is paused on a YYYY-MM-DD date
+pub(crate) fn is_habit_paused_on(
+    conn: &Connection,
+    habit_id: &str,
+    date: &str,
+) -> Result<bool, String> {
+    let paused = conn
+        .query_row(
+            "SELECT 1 FROM habit_pauses
+             WHERE habit_id = ?1 AND start_date <= ?2 AND (end_date IS NULL OR end_date >= ?2)
+             LIMIT 1",
+            params![habit_id, date],
+            |_| Ok(()),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to query habit pauses: {}", e))?
+        .is_some();
+
+    Ok(paused)
+}
+
+/// Pause a habit from `from` until `to` (inclusive), or until resumed if `to` is None
+#[tauri::command]
+pub async fn pause_habit(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    from: String,
+    to: Option<String>,
+) -> Result<HabitPause, String> {
+    let start = parse_date(&from)?;
+    if let Some(to) = &to {
+        if parse_date(to)? < start {
+            return Err("Pause end date must not be before its start date".to_string());
+        }
+    }
+
+    with_connection(&state, |db| {
+        let habit_exists = db
+            .query_row("SELECT 1 FROM habits WHERE id = ?1", params![id], |_| Ok(()))
+            .optional()
+            .map_err(|e| format!("Failed to query habit: {}", e))?
+            .is_some();
+
+        if !habit_exists {
+            return Err(DatabaseError::not_found("Habit", &id).into());
+        }
+
+        // Open-ended ranges compare as running to the end of time
+        let overlaps = db
+            .query_row(
+                "SELECT 1 FROM habit_pauses
+                 WHERE habit_id = ?1
+                   AND start_date <= COALESCE(?3, '9999-12-31')
+                   AND COALESCE(end_date, '9999-12-31') >= ?2
+                 LIMIT 1",
+                params![id, from, to],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to query habit pauses: {}", e))?
+            .is_some();
+
+        if overlaps {
+            return Err("Pause overlaps an existing pause for this habit".to_string());
+        }
+
+        db.execute(
+            "INSERT INTO habit_pauses (habit_id, start_date, end_date) VALUES (?1, ?2, ?3)",
+            params![id, from, to],
+        )
+        .map_err(|e| format!("Failed to pause habit: {}", e))?;
+
+        db.query_row(
+            "SELECT * FROM habit_pauses WHERE id = ?1",
+            params![db.last_insert_rowid()],
+            HabitPause::from_row,
+        )
+        .map_err(|e| format!("Failed to query habit pause: {}", e))
+    })
+}
+
+/// End the pause covering today in the configured timezone. Returns false if the habit wasn't paused.
+#[tauri::command]
+pub async fn resume_habit(
+    state: tauri::State<'_, AppState>,
+    id: String,
+) -> Result<bool, String> {
+    let today = configured_now(load_settings_from_db(&state)?.as_ref()).date();
+
+    with_connection(&state, |db| {
+        let today_str = today.format("%Y-%m-%d").to_string();
+        let yesterday = (today - Duration::days(1)).format("%Y-%m-%d").to_string();
+
+        // A pause starting today never took effect, so drop it entirely
+        let removed = db
+            .execute(
+                "DELETE FROM habit_pauses WHERE habit_id = ?1 AND start_date = ?2",
+                params![id, today_str],
+            )
+            .map_err(|e| format!("Failed to resume habit: {}", e))?;
+
+        // Earlier pauses keep their history and end yesterday
+        let ended = db
+            .execute(
+                "UPDATE habit_pauses SET end_date = ?3
+                 WHERE habit_id = ?1 AND start_date < ?2 AND (end_date IS NULL OR end_date >= ?2)",
+                params![id, today_str, yesterday],
+            )
+            .map_err(|e| format!("Failed to resume habit: {}", e))?;
+
+        Ok(removed + ended > 0)
+    })
+}
+
+/// Get all pause periods for a habit
+#[tauri::command]
+pub async fn get_habit_pauses(
+    state: tauri::State<'_, AppState>,
+    habit_id: String,
+) -> Result<Vec<HabitPause>, String> {
+    let _timer = CommandTimer::start("get_habit_pauses");
+    with_connection(&state, |db| {
+        get_pauses_for_habit(db, &habit_id)
+    })
+}
@@ -0,0 +1,353 @@
+use crate::commands::habit_frequency_history::{frequency_on, get_frequency_history};
+use crate::commands::habit_pauses::{get_pauses_for_habit, is_habit_paused_on};
+use crate::commands::habits::{Frequency, Habit};
+use crate::commands::notifications::fill_reminder_times;
+use crate::commands::settings::{configured_now, configured_week_start, load_settings_from_db};
+use crate::database::{with_connection, AppState, DatabaseError};
+use crate::timing::CommandTimer;
+use crate::validation::ValidationError;
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// How far ahead to look for a habit's next occurrence before treating its schedule as ended
+const MAX_LOOKAHEAD_DAYS: usize = 3660;
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Parse the date part of a habit's start date, which may carry a time component
+fn habit_start_date(habit: &Habit) -> Option<NaiveDate> {
+    let date = habit.start_date.get(..10)?;
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// Whether a habit's frequency schedules it on `date`.
+/// Mirrors `HabitFrequencyManager.shouldCompleteOnDate` on the frontend.
+pub(crate) fn is_scheduled_on(habit: &Habit, date: NaiveDate) -> bool {
+    is_scheduled_with(habit, &habit.frequency, date)
+}
+
+/// Whether `frequency` schedules a habit on `date`, for days that followed an earlier frequency
+pub(crate) fn is_scheduled_with(habit: &Habit, frequency: &Frequency, date: NaiveDate) -> bool {
+    let Some(start) = habit_start_date(habit) else {
+        return false;
+    };
+
+    if date < start {
+        return false;
+    }
+
+    let value = &frequency.value;
+
+    match frequency.freq_type.as_str() {
+        // Value is the list of weekday names the habit runs on
+        "daily" => {
+            let weekday = date.format("%A").to_string().to_lowercase();
+            value
+                .as_array()
+                .is_some_and(|days| days.iter().any(|day| day.as_str() == Some(weekday.as_str())))
+        }
+        // Value is the list of days of the month
+        "specific_dates" => value
+            .as_array()
+            .is_some_and(|days| days.iter().any(|day| day.as_u64() == Some(date.day() as u64))),
+        // Any day counts toward the period quota
+        "x_times_per_period" => true,
+        "interval" => match value.get("interval").and_then(Value::as_i64) {
+            Some(interval) if interval > 0 => (date - start).num_days() % interval == 0,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// First and last day of the quota period containing `date`
+fn period_bounds(period: &str, date: NaiveDate, week_start: Weekday) -> Option<(NaiveDate, NaiveDate)> {
+    match period {
+        "week" => {
+            let offset = (7 + date.weekday().num_days_from_monday() - week_start.num_days_from_monday()) % 7;
+            let start = date - Duration::days(offset as i64);
+            Some((start, start + Duration::days(6)))
+        }
+        "month" => {
+            let start = date.with_day(1)?;
+            let end = start.checked_add_months(Months::new(1))?.pred_opt()?;
+            Some((start, end))
+        }
+        _ => None,
+    }
+}
+
+/// Whether an x_times_per_period habit already has enough completions in the period containing `date`
+fn is_quota_met(
+    conn: &Connection,
+    habit: &Habit,
+    date: NaiveDate,
+    week_start: Weekday,
+) -> Result<bool, String> {
+    let value = &habit.frequency.value;
+    let repetitions = value.get("repetitionsPerPeriod").and_then(Value::as_i64);
+    let bounds = value
+        .get("period")
+        .and_then(Value::as_str)
+        .and_then(|period| period_bounds(period, date, week_start));
+
+    let (Some(repetitions), Some((start, end))) = (repetitions, bounds) else {
+        return Ok(false);
+    };
+
+    let completed: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM habit_completions
+             WHERE habit_id = ?1 AND date BETWEEN ?2 AND ?3 AND completed = 1 AND skipped = 0",
+            params![habit.id, format_date(start), format_date(end)],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Failed to count habit completions: {}", e))?;
+
+    Ok(completed >= repetitions)
+}
+
+/// Whether a habit is scheduled on `date` and still waiting to be completed or skipped
+pub(crate) fn is_pending_on(
+    conn: &Connection,
+    habit: &Habit,
+    date: NaiveDate,
+    week_start: Weekday,
+) -> Result<bool, String> {
+    if !is_scheduled_on(habit, date) {
+        return Ok(false);
+    }
+
+    let date_str = format_date(date);
+
+    if is_habit_paused_on(conn, &habit.id, &date_str)? {
+        return Ok(false);
+    }
+
+    let handled = conn
+        .query_row(
+            "SELECT 1 FROM habit_completions
+             WHERE habit_id = ?1 AND date = ?2 AND (completed = 1 OR skipped = 1)",
+            params![habit.id, date_str],
+            |_| Ok(()),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to query habit completion: {}", e))?
+        .is_some();
+
+    if handled {
+        return Ok(false);
+    }
+
+    if habit.frequency.freq_type == "x_times_per_period" {
+        return Ok(!is_quota_met(conn, habit, date, week_start)?);
+    }
+
+    Ok(true)
+}
+
+/// Get the habits still pending on `date`
+pub(crate) fn habits_due_on(
+    conn: &Connection,
+    date: NaiveDate,
+    week_start: Weekday,
+) -> Result<Vec<Habit>, String> {
+    let mut stmt = conn
+        .prepare("SELECT * FROM habits ORDER BY created_at DESC")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let habits = stmt
+        .query_map([], Habit::from_row)
+        .map_err(|e| format!("Failed to query habits: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect habits: {}", e))?;
+
+    let mut due = Vec::new();
+    for habit in habits {
+        if is_pending_on(conn, &habit, date, week_start)? {
+            due.push(habit);
+        }
+    }
+
+    Ok(due)
+}
+
+/// Get habits scheduled today, in the configured timezone, that haven't been completed or skipped yet
+#[tauri::command]
+pub async fn get_habits_due_today(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<Habit>, String> {
+    let _timer = CommandTimer::start("get_habits_due_today");
+    let settings = load_settings_from_db(&state)?;
+    // Weekday schedules follow the configured timezone's calendar
+    let today = configured_now(settings.as_ref()).date();
+    let week_start = configured_week_start(settings.as_ref());
+
+    with_connection(&state, |db| {
+        let mut habits = habits_due_on(db, today, week_start)?;
+        fill_reminder_times(db, &mut habits)?;
+
+        Ok(habits)
+    })
+}
+
+/// Next date on or after `from` that the habit is due. Today only counts while still pending.
+/// None when the habit is paused indefinitely or never comes up again within the lookahead.
+pub(crate) fn next_occurrence(
+    conn: &Connection,
+    habit: &Habit,
+    from: NaiveDate,
+    week_start: Weekday,
+) -> Result<Option<NaiveDate>, String> {
+    if is_pending_on(conn, habit, from, week_start)? {
+        return Ok(Some(from));
+    }
+
+    let pauses = get_pauses_for_habit(conn, &habit.id)?;
+
+    for date in from.iter_days().skip(1).take(MAX_LOOKAHEAD_DAYS) {
+        let date_str = format_date(date);
+
+        if pauses
+            .iter()
+            .any(|pause| pause.end_date.is_none() && pause.start_date <= date_str)
+        {
+            return Ok(None);
+        }
+
+        if !is_scheduled_on(habit, date) || pauses.iter().any(|pause| pause.covers(&date_str)) {
+            continue;
+        }
+
+        if habit.frequency.freq_type == "x_times_per_period"
+            && is_quota_met(conn, habit, date, week_start)?
+        {
+            continue;
+        }
+
+        return Ok(Some(date));
+    }
+
+    Ok(None)
+}
+
+/// Get the next date a habit is due, counting from today in the configured timezone
+#[tauri::command]
+pub async fn get_next_occurrence(
+    state: tauri::State<'_, AppState>,
+    habit_id: String,
+) -> Result<Option<String>, String> {
+    let _timer = CommandTimer::start("get_next_occurrence");
+    let settings = load_settings_from_db(&state)?;
+    let today = configured_now(settings.as_ref()).date();
+    let week_start = configured_week_start(settings.as_ref());
+
+    with_connection(&state, |db| {
+        let habit = db
+            .query_row(
+                "SELECT * FROM habits WHERE id = ?1",
+                params![habit_id],
+                Habit::from_row,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to query habit: {}", e))?
+            .ok_or_else(|| DatabaseError::not_found("Habit", &habit_id))?;
+
+        Ok(next_occurrence(db, &habit, today, week_start)?.map(format_date))
+    })
+}
+
+/// Get the dates between `start_date` and `end_date` (YYYY-MM-DD, inclusive) on which a habit
+/// was scheduled but neither completed nor skipped. Days before its start date, paused days
+/// and days after today are never missed.
+#[tauri::command]
+pub async fn get_missed_dates(
+    state: tauri::State<'_, AppState>,
+    habit_id: String,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<String>, String> {
+    let _timer = CommandTimer::start("get_missed_dates");
+    let start = NaiveDate::parse_from_str(&start_date, "%Y-%m-%d")
+        .map_err(|_| ValidationError::InvalidDate(start_date.clone()))?;
+    let end = NaiveDate::parse_from_str(&end_date, "%Y-%m-%d")
+        .map_err(|_| ValidationError::InvalidDate(end_date.clone()))?;
+    if start > end {
+        return Err("Start date must not be after end date".to_string());
+    }
+
+    let today = configured_now(load_settings_from_db(&state)?.as_ref()).date();
+    let end = end.min(today);
+
+    with_connection(&state, |db| {
+        let habit = db
+            .query_row(
+                "SELECT * FROM habits WHERE id = ?1",
+                params![habit_id],
+                Habit::from_row,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to query habit: {}", e))?
+            .ok_or_else(|| DatabaseError::not_found("Habit", &habit_id))?;
+
+        let mut stmt = db
+            .prepare(
+                "SELECT date FROM habit_completions
+                 WHERE habit_id = ?1 AND date BETWEEN ?2 AND ?3 AND (completed = 1 OR skipped = 1)",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let handled = stmt
+            .query_map(params![habit_id, start_date, format_date(end)], |row| {
+                row.get::<_, String>(0)
+            })
+            .map_err(|e| format!("Failed to query habit completions: {}", e))?
+            .collect::<Result<HashSet<_>, _>>()
+            .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
+
+        let pauses = get_pauses_for_habit(db, &habit_id)?;
+        let frequency_history = get_frequency_history(db, &habit_id)?;
+
+        let mut missed = Vec::new();
+        for day in start.iter_days().take_while(|day| *day <= end) {
+            let day_str = format_date(day);
+            let frequency = frequency_on(&habit, &frequency_history, &day_str);
+
+            if is_scheduled_with(&habit, frequency, day)
+                && !pauses.iter().any(|pause| pause.covers(&day_str))
+                && !handled.contains(&day_str)
+            {
+                missed.push(day_str);
+            }
+        }
+
+        Ok(missed)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{insert_test_habit, test_connection};
+
+    #[test]
+    fn mon_wed_fri_habit_is_due_only_on_those_days() {
+        let conn = test_connection();
+        insert_test_habit(&conn, "h", &["monday", "wednesday", "friday"]);
+        let habit = Habit::find(&conn, "h").unwrap().unwrap();
+
+        // Two weeks from Monday 2024-01-01
+        let first_monday = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        for date in first_monday.iter_days().take(14) {
+            let expected = matches!(date.weekday(), Weekday::Mon | Weekday::Wed | Weekday::Fri);
+            let due = habits_due_on(&conn, date, Weekday::Mon).unwrap();
+
+            assert_eq!(is_scheduled_on(&habit, date), expected, "{}", date);
+            assert_eq!(due.len(), usize::from(expected), "{}", date);
+        }
+    }
+}
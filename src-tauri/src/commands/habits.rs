@@ -1,7 +1,30 @@
-use crate::database::AppState;
-use rusqlite::{params, OptionalExtension, Row};
+use crate::commands::habit_completions::{calculate_streaks, HabitCompletion};
+use crate::commands::habit_frequency_history::{
+    frequency_on, get_frequency_history, record_frequency_change,
+};
+use crate::commands::habit_pauses::get_pauses_for_habit;
+use crate::commands::habit_schedule::is_scheduled_with;
+use crate::commands::idempotency::{find_idempotent_id, remember_idempotent_id};
+use crate::commands::notifications::fill_reminder_times;
+use crate::commands::settings::{
+    configured_now, configured_row_limit, configured_streak_grace_days, load_settings_from_db,
+};
+use crate::database::{
+    distinct_categories, ensure_below_row_limit, rename_category, with_connection, AppState,
+    DatabaseError,
+};
+use crate::events::{emit_data_changed, Action, Entity};
+use crate::timing::CommandTimer;
+use crate::validation::{
+    normalize_color, validate_completion_threshold, validate_icon, validate_weekdays,
+    ValidationError,
+};
+use chrono::{Duration, NaiveDate};
+use std::collections::HashMap;
+use rusqlite::{params, Connection, OptionalExtension, Row};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use tauri::AppHandle;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -21,6 +44,30 @@ pub struct Habit {
     pub reminder: Reminder,
     pub created_at: String,
     pub updated_at: String,
+    /// Share of the target an amount must reach for the day to count as completed (0.0 - 1.0)
+    #[serde(default = "default_completion_threshold")]
+    pub completion_threshold: f64,
+}
+
+/// Habits count as completed only once the whole target is reached unless they say otherwise
+pub(crate) fn default_completion_threshold() -> f64 {
+    1.0
+}
+
+/// Weight of each day relative to the day after it in `get_consistency_score`.
+/// At 0.9 a day a week back counts about half as much as today, and one a month back about 4%.
+pub const DEFAULT_CONSISTENCY_DECAY: f64 = 0.9;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HabitDetail {
+    pub habit: Habit,
+    pub current_streak: i32,
+    pub longest_streak: i32,
+    /// Share of scheduled, unpaused days in the window that were completed (0.0 - 1.0)
+    pub completion_rate: f64,
+    /// Completions within the window, newest first
+    pub recent_completions: Vec<HabitCompletion>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,12 +80,28 @@ pub struct Frequency {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Reminder {
     pub enabled: bool,
+    /// Earliest reminder time. Every time is a schedule row, see `set_habit_reminders`.
     pub time: String,
+    /// Every daily reminder time, earliest first. Read from the schedules and ignored when saving a habit.
+    #[serde(default)]
+    pub times: Vec<String>,
+}
+
+impl Frequency {
+    /// Check the parts of the value the backend relies on.
+    /// A "daily" value lists the weekdays the habit runs on, e.g. `["monday", "wednesday", "friday"]`.
+    pub(crate) fn validate(&self) -> Result<(), ValidationError> {
+        if self.freq_type == "daily" {
+            validate_weekdays(&self.value)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl Habit {
     /// Map a database row to a Habit struct
-    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+    pub(crate) fn from_row(row: &Row) -> rusqlite::Result<Self> {
         let frequency_value_str: String = row.get(8)?;
         let linked_goals_str: String = row.get(11)?;
 
@@ -61,9 +124,11 @@ impl Habit {
             reminder: Reminder {
                 enabled: row.get::<_, i32>(13)? != 0,
                 time: row.get(14)?,
+                times: Vec::new(),
             },
             created_at: row.get(15)?,
             updated_at: row.get(16)?,
+            completion_threshold: row.get(17)?,
         })
     }
 
@@ -78,106 +143,201 @@ impl Habit {
         serde_json::to_string(&self.linked_goals)
             .map_err(|e| format!("Failed to serialize linked goals: {}", e))
     }
+
+    /// Insert this habit as a new row
+    pub(crate) fn insert(&self, conn: &Connection) -> Result<(), String> {
+        let frequency_value = self.serialize_frequency_value()?;
+        let linked_goals = self.serialize_linked_goals()?;
+
+        conn.execute(
+            "INSERT INTO habits (
+                id, name, category, icon, color, target_amount, unit,
+                frequency_type, frequency_value, priority, notes, linked_goals,
+                start_date, reminder_enabled, reminder_time, created_at, updated_at,
+                completion_threshold
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+            params![
+                self.id,
+                self.name,
+                self.category,
+                self.icon,
+                self.color,
+                self.target_amount,
+                self.unit,
+                self.frequency.freq_type,
+                frequency_value,
+                self.priority,
+                self.notes,
+                linked_goals,
+                self.start_date,
+                self.reminder.enabled as i32,
+                self.reminder.time,
+                self.created_at,
+                self.updated_at,
+                self.completion_threshold,
+            ],
+        )
+        .map_err(|e| format!("Failed to create habit: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Look up a habit by id
+    pub(crate) fn find(conn: &Connection, id: &str) -> Result<Option<Self>, String> {
+        conn.query_row("SELECT * FROM habits WHERE id = ?1", params![id], Self::from_row)
+            .optional()
+            .map_err(|e| format!("Failed to query habit: {}", e))
+    }
 }
 
+/// Create a habit. Retrying with the same `idempotency_key` within a day returns the
+/// habit the first call created instead of adding another.
 #[tauri::command]
 pub async fn create_habit(
     state: tauri::State<'_, AppState>,
-    habit: Habit,
+    app_handle: AppHandle,
+    mut habit: Habit,
+    idempotency_key: Option<String>,
 ) -> Result<Habit, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    let frequency_value = habit.serialize_frequency_value()?;
-    let linked_goals = habit.serialize_linked_goals()?;
-
-    db.execute(
-        "INSERT INTO habits (
-            id, name, category, icon, color, target_amount, unit,
-            frequency_type, frequency_value, priority, notes, linked_goals,
-            start_date, reminder_enabled, reminder_time, created_at, updated_at
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
-        params![
-            habit.id,
-            habit.name,
-            habit.category,
-            habit.icon,
-            habit.color,
-            habit.target_amount,
-            habit.unit,
-            habit.frequency.freq_type,
-            frequency_value,
-            habit.priority,
-            habit.notes,
-            linked_goals,
-            habit.start_date,
-            habit.reminder.enabled as i32,
-            habit.reminder.time,
-            habit.created_at,
-            habit.updated_at,
-        ],
-    )
-    .map_err(|e| format!("Failed to create habit: {}", e))?;
-
-    Ok(habit)
+    habit.color = normalize_color(&habit.color)?;
+    validate_icon(&habit.icon)?;
+    validate_completion_threshold(habit.completion_threshold)?;
+    habit.frequency.validate()?;
+    let row_limit = configured_row_limit(load_settings_from_db(&state)?.as_ref());
+    let key = idempotency_key.as_deref();
+
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        if let Some(id) = find_idempotent_id(&tx, key, "habit")? {
+            if let Some(existing) = Habit::find(&tx, &id)? {
+                return Ok(existing);
+            }
+        }
+
+        ensure_below_row_limit(&tx, "habits", row_limit)?;
+        habit.insert(&tx)?;
+        remember_idempotent_id(&tx, key, "habit", &habit.id)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        emit_data_changed(&app_handle, Entity::Habit, Action::Create, &habit.id);
+
+        Ok(habit)
+    })
 }
 
 #[tauri::command]
 pub async fn update_habit(
     state: tauri::State<'_, AppState>,
-    habit: Habit,
+    app_handle: AppHandle,
+    mut habit: Habit,
+    expected_updated_at: Option<String>,
 ) -> Result<Habit, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    let frequency_value = habit.serialize_frequency_value()?;
-    let linked_goals = habit.serialize_linked_goals()?;
-
-    let rows = db.execute(
-        "UPDATE habits SET
-            name = ?1, category = ?2, icon = ?3, color = ?4,
-            target_amount = ?5, unit = ?6, frequency_type = ?7, frequency_value = ?8,
-            priority = ?9, notes = ?10, linked_goals = ?11, start_date = ?12,
-            reminder_enabled = ?13, reminder_time = ?14, updated_at = ?15
-        WHERE id = ?16",
-        params![
-            habit.name,
-            habit.category,
-            habit.icon,
-            habit.color,
-            habit.target_amount,
-            habit.unit,
-            habit.frequency.freq_type,
-            frequency_value,
-            habit.priority,
-            habit.notes,
-            linked_goals,
-            habit.start_date,
-            habit.reminder.enabled as i32,
-            habit.reminder.time,
-            habit.updated_at,
-            habit.id,
-        ],
-    )
-    .map_err(|e| format!("Failed to update habit: {}", e))?;
-
-    if rows == 0 {
-        return Err(format!("Habit with id '{}' not found", habit.id));
-    }
+    habit.color = normalize_color(&habit.color)?;
+    validate_icon(&habit.icon)?;
+    validate_completion_threshold(habit.completion_threshold)?;
+    habit.frequency.validate()?;
+    let today = configured_now(load_settings_from_db(&state)?.as_ref()).date();
 
-    Ok(habit)
+    with_connection(&state, |db| {
+        let frequency_value = habit.serialize_frequency_value()?;
+        let linked_goals = habit.serialize_linked_goals()?;
+
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        let previous = tx
+            .query_row(
+                "SELECT frequency_type, frequency_value FROM habits WHERE id = ?1",
+                params![habit.id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Failed to query habit: {}", e))?
+            .ok_or_else(|| DatabaseError::not_found("Habit", &habit.id))?;
+
+        let rows = tx.execute(
+            "UPDATE habits SET
+                name = ?1, category = ?2, icon = ?3, color = ?4,
+                target_amount = ?5, unit = ?6, frequency_type = ?7, frequency_value = ?8,
+                priority = ?9, notes = ?10, linked_goals = ?11, start_date = ?12,
+                reminder_enabled = ?13, reminder_time = ?14, updated_at = ?15,
+                completion_threshold = ?16
+            WHERE id = ?17 AND (?18 IS NULL OR updated_at = ?18)",
+            params![
+                habit.name,
+                habit.category,
+                habit.icon,
+                habit.color,
+                habit.target_amount,
+                habit.unit,
+                habit.frequency.freq_type,
+                frequency_value,
+                habit.priority,
+                habit.notes,
+                linked_goals,
+                habit.start_date,
+                habit.reminder.enabled as i32,
+                habit.reminder.time,
+                habit.updated_at,
+                habit.completion_threshold,
+                habit.id,
+                expected_updated_at,
+            ],
+        )
+        .map_err(|e| format!("Failed to update habit: {}", e))?;
+
+        // The habit was found above, so no match means it changed since the caller loaded it
+        if rows == 0 {
+            return Err(DatabaseError::conflict("Habit", &habit.id).into());
+        }
+
+        // Past days keep the frequency they followed, so completion rates aren't rewritten
+        if previous != (habit.frequency.freq_type.clone(), frequency_value) {
+            let previous_frequency = Frequency {
+                freq_type: previous.0,
+                value: serde_json::from_str(&previous.1).unwrap_or(Value::Null),
+            };
+            record_frequency_change(
+                &tx,
+                &habit,
+                &previous_frequency,
+                &today.format("%Y-%m-%d").to_string(),
+            )?;
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        emit_data_changed(&app_handle, Entity::Habit, Action::Update, &habit.id);
+
+        Ok(habit)
+    })
 }
 
 #[tauri::command]
 pub async fn delete_habit(
     state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
     id: String,
 ) -> Result<bool, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    with_connection(&state, |db| {
+        let deleted = delete_habit_row(db, &id)?;
+
+        if deleted {
+            emit_data_changed(&app_handle, Entity::Habit, Action::Delete, &id);
+        }
+
+        Ok(deleted)
+    })
+}
 
-    // Habit completions will be automatically deleted due to ON DELETE CASCADE
-    let rows_affected = db
+/// Delete a habit row. Completions and notifications are removed by ON DELETE CASCADE.
+pub(crate) fn delete_habit_row(conn: &Connection, id: &str) -> Result<bool, String> {
+    let rows_affected = conn
         .execute("DELETE FROM habits WHERE id = ?1", params![id])
         .map_err(|e| format!("Failed to delete habit: {}", e))?;
 
@@ -188,20 +348,22 @@ pub async fn delete_habit(
 pub async fn get_all_habits(
     state: tauri::State<'_, AppState>,
 ) -> Result<Vec<Habit>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    let _timer = CommandTimer::start("get_all_habits");
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare("SELECT * FROM habits ORDER BY created_at DESC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    let mut stmt = db
-        .prepare("SELECT * FROM habits ORDER BY created_at DESC")
-        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+        let mut habits = stmt
+            .query_map([], Habit::from_row)
+            .map_err(|e| format!("Failed to query habits: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect habits: {}", e))?;
 
-    let habits = stmt
-        .query_map([], Habit::from_row)
-        .map_err(|e| format!("Failed to query habits: {}", e))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to collect habits: {}", e))?;
+        fill_reminder_times(db, &mut habits)?;
 
-    Ok(habits)
+        Ok(habits)
+    })
 }
 
 #[tauri::command]
@@ -209,19 +371,13 @@ pub async fn get_habit_by_id(
     state: tauri::State<'_, AppState>,
     id: String,
 ) -> Result<Option<Habit>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    let habit = db
-        .query_row(
-            "SELECT * FROM habits WHERE id = ?1",
-            params![id],
-            Habit::from_row,
-        )
-        .optional()
-        .map_err(|e| format!("Failed to query habit: {}", e))?;
+    let _timer = CommandTimer::start("get_habit_by_id");
+    with_connection(&state, |db| {
+        let mut habit = Habit::find(db, &id)?;
+        fill_reminder_times(db, habit.as_mut_slice())?;
 
-    Ok(habit)
+        Ok(habit)
+    })
 }
 
 #[tauri::command]
@@ -229,18 +385,290 @@ pub async fn get_habits_by_category(
     state: tauri::State<'_, AppState>,
     category: String,
 ) -> Result<Vec<Habit>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    let _timer = CommandTimer::start("get_habits_by_category");
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare("SELECT * FROM habits WHERE category = ?1 ORDER BY created_at DESC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let mut habits = stmt
+            .query_map(params![category], Habit::from_row)
+            .map_err(|e| format!("Failed to query habits: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect habits: {}", e))?;
+
+        fill_reminder_times(db, &mut habits)?;
+
+        Ok(habits)
+    })
+}
+
+/// Rename a habit category on every habit that uses it, returning how many changed
+#[tauri::command]
+pub async fn rename_habit_category(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    old: String,
+    new: String,
+) -> Result<usize, String> {
+    with_connection(&state, |db| {
+        let ids = rename_category(db, "habits", &old, &new)?;
+
+        for id in &ids {
+            emit_data_changed(&app_handle, Entity::Habit, Action::Update, id);
+        }
 
-    let mut stmt = db
-        .prepare("SELECT * FROM habits WHERE category = ?1 ORDER BY created_at DESC")
-        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+        Ok(ids.len())
+    })
+}
 
-    let habits = stmt
-        .query_map(params![category], Habit::from_row)
-        .map_err(|e| format!("Failed to query habits: {}", e))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to collect habits: {}", e))?;
+/// Get every category used by a habit, sorted, for the category picker to offer
+#[tauri::command]
+pub async fn get_habit_categories(state: tauri::State<'_, AppState>) -> Result<Vec<String>, String> {
+    let _timer = CommandTimer::start("get_habit_categories");
+    with_connection(&state, |db| distinct_categories(db, "habits"))
+}
 
-    Ok(habits)
-}
\ No newline at end of file
+/// Get habits whose linked_goals array contains the goal ID
+#[tauri::command]
+pub async fn get_habits_by_linked_goal(
+    state: tauri::State<'_, AppState>,
+    goal_id: String,
+) -> Result<Vec<Habit>, String> {
+    let _timer = CommandTimer::start("get_habits_by_linked_goal");
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare(
+                "SELECT h.* FROM habit_goals hg
+                 INNER JOIN habits h ON h.id = hg.habit_id
+                 WHERE hg.goal_id = ?1
+                 ORDER BY h.created_at DESC",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let mut habits = stmt
+            .query_map(params![goal_id], Habit::from_row)
+            .map_err(|e| format!("Failed to query habits: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect habits: {}", e))?;
+
+        fill_reminder_times(db, &mut habits)?;
+
+        Ok(habits)
+    })
+}
+
+/// Share of the days a habit was scheduled between `start` and `end` (inclusive) that were completed.
+/// Paused and skipped days are left out, and no scheduled days gives 0.0.
+pub(crate) fn completion_rate(
+    conn: &Connection,
+    habit: &Habit,
+    completions: &[HabitCompletion],
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<f64, String> {
+    let pauses = get_pauses_for_habit(conn, &habit.id)?;
+    let frequency_history = get_frequency_history(conn, &habit.id)?;
+    let mut scheduled = 0;
+    let mut completed = 0;
+
+    for day in start.iter_days().take_while(|day| *day <= end) {
+        let day_str = day.format("%Y-%m-%d").to_string();
+        let frequency = frequency_on(habit, &frequency_history, &day_str);
+        if !is_scheduled_with(habit, frequency, day) || pauses.iter().any(|pause| pause.covers(&day_str)) {
+            continue;
+        }
+
+        // Skipped days are intentional rest days and don't count against the rate
+        match completions.iter().find(|c| c.date == day_str) {
+            Some(c) if c.skipped => continue,
+            Some(c) if c.completed => {
+                scheduled += 1;
+                completed += 1;
+            }
+            _ => scheduled += 1,
+        }
+    }
+
+    Ok(if scheduled > 0 {
+        completed as f64 / scheduled as f64
+    } else {
+        0.0
+    })
+}
+
+/// Get a habit with its streaks, completion rate and completions over the last `recent_days` days
+#[tauri::command]
+pub async fn get_habit_detail(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    recent_days: i32,
+) -> Result<Option<HabitDetail>, String> {
+    let _timer = CommandTimer::start("get_habit_detail");
+    let settings = load_settings_from_db(&state)?;
+    let today = configured_now(settings.as_ref()).date();
+    let grace_days = configured_streak_grace_days(settings.as_ref());
+
+    with_connection(&state, |db| {
+        let mut habit = match db
+            .query_row(
+                "SELECT * FROM habits WHERE id = ?1",
+                params![id],
+                Habit::from_row,
+            )
+            .optional()
+            .map_err(|e| format!("Failed to query habit: {}", e))?
+        {
+            Some(habit) => habit,
+            None => return Ok(None),
+        };
+        fill_reminder_times(db, std::slice::from_mut(&mut habit))?;
+
+        let (current_streak, longest_streak) = calculate_streaks(db, &id, grace_days)?;
+
+        let window_start = today - Duration::days(recent_days.clamp(1, 3650) as i64 - 1);
+        let window_start_str = window_start.format("%Y-%m-%d").to_string();
+        let today_str = today.format("%Y-%m-%d").to_string();
+
+        let mut stmt = db
+            .prepare(
+                "SELECT * FROM habit_completions
+                 WHERE habit_id = ?1 AND date BETWEEN ?2 AND ?3
+                 ORDER BY date DESC",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let recent_completions = stmt
+            .query_map(params![id, window_start_str, today_str], HabitCompletion::from_row)
+            .map_err(|e| format!("Failed to query habit completions: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
+
+        let completion_rate = completion_rate(db, &habit, &recent_completions, window_start, today)?;
+
+        Ok(Some(HabitDetail {
+            habit,
+            current_streak,
+            longest_streak,
+            completion_rate,
+            recent_completions,
+        }))
+    })
+}
+
+/// Get how consistently a habit was completed over the last `window_days` days, from 0.0 to 1.0.
+/// Each scheduled day is weighted by `decay` (default `DEFAULT_CONSISTENCY_DECAY`, 1.0 for no
+/// weighting) raised to how many days ago it was, so recent days count more. Days that weren't
+/// scheduled, were paused or were skipped don't count, and neither does today until it's completed.
+#[tauri::command]
+pub async fn get_consistency_score(
+    state: tauri::State<'_, AppState>,
+    habit_id: String,
+    window_days: i32,
+    decay: Option<f64>,
+) -> Result<f64, String> {
+    let _timer = CommandTimer::start("get_consistency_score");
+    let decay = decay.unwrap_or(DEFAULT_CONSISTENCY_DECAY);
+    if !(decay > 0.0 && decay <= 1.0) {
+        return Err(ValidationError::InvalidConsistencyDecay(decay).into());
+    }
+
+    let today = configured_now(load_settings_from_db(&state)?.as_ref()).date();
+    let window_start = today - Duration::days(window_days.clamp(1, 3650) as i64 - 1);
+
+    with_connection(&state, |db| {
+        let habit = Habit::find(db, &habit_id)?
+            .ok_or_else(|| DatabaseError::not_found("Habit", &habit_id))?;
+
+        let mut stmt = db
+            .prepare(
+                "SELECT date, completed, skipped FROM habit_completions
+                 WHERE habit_id = ?1 AND date BETWEEN ?2 AND ?3",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let completions = stmt
+            .query_map(
+                params![
+                    habit_id,
+                    window_start.format("%Y-%m-%d").to_string(),
+                    today.format("%Y-%m-%d").to_string(),
+                ],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        (row.get::<_, i32>(1)? != 0, row.get::<_, i32>(2)? != 0),
+                    ))
+                },
+            )
+            .map_err(|e| format!("Failed to query habit completions: {}", e))?
+            .collect::<Result<HashMap<_, _>, _>>()
+            .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
+
+        let pauses = get_pauses_for_habit(db, &habit_id)?;
+        let frequency_history = get_frequency_history(db, &habit_id)?;
+        let mut scheduled_weight = 0.0;
+        let mut completed_weight = 0.0;
+
+        for day in window_start.iter_days().take_while(|day| *day <= today) {
+            let day_str = day.format("%Y-%m-%d").to_string();
+            let frequency = frequency_on(&habit, &frequency_history, &day_str);
+            if !is_scheduled_with(&habit, frequency, day) || pauses.iter().any(|pause| pause.covers(&day_str)) {
+                continue;
+            }
+
+            let (completed, skipped) = completions.get(&day_str).copied().unwrap_or_default();
+            if skipped || (day == today && !completed) {
+                continue;
+            }
+
+            let weight = decay.powi((today - day).num_days() as i32);
+            scheduled_weight += weight;
+            if completed {
+                completed_weight += weight;
+            }
+        }
+
+        Ok(if scheduled_weight > 0.0 {
+            completed_weight / scheduled_weight
+        } else {
+            0.0
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{insert_test_habit, test_connection, ALL_WEEKDAYS};
+
+    #[test]
+    fn skipped_day_keeps_the_streak_and_leaves_the_rate() {
+        let conn = test_connection();
+        insert_test_habit(&conn, "h", &ALL_WEEKDAYS);
+        for day in 1..=7 {
+            conn.execute(
+                "INSERT INTO habit_completions (id, habit_id, date, completed, skipped, created_at, updated_at)
+                 VALUES (?1, 'h', ?1, ?2, ?3, ?1, ?1)",
+                params![format!("2024-01-0{}", day), day != 4, day == 4],
+            )
+            .unwrap();
+        }
+
+        // The skipped Thursday bridges the run without adding to it
+        assert_eq!(calculate_streaks(&conn, "h", 0).unwrap(), (6, 6));
+
+        let habit = Habit::find(&conn, "h").unwrap().unwrap();
+        let completions = conn
+            .prepare("SELECT * FROM habit_completions WHERE habit_id = 'h'")
+            .unwrap()
+            .query_map([], HabitCompletion::from_row)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let week_start = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        let week_end = NaiveDate::from_ymd_opt(2024, 1, 7).unwrap();
+
+        assert_eq!(completion_rate(&conn, &habit, &completions, week_start, week_end).unwrap(), 1.0);
+    }
+}
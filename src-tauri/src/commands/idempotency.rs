@@ -0,0 +1,63 @@
+use rusqlite::{params, Connection, OptionalExtension};
+
+/// How long a key keeps returning the entity it first created, as an SQLite date modifier
+const KEY_LIFETIME: &str = "-1 day";
+
+/// Get the id of the entity an unexpired `key` already created, if any.
+/// Reusing a key for a different kind of entity is an error rather than a silent replay.
+pub(crate) fn find_idempotent_id(
+    conn: &Connection,
+    key: Option<&str>,
+    entity_type: &str,
+) -> Result<Option<String>, String> {
+    let Some(key) = key else {
+        return Ok(None);
+    };
+
+    let existing = conn
+        .query_row(
+            "SELECT entity_type, entity_id FROM idempotency
+             WHERE key = ?1 AND created_at > datetime('now', ?2)",
+            params![key, KEY_LIFETIME],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to query idempotency key: {}", e))?;
+
+    match existing {
+        Some((existing_type, _)) if existing_type != entity_type => Err(format!(
+            "Idempotency key '{}' was already used to create a {}",
+            key, existing_type
+        )),
+        Some((_, entity_id)) => Ok(Some(entity_id)),
+        None => Ok(None),
+    }
+}
+
+/// Remember that `key` created `entity_id`, dropping expired keys along the way
+pub(crate) fn remember_idempotent_id(
+    conn: &Connection,
+    key: Option<&str>,
+    entity_type: &str,
+    entity_id: &str,
+) -> Result<(), String> {
+    let Some(key) = key else {
+        return Ok(());
+    };
+
+    conn.execute(
+        "DELETE FROM idempotency WHERE created_at <= datetime('now', ?1)",
+        params![KEY_LIFETIME],
+    )
+    .map_err(|e| format!("Failed to remove expired idempotency keys: {}", e))?;
+
+    // A key whose entity was deleted since is pointed at the new one
+    conn.execute(
+        "INSERT OR REPLACE INTO idempotency (key, entity_type, entity_id, created_at)
+         VALUES (?1, ?2, ?3, datetime('now'))",
+        params![key, entity_type, entity_id],
+    )
+    .map_err(|e| format!("Failed to save idempotency key: {}", e))?;
+
+    Ok(())
+}
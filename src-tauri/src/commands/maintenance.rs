@@ -0,0 +1,164 @@
+use crate::commands::settings::{ensure_default_settings, refresh_close_behavior};
+use crate::commands::sync::refresh_sync_server;
+use crate::commands::undo::DeletedGoals;
+use crate::commands::user_data::get_user_data_path;
+use crate::database::{create_indexes, with_connection, AppState};
+use rusqlite::{Connection, Transaction};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs;
+use tauri::{AppHandle, Manager};
+
+/// Phrase the frontend must send back before a factory reset goes ahead
+const FACTORY_RESET_CONFIRMATION: &str = "DELETE ALL DATA";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResetSummary {
+    pub goals: usize,
+    pub tasks: usize,
+    pub habits: usize,
+    pub completions: usize,
+    pub attachments: usize,
+    pub focus_sessions: usize,
+    pub habit_pauses: usize,
+    pub habit_frequency_history: usize,
+    pub notification_schedules: usize,
+    pub notification_history: usize,
+    pub settings: usize,
+    pub user_config_deleted: bool,
+}
+
+/// Flush the WAL into the main database file so backups and exports see consistent bytes.
+/// Returns false if another connection kept the checkpoint from completing.
+#[tauri::command]
+pub async fn flush_database(state: tauri::State<'_, AppState>) -> Result<bool, String> {
+    with_connection(&state, |db| {
+        let busy: i32 = db
+            .query_row("PRAGMA wal_checkpoint(FULL)", [], |row| row.get(0))
+            .map_err(|e| format!("Failed to checkpoint database: {}", e))?;
+
+        Ok(busy == 0)
+    })
+}
+
+/// Names of every index in the database, including SQLite's automatic ones
+fn index_names(conn: &Connection) -> Result<HashSet<String>, String> {
+    let mut stmt = conn
+        .prepare("SELECT name FROM sqlite_master WHERE type = 'index'")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let names = stmt
+        .query_map([], |row| row.get(0))
+        .map_err(|e| format!("Failed to query indexes: {}", e))?
+        .collect::<Result<HashSet<String>, _>>()
+        .map_err(|e| format!("Failed to collect indexes: {}", e))?;
+
+    Ok(names)
+}
+
+/// Create any index this version expects that the database is missing, then refresh the
+/// query planner's statistics. Returns the names of the indexes that were created, sorted.
+#[tauri::command]
+pub async fn rebuild_indexes(state: tauri::State<'_, AppState>) -> Result<Vec<String>, String> {
+    with_connection(&state, |db| {
+        let existing = index_names(db)?;
+
+        create_indexes(db).map_err(|e| format!("Failed to create indexes: {}", e))?;
+
+        let mut created: Vec<String> = index_names(db)?
+            .into_iter()
+            .filter(|name| !existing.contains(name))
+            .collect();
+        created.sort();
+
+        if !created.is_empty() {
+            log::info!("Created missing indexes: {}", created.join(", "));
+        }
+
+        db.execute_batch("ANALYZE")
+            .map_err(|e| format!("Failed to analyze database: {}", e))?;
+
+        Ok(created)
+    })
+}
+
+/// Delete every row of a table, returning how many there were.
+/// Rows are counted up front because cascaded deletes aren't included in the change count.
+fn clear_table(tx: &Transaction, table: &str) -> Result<usize, String> {
+    let count: i64 = tx
+        .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+        .map_err(|e| format!("Failed to count {}: {}", table, e))?;
+
+    tx.execute(&format!("DELETE FROM {}", table), [])
+        .map_err(|e| format!("Failed to clear {}: {}", table, e))?;
+
+    Ok(count as usize)
+}
+
+/// Remove all app data and the user config. Only runs when given the exact confirmation phrase.
+#[tauri::command]
+pub async fn factory_reset(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    confirmation: String,
+) -> Result<ResetSummary, String> {
+    if confirmation != FACTORY_RESET_CONFIRMATION {
+        return Err(format!(
+            "Factory reset requires the confirmation \"{}\"",
+            FACTORY_RESET_CONFIRMATION
+        ));
+    }
+
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        // Children before parents so every count reflects the table's own rows
+        let completions = clear_table(&tx, "habit_completions")?;
+        let attachments = clear_table(&tx, "attachments")?;
+        let focus_sessions = clear_table(&tx, "focus_sessions")?;
+        let habit_pauses = clear_table(&tx, "habit_pauses")?;
+        let habit_frequency_history = clear_table(&tx, "habit_frequency_history")?;
+        let notification_history = clear_table(&tx, "notification_history")?;
+        let notification_schedules = clear_table(&tx, "notification_schedules")?;
+        let tasks = clear_table(&tx, "tasks")?;
+        let habits = clear_table(&tx, "habits")?;
+        let goals = clear_table(&tx, "goals")?;
+        let settings = clear_table(&tx, "settings")?;
+        clear_table(&tx, "idempotency")?;
+        ensure_default_settings(&tx)
+            .map_err(|e| format!("Failed to restore default settings: {}", e))?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        if let Some(deleted_goals) = app_handle.try_state::<DeletedGoals>() {
+            deleted_goals.clear();
+        }
+        refresh_close_behavior(&state, &app_handle)?;
+        refresh_sync_server(&app_handle);
+
+        let path = get_user_data_path(&app_handle)?;
+        let user_config_deleted = path.exists();
+        if user_config_deleted {
+            fs::remove_file(&path)
+                .map_err(|e| format!("Failed to delete user data: {}", e))?;
+        }
+
+        Ok(ResetSummary {
+            goals,
+            tasks,
+            habits,
+            completions,
+            attachments,
+            focus_sessions,
+            habit_pauses,
+            habit_frequency_history,
+            notification_schedules,
+            notification_history,
+            settings,
+            user_config_deleted,
+        })
+    })
+}
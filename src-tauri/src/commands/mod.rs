@@ -1,9 +1,23 @@
+pub mod analytics;
 pub mod app;
+pub mod attachments;
 pub mod auth;
+pub mod focus_sessions;
 pub mod goals;
 pub mod habit_completions;
+pub mod habit_frequency_history;
+pub mod habit_pauses;
+pub mod habit_schedule;
 pub mod habits;
+pub mod idempotency;
+pub mod maintenance;
 pub mod notifications;
+pub mod overview;
+pub mod profiles;
+pub mod reports;
+pub mod sync;
 pub mod tasks;
+pub mod undo;
+pub mod updater;
 pub mod user_data;
 pub mod settings;
\ No newline at end of file
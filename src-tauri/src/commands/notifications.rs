@@ -1,9 +1,20 @@
-use crate::database::AppState;
-use rusqlite::{params, Row};
+use crate::commands::habit_pauses::is_habit_paused_on;
+use crate::commands::habits::Habit;
+use crate::commands::settings::{configured_now, load_settings_from_db, AppSettings};
+use crate::database::{with_connection, AppState, DatabaseError};
+use crate::events::{emit_data_changed, Action, Entity};
+use crate::timing::CommandTimer;
+use crate::validation::parse_time_of_day;
+use chrono::NaiveDateTime;
+use rusqlite::{params, Connection, OptionalExtension, Row};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use tauri::AppHandle;
 use tauri_plugin_notification::NotificationExt;
 
+/// Notification type of the recurring reminders managed by `set_habit_reminders`
+const REMINDER_TYPE: &str = "reminder";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NotificationPayload {
@@ -17,6 +28,22 @@ pub struct NotificationPayload {
     pub icon: Option<String>,
     pub actions: Option<Vec<NotificationAction>>,
     pub data: Option<serde_json::Value>,
+    /// Recurring reminders are skipped during quiet hours instead of deferred
+    #[serde(default)]
+    pub is_recurring: bool,
+}
+
+/// What happened to a notification passed to `send_system_notification`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum NotificationDelivery {
+    Shown,
+    /// The habit is paused today
+    Paused,
+    /// A recurring reminder fell in quiet hours and waits for its next occurrence
+    Skipped,
+    /// Scheduled again for when quiet hours end
+    Deferred { until: String },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,6 +56,9 @@ pub struct NotificationAction {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NotificationSchedule {
+    /// Row id, assigned when the schedule is stored
+    #[serde(default)]
+    pub id: Option<i64>,
     pub habit_id: String,
     pub habit_name: String,
     pub scheduled_time: String,
@@ -46,6 +76,26 @@ pub struct NotificationHistory {
     pub opened: bool,
     pub action_taken: Option<String>,
     pub payload_data: String,
+    /// Filled in when recording if the caller doesn't provide it
+    #[serde(default)]
+    pub created_at: Option<String>,
+}
+
+/// Column used to order notification history
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HistoryOrder {
+    SentAt,
+    CreatedAt,
+}
+
+impl HistoryOrder {
+    fn column(self) -> &'static str {
+        match self {
+            HistoryOrder::SentAt => "sent_at",
+            HistoryOrder::CreatedAt => "created_at",
+        }
+    }
 }
 
 impl NotificationSchedule {
@@ -53,9 +103,14 @@ impl NotificationSchedule {
         let schedule_data_str: String = row.get(6)?;
 
         match serde_json::from_str::<NotificationSchedule>(&schedule_data_str) {
-            Ok(schedule) => Ok(schedule),
+            // The stored JSON was serialized before the row had an id
+            Ok(schedule) => Ok(Self {
+                id: row.get(0)?,
+                ..schedule
+            }),
             Err(_) => {
                 Ok(Self {
+                    id: row.get(0)?,
                     habit_id: row.get(1)?,
                     habit_name: row.get(2)?,
                     scheduled_time: row.get(3)?,
@@ -68,7 +123,7 @@ impl NotificationSchedule {
 }
 
 impl NotificationHistory {
-    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+    pub(crate) fn from_row(row: &Row) -> rusqlite::Result<Self> {
         Ok(Self {
             id: row.get(0)?,
             habit_id: row.get(1)?,
@@ -77,39 +132,36 @@ impl NotificationHistory {
             opened: row.get::<_, i32>(4)? != 0,
             action_taken: row.get(5)?,
             payload_data: row.get(6)?,
+            created_at: row.get(7)?,
         })
     }
 }
 
 
-#[tauri::command]
-pub async fn send_system_notification(
-    app: AppHandle,
-    payload: NotificationPayload,
-) -> Result<(), String> {
-    app.notification()
-        .builder()
-        .title(&payload.title)
-        .body(&payload.body)
-        .icon("../../icons/32x32.png")
-        .show()
-        .map_err(|e| format!("Failed to send notification: {}", e))?;
+/// How quiet hours affect a notification due at `now`, or None outside quiet hours.
+/// Recurring reminders are skipped so they don't pile up into a burst when the window ends.
+pub(crate) fn quiet_hours_delivery(
+    settings: Option<&AppSettings>,
+    now: NaiveDateTime,
+    is_recurring: bool,
+) -> Option<NotificationDelivery> {
+    let window_end = settings?.notifications.quiet_hours.window_end(now)?;
 
-    Ok(())
+    if is_recurring {
+        Some(NotificationDelivery::Skipped)
+    } else {
+        Some(NotificationDelivery::Deferred {
+            until: window_end.format("%Y-%m-%dT%H:%M:%S").to_string(),
+        })
+    }
 }
 
-#[tauri::command]
-pub async fn schedule_notification(
-    state: tauri::State<'_, AppState>,
-    schedule: NotificationSchedule,
-) -> Result<NotificationSchedule, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    let schedule_json = serde_json::to_string(&schedule)
+/// Store a schedule, returning its row id
+fn insert_schedule(conn: &Connection, schedule: &NotificationSchedule) -> Result<i64, String> {
+    let schedule_json = serde_json::to_string(schedule)
         .map_err(|e| format!("Failed to serialize schedule: {}", e))?;
 
-    db.execute(
+    conn.execute(
         "INSERT OR REPLACE INTO notification_schedules (
             habit_id, habit_name, scheduled_time, notification_type, is_recurring, schedule_data
         ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
@@ -124,59 +176,300 @@ pub async fn schedule_notification(
     )
     .map_err(|e| format!("Failed to schedule notification: {}", e))?;
 
-    Ok(schedule)
+    Ok(conn.last_insert_rowid())
+}
+
+/// Schedule the daily reminder a habit's own reminder fields describe, if it has none.
+/// Returns whether one was added. Restores reminders from backups that only carried the habit.
+pub(crate) fn restore_habit_reminder(conn: &Connection, habit_id: &str) -> Result<bool, String> {
+    let habit = conn
+        .query_row(
+            "SELECT name, reminder_time FROM habits h
+             WHERE id = ?1 AND reminder_enabled = 1
+               AND NOT EXISTS (
+                  SELECT 1 FROM notification_schedules s
+                  WHERE s.habit_id = h.id AND s.notification_type = ?2 AND s.is_recurring = 1
+               )",
+            params![habit_id, REMINDER_TYPE],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to query habit: {}", e))?;
+
+    let Some((habit_name, reminder_time)) = habit else {
+        return Ok(false);
+    };
+    let Ok(time) = parse_time_of_day(reminder_time.trim()) else {
+        return Ok(false);
+    };
+
+    insert_schedule(
+        conn,
+        &NotificationSchedule {
+            id: None,
+            habit_id: habit_id.to_string(),
+            habit_name,
+            scheduled_time: time.format("%H:%M").to_string(),
+            notification_type: REMINDER_TYPE.to_string(),
+            is_recurring: true,
+        },
+    )?;
+
+    Ok(true)
+}
+
+/// Show a system notification, unless the habit is paused today or it's quiet hours
+#[tauri::command]
+pub async fn send_system_notification(
+    state: tauri::State<'_, AppState>,
+    app: AppHandle,
+    payload: NotificationPayload,
+) -> Result<NotificationDelivery, String> {
+    let settings = load_settings_from_db(&state)?;
+    let now = configured_now(settings.as_ref());
+
+    with_connection(&state, |db| {
+        let today = now.date().format("%Y-%m-%d").to_string();
+        if is_habit_paused_on(db, &payload.habit_id, &today)? {
+            return Ok(NotificationDelivery::Paused);
+        }
+
+        if let Some(delivery) = quiet_hours_delivery(settings.as_ref(), now, payload.is_recurring) {
+            if let NotificationDelivery::Deferred { until } = &delivery {
+                insert_schedule(
+                    db,
+                    &NotificationSchedule {
+                        id: None,
+                        habit_id: payload.habit_id.clone(),
+                        habit_name: payload.title.clone(),
+                        scheduled_time: until.clone(),
+                        notification_type: payload.notification_type.clone(),
+                        is_recurring: false,
+                    },
+                )?;
+            }
+
+            return Ok(delivery);
+        }
+
+        app.notification()
+            .builder()
+            .title(&payload.title)
+            .body(&payload.body)
+            .icon("../../icons/32x32.png")
+            .show()
+            .map_err(|e| format!("Failed to send notification: {}", e))?;
+
+        Ok(NotificationDelivery::Shown)
+    })
+}
+
+#[tauri::command]
+pub async fn schedule_notification(
+    state: tauri::State<'_, AppState>,
+    mut schedule: NotificationSchedule,
+) -> Result<NotificationSchedule, String> {
+    with_connection(&state, |db| {
+        schedule.id = Some(insert_schedule(db, &schedule)?);
+
+        Ok(schedule)
+    })
 }
 
 #[tauri::command]
 pub async fn get_scheduled_notifications(
     state: tauri::State<'_, AppState>,
 ) -> Result<Vec<NotificationSchedule>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    let mut stmt = db
-        .prepare(
-            "SELECT id, habit_id, habit_name, scheduled_time, notification_type,
-                    is_recurring, schedule_data
-             FROM notification_schedules
-             ORDER BY scheduled_time ASC"
+    let _timer = CommandTimer::start("get_scheduled_notifications");
+    // Habits paused today are left out so no reminders fire for them
+    let today = configured_now(load_settings_from_db(&state)?.as_ref())
+        .date()
+        .format("%Y-%m-%d")
+        .to_string();
+
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare(
+                "SELECT id, habit_id, habit_name, scheduled_time, notification_type,
+                        is_recurring, schedule_data
+                 FROM notification_schedules s
+                 WHERE NOT EXISTS (
+                    SELECT 1 FROM habit_pauses p
+                    WHERE p.habit_id = s.habit_id
+                      AND p.start_date <= ?1
+                      AND (p.end_date IS NULL OR p.end_date >= ?1)
+                 )
+                 ORDER BY scheduled_time ASC"
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let schedules = stmt
+            .query_map(params![today], NotificationSchedule::from_row)
+            .map_err(|e| format!("Failed to query schedules: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect schedules: {}", e))?;
+
+        Ok(schedules)
+    })
+}
+
+#[tauri::command]
+pub async fn get_habit_notifications(
+    state: tauri::State<'_, AppState>,
+    habit_id: String,
+) -> Result<Vec<NotificationSchedule>, String> {
+    let _timer = CommandTimer::start("get_habit_notifications");
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare(
+                "SELECT id, habit_id, habit_name, scheduled_time, notification_type,
+                        is_recurring, schedule_data
+                 FROM notification_schedules
+                 WHERE habit_id = ?1
+                 ORDER BY scheduled_time ASC"
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let schedules = stmt
+            .query_map(params![habit_id], NotificationSchedule::from_row)
+            .map_err(|e| format!("Failed to query schedules: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect schedules: {}", e))?;
+
+        Ok(schedules)
+    })
+}
+
+/// Fill in each habit's `reminder.times` from its recurring reminder schedules
+pub(crate) fn fill_reminder_times(conn: &Connection, habits: &mut [Habit]) -> Result<(), String> {
+    if habits.is_empty() {
+        return Ok(());
+    }
+
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT habit_id, scheduled_time FROM notification_schedules
+             WHERE notification_type = ?1 AND is_recurring = 1
+             ORDER BY scheduled_time ASC",
         )
         .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
-    let schedules = stmt
-        .query_map([], NotificationSchedule::from_row)
-        .map_err(|e| format!("Failed to query schedules: {}", e))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to collect schedules: {}", e))?;
+    let mut times: HashMap<String, Vec<String>> = HashMap::new();
+    let rows = stmt
+        .query_map(params![REMINDER_TYPE], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| format!("Failed to query reminders: {}", e))?;
+    for row in rows {
+        let (habit_id, time) = row.map_err(|e| format!("Failed to collect reminders: {}", e))?;
+        times.entry(habit_id).or_default().push(time);
+    }
+
+    for habit in habits {
+        habit.reminder.times = times.remove(&habit.id).unwrap_or_default();
+    }
 
-    Ok(schedules)
+    Ok(())
 }
 
+/// Set all of a habit's daily reminder times, adding and removing its reminder schedules to match.
+/// The habit's own reminder fields mirror the earliest time, and an empty list turns reminders off.
 #[tauri::command]
-pub async fn get_habit_notifications(
+pub async fn set_habit_reminders(
     state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
     habit_id: String,
+    times: Vec<String>,
 ) -> Result<Vec<NotificationSchedule>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    let mut stmt = db
-        .prepare(
-            "SELECT id, habit_id, habit_name, scheduled_time, notification_type,
-                    is_recurring, schedule_data
-             FROM notification_schedules
-             WHERE habit_id = ?1
-             ORDER BY scheduled_time ASC"
-        )
-        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    let mut times = times
+        .iter()
+        .map(|time| parse_time_of_day(time.trim()).map(|time| time.format("%H:%M").to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    times.sort();
+    times.dedup();
+
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        let habit_name: String = tx
+            .query_row("SELECT name FROM habits WHERE id = ?1", params![habit_id], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("Failed to query habit: {}", e))?
+            .ok_or_else(|| DatabaseError::not_found("Habit", &habit_id))?;
+
+        let existing = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT scheduled_time FROM notification_schedules
+                     WHERE habit_id = ?1 AND notification_type = ?2 AND is_recurring = 1",
+                )
+                .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+            let existing = stmt
+                .query_map(params![habit_id, REMINDER_TYPE], |row| row.get::<_, String>(0))
+                .map_err(|e| format!("Failed to query reminders: {}", e))?
+                .collect::<Result<HashSet<_>, _>>()
+                .map_err(|e| format!("Failed to collect reminders: {}", e))?;
+            existing
+        };
+
+        for time in existing.iter().filter(|time| !times.contains(time)) {
+            tx.execute(
+                "DELETE FROM notification_schedules
+                 WHERE habit_id = ?1 AND notification_type = ?2 AND is_recurring = 1 AND scheduled_time = ?3",
+                params![habit_id, REMINDER_TYPE, time],
+            )
+            .map_err(|e| format!("Failed to remove reminder at {}: {}", time, e))?;
+        }
 
-    let schedules = stmt
-        .query_map(params![habit_id], NotificationSchedule::from_row)
-        .map_err(|e| format!("Failed to query schedules: {}", e))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to collect schedules: {}", e))?;
+        for time in times.iter().filter(|time| !existing.contains(*time)) {
+            insert_schedule(
+                &tx,
+                &NotificationSchedule {
+                    id: None,
+                    habit_id: habit_id.clone(),
+                    habit_name: habit_name.clone(),
+                    scheduled_time: time.clone(),
+                    notification_type: REMINDER_TYPE.to_string(),
+                    is_recurring: true,
+                },
+            )?;
+        }
 
-    Ok(schedules)
+        let updated_at = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+        tx.execute(
+            "UPDATE habits
+             SET reminder_enabled = ?2, reminder_time = COALESCE(?3, reminder_time), updated_at = ?4
+             WHERE id = ?1",
+            params![habit_id, !times.is_empty() as i32, times.first(), updated_at],
+        )
+        .map_err(|e| format!("Failed to update habit reminder: {}", e))?;
+
+        let reminders = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT id, habit_id, habit_name, scheduled_time, notification_type,
+                            is_recurring, schedule_data
+                     FROM notification_schedules
+                     WHERE habit_id = ?1 AND notification_type = ?2 AND is_recurring = 1
+                     ORDER BY scheduled_time ASC",
+                )
+                .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+            let reminders = stmt
+                .query_map(params![habit_id, REMINDER_TYPE], NotificationSchedule::from_row)
+                .map_err(|e| format!("Failed to query reminders: {}", e))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Failed to collect reminders: {}", e))?;
+            reminders
+        };
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        emit_data_changed(&app_handle, Entity::Habit, Action::Update, &habit_id);
+
+        Ok(reminders)
+    })
 }
 
 #[tauri::command]
@@ -184,92 +477,112 @@ pub async fn cancel_notification(
     state: tauri::State<'_, AppState>,
     habit_id: String,
 ) -> Result<bool, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    with_connection(&state, |db| {
+        let rows = db.execute(
+            "DELETE FROM notification_schedules WHERE habit_id = ?1",
+            params![habit_id],
+        )
+        .map_err(|e| format!("Failed to cancel notification: {}", e))?;
 
-    let rows = db.execute(
-        "DELETE FROM notification_schedules WHERE habit_id = ?1",
-        params![habit_id],
-    )
-    .map_err(|e| format!("Failed to cancel notification: {}", e))?;
+        Ok(rows > 0)
+    })
+}
+
+/// Cancel a single schedule, for habits with more than one
+#[tauri::command]
+pub async fn cancel_notification_by_id(
+    state: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<bool, String> {
+    with_connection(&state, |db| {
+        let rows = db.execute(
+            "DELETE FROM notification_schedules WHERE id = ?1",
+            params![id],
+        )
+        .map_err(|e| format!("Failed to cancel notification: {}", e))?;
 
-    Ok(rows > 0)
+        Ok(rows > 0)
+    })
 }
 
 #[tauri::command]
 pub async fn cancel_all_notifications(
     state: tauri::State<'_, AppState>,
 ) -> Result<usize, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    with_connection(&state, |db| {
+        let rows = db.execute("DELETE FROM notification_schedules", [])
+            .map_err(|e| format!("Failed to cancel all notifications: {}", e))?;
 
-    let rows = db.execute("DELETE FROM notification_schedules", [])
-        .map_err(|e| format!("Failed to cancel all notifications: {}", e))?;
-
-    Ok(rows)
+        Ok(rows)
+    })
 }
 
 #[tauri::command]
 pub async fn record_notification(
     state: tauri::State<'_, AppState>,
-    history: NotificationHistory,
+    mut history: NotificationHistory,
 ) -> Result<NotificationHistory, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    with_connection(&state, |db| {
+        // Same format as the column's datetime('now') default
+        if history.created_at.is_none() {
+            history.created_at = Some(chrono::Utc::now().format("%Y-%m-%d %H:%M:%S").to_string());
+        }
 
-    db.execute(
-        "INSERT INTO notification_history (
-            id, habit_id, sent_at, notification_type, opened, action_taken, payload_data
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
-        params![
-            history.id,
-            history.habit_id,
-            history.sent_at,
-            history.notification_type,
-            history.opened as i32,
-            history.action_taken,
-            history.payload_data,
-        ],
-    )
-    .map_err(|e| format!("Failed to record notification: {}", e))?;
+        db.execute(
+            "INSERT INTO notification_history (
+                id, habit_id, sent_at, notification_type, opened, action_taken, payload_data, created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                history.id,
+                history.habit_id,
+                history.sent_at,
+                history.notification_type,
+                history.opened as i32,
+                history.action_taken,
+                history.payload_data,
+                history.created_at,
+            ],
+        )
+        .map_err(|e| format!("Failed to record notification: {}", e))?;
 
-    Ok(history)
+        Ok(history)
+    })
 }
 
 #[tauri::command]
 pub async fn get_notification_history(
     state: tauri::State<'_, AppState>,
     limit: Option<i32>,
+    order_by: Option<HistoryOrder>,
+    since: Option<String>,
 ) -> Result<Vec<NotificationHistory>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    let _timer = CommandTimer::start("get_notification_history");
+    with_connection(&state, |db| {
+        let column = order_by.unwrap_or(HistoryOrder::SentAt).column();
 
-    let query = if let Some(limit) = limit {
-        format!(
-            "SELECT id, habit_id, sent_at, notification_type, opened, action_taken, payload_data
+        // sent_at is ISO 8601 while created_at uses SQLite's format, so compare via datetime()
+        let query = format!(
+            "SELECT id, habit_id, sent_at, notification_type, opened, action_taken, payload_data, created_at
              FROM notification_history
-             ORDER BY sent_at DESC
-             LIMIT {}",
-            limit
-        )
-    } else {
-        "SELECT id, habit_id, sent_at, notification_type, opened, action_taken, payload_data
-         FROM notification_history
-         ORDER BY sent_at DESC"
-            .to_string()
-    };
-
-    let mut stmt = db
-        .prepare(&query)
-        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
-
-    let history = stmt
-        .query_map([], NotificationHistory::from_row)
-        .map_err(|e| format!("Failed to query history: {}", e))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to collect history: {}", e))?;
-
-    Ok(history)
+             WHERE ?1 IS NULL OR datetime({column}) >= datetime(?1)
+             ORDER BY {column} DESC
+             LIMIT ?2",
+            column = column
+        );
+
+        let mut stmt = db
+            .prepare(&query)
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        // A negative LIMIT means no limit in SQLite
+        let history = stmt
+            .query_map(params![since, limit.unwrap_or(-1)], NotificationHistory::from_row)
+            .map_err(|e| format!("Failed to query history: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect history: {}", e))?;
+
+        Ok(history)
+    })
 }
 
 #[tauri::command]
@@ -278,18 +591,17 @@ pub async fn mark_notification_opened(
     notification_id: String,
     action_taken: Option<String>,
 ) -> Result<(), String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    db.execute(
-        "UPDATE notification_history
-         SET opened = 1, action_taken = ?2
-         WHERE id = ?1",
-        params![notification_id, action_taken],
-    )
-    .map_err(|e| format!("Failed to update notification: {}", e))?;
+    with_connection(&state, |db| {
+        db.execute(
+            "UPDATE notification_history
+             SET opened = 1, action_taken = ?2
+             WHERE id = ?1",
+            params![notification_id, action_taken],
+        )
+        .map_err(|e| format!("Failed to update notification: {}", e))?;
 
-    Ok(())
+        Ok(())
+    })
 }
 
 #[tauri::command]
@@ -297,17 +609,50 @@ pub async fn clean_notification_history(
     state: tauri::State<'_, AppState>,
     days_to_keep: i32,
 ) -> Result<usize, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    with_connection(&state, |db| {
+        let rows = db.execute(
+            "DELETE FROM notification_history
+             WHERE sent_at < datetime('now', '-' || ?1 || ' days')",
+            params![days_to_keep],
+        )
+        .map_err(|e| format!("Failed to clean notification history: {}", e))?;
+
+        Ok(rows)
+    })
+}
+
+/// Delete schedules whose habit no longer exists, which imports and restores can leave behind
+#[tauri::command]
+pub async fn cleanup_orphaned_schedules(
+    state: tauri::State<'_, AppState>,
+) -> Result<usize, String> {
+    with_connection(&state, |db| {
+        let rows = db.execute(
+            "DELETE FROM notification_schedules
+             WHERE NOT EXISTS (SELECT 1 FROM habits h WHERE h.id = notification_schedules.habit_id)",
+            [],
+        )
+        .map_err(|e| format!("Failed to clean up orphaned schedules: {}", e))?;
 
-    let rows = db.execute(
-        "DELETE FROM notification_history
-         WHERE sent_at < datetime('now', '-' || ?1 || ' days')",
-        params![days_to_keep],
-    )
-    .map_err(|e| format!("Failed to clean notification history: {}", e))?;
+        Ok(rows)
+    })
+}
+
+/// Delete history entries whose habit no longer exists
+#[tauri::command]
+pub async fn cleanup_orphaned_history(
+    state: tauri::State<'_, AppState>,
+) -> Result<usize, String> {
+    with_connection(&state, |db| {
+        let rows = db.execute(
+            "DELETE FROM notification_history
+             WHERE NOT EXISTS (SELECT 1 FROM habits h WHERE h.id = notification_history.habit_id)",
+            [],
+        )
+        .map_err(|e| format!("Failed to clean up orphaned history: {}", e))?;
 
-    Ok(rows)
+        Ok(rows)
+    })
 }
 
 #[tauri::command]
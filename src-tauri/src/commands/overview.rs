@@ -0,0 +1,183 @@
+use crate::commands::goals::{Goal, DEFAULT_DEADLINE_WARNING_DAYS};
+use crate::commands::habit_completions::{calculate_current_streak, HabitCompletion};
+use crate::commands::habit_pauses::is_habit_paused_on;
+use crate::commands::habit_schedule::{habits_due_on, is_pending_on, is_scheduled_on};
+use crate::commands::habits::Habit;
+use crate::commands::settings::{
+    configured_now, configured_streak_grace_days, configured_week_start, load_settings_from_db,
+};
+use crate::commands::tasks::{tasks_due_on, tasks_overdue_on, Task};
+use crate::database::{with_connection, AppState};
+use crate::timing::CommandTimer;
+use crate::validation::ValidationError;
+use chrono::{Duration, NaiveDate};
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// A habit scheduled on the overview's date
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodayHabit {
+    pub habit: Habit,
+    /// The day's logged completion, if any
+    pub completion: Option<HabitCompletion>,
+    /// Still waiting to be completed or skipped
+    pub pending: bool,
+    pub current_streak: i32,
+}
+
+/// Everything the home screen shows for one day
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodayOverview {
+    pub date: String,
+    /// Unpaused habits scheduled on the date, in creation order, newest first
+    pub habits: Vec<TodayHabit>,
+    pub tasks_due: Vec<Task>,
+    pub tasks_overdue: Vec<Task>,
+    /// Unfinished goals whose deadline has passed or falls within the warning window, soonest first
+    pub goals_with_deadlines: Vec<Goal>,
+    pub habits_pending: usize,
+    pub habits_completed: usize,
+}
+
+/// Row counts for navigation badges
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Counts {
+    pub active_goals: i64,
+    /// Tasks not done yet, overdue ones included
+    pub open_tasks: i64,
+    pub overdue_tasks: i64,
+    pub habits: i64,
+    /// Habits scheduled today that haven't been completed or skipped, as in `get_habits_due_today`
+    pub habits_due_today: usize,
+}
+
+/// Count goals, tasks and habits without fetching their rows, using today in the configured timezone
+#[tauri::command]
+pub async fn get_counts(state: tauri::State<'_, AppState>) -> Result<Counts, String> {
+    let _timer = CommandTimer::start("get_counts");
+    let settings = load_settings_from_db(&state)?;
+    let today = configured_now(settings.as_ref()).date();
+    let week_start = configured_week_start(settings.as_ref());
+    let date = today.format("%Y-%m-%d").to_string();
+
+    with_connection(&state, |db| {
+        // Overdue matches tasks_overdue_on: due dates compare by local calendar day
+        let (active_goals, open_tasks, overdue_tasks, habits) = db
+            .query_row(
+                "SELECT
+                    (SELECT COUNT(*) FROM goals WHERE status = 'active'),
+                    (SELECT COUNT(*) FROM tasks WHERE done = 0),
+                    (SELECT COUNT(*) FROM tasks
+                     WHERE done = 0 AND due_date IS NOT NULL AND date(due_date, 'localtime') < ?1),
+                    (SELECT COUNT(*) FROM habits)",
+                params![date],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .map_err(|e| format!("Failed to count rows: {}", e))?;
+
+        Ok(Counts {
+            active_goals,
+            open_tasks,
+            overdue_tasks,
+            habits,
+            habits_due_today: habits_due_on(db, today, week_start)?.len(),
+        })
+    })
+}
+
+/// Get the habits, tasks and goal deadlines for `date` (YYYY-MM-DD, default today) in one call
+#[tauri::command]
+pub async fn get_today_overview(
+    state: tauri::State<'_, AppState>,
+    date: Option<String>,
+) -> Result<TodayOverview, String> {
+    let _timer = CommandTimer::start("get_today_overview");
+    let settings = load_settings_from_db(&state)?;
+    let day = match &date {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| ValidationError::InvalidDate(date.clone()))?,
+        None => configured_now(settings.as_ref()).date(),
+    };
+    let week_start = configured_week_start(settings.as_ref());
+    let grace_days = configured_streak_grace_days(settings.as_ref());
+    let warning_days = settings
+        .as_ref()
+        .map(|settings| settings.goals.deadline_warning_days)
+        .unwrap_or(DEFAULT_DEADLINE_WARNING_DAYS);
+
+    let date = day.format("%Y-%m-%d").to_string();
+
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare("SELECT * FROM habits ORDER BY created_at DESC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let all_habits = stmt
+            .query_map([], Habit::from_row)
+            .map_err(|e| format!("Failed to query habits: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect habits: {}", e))?;
+
+        let mut stmt = db
+            .prepare("SELECT * FROM habit_completions WHERE date = ?1")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let mut completions = stmt
+            .query_map(params![date], HabitCompletion::from_row)
+            .map_err(|e| format!("Failed to query habit completions: {}", e))?
+            .map(|completion| completion.map(|c| (c.habit_id.clone(), c)))
+            .collect::<Result<HashMap<_, _>, _>>()
+            .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
+
+        let mut habits = Vec::new();
+        for habit in all_habits {
+            if !is_scheduled_on(&habit, day) || is_habit_paused_on(db, &habit.id, &date)? {
+                continue;
+            }
+
+            habits.push(TodayHabit {
+                pending: is_pending_on(db, &habit, day, week_start)?,
+                current_streak: calculate_current_streak(db, &habit.id, grace_days),
+                completion: completions.remove(&habit.id),
+                habit,
+            });
+        }
+
+        let habits_pending = habits.iter().filter(|habit| habit.pending).count();
+        let habits_completed = habits
+            .iter()
+            .filter(|habit| habit.completion.as_ref().is_some_and(|c| c.completed))
+            .count();
+
+        // Deadlines may be full ISO timestamps, so compare local calendar days like the frontend does
+        let warning_end = day + Duration::days(warning_days as i64);
+        let mut stmt = db
+            .prepare(
+                "SELECT * FROM goals
+                 WHERE deadline IS NOT NULL AND status != 'completed'
+                   AND date(deadline, 'localtime') <= ?1
+                 ORDER BY deadline ASC",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let goals_with_deadlines = stmt
+            .query_map(params![warning_end.format("%Y-%m-%d").to_string()], Goal::from_row)
+            .map_err(|e| format!("Failed to query goals: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect goals: {}", e))?;
+
+        Ok(TodayOverview {
+            tasks_due: tasks_due_on(db, &date)?,
+            tasks_overdue: tasks_overdue_on(db, &date)?,
+            date,
+            habits,
+            goals_with_deadlines,
+            habits_pending,
+            habits_completed,
+        })
+    })
+}
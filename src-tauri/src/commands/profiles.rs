@@ -0,0 +1,128 @@
+use crate::commands::settings::refresh_close_behavior;
+use crate::commands::sync::refresh_sync_server;
+use crate::commands::undo::DeletedGoals;
+use crate::database::{self, AppState, DEFAULT_PROFILE};
+use serde::Serialize;
+use std::fs;
+use tauri::{AppHandle, Emitter, Manager};
+
+/// Names reserved because they map onto the base database filenames
+const RESERVED_PROFILE_NAMES: [&str; 2] = [DEFAULT_PROFILE, "dev"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub name: String,
+    pub active: bool,
+}
+
+/// Profile names become part of the database filename, so keep them simple
+fn validate_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 32 {
+        return Err("Profile name must be between 1 and 32 characters".to_string());
+    }
+
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+    {
+        return Err(
+            "Profile name may only contain lowercase letters, digits and underscores".to_string(),
+        );
+    }
+
+    if RESERVED_PROFILE_NAMES.contains(&name) {
+        return Err(format!("Profile name '{}' is reserved", name));
+    }
+
+    Ok(())
+}
+
+/// List all profiles in the app data directory
+#[tauri::command]
+pub async fn list_profiles(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<Profile>, String> {
+    let app_dir = database::get_app_dir(&app_handle)?;
+    let active_profile = state.db.profile();
+    let prefix = format!("{}-", database::db_file_prefix());
+
+    let mut names: Vec<String> = fs::read_dir(&app_dir)
+        .map_err(|e| format!("Failed to read app data directory: {}", e))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().into_string().ok()?;
+            let name = file_name.strip_prefix(&prefix)?.strip_suffix(".db")?;
+            validate_profile_name(name).ok()?;
+            Some(name.to_string())
+        })
+        .collect();
+
+    names.sort();
+    names.insert(0, DEFAULT_PROFILE.to_string());
+
+    Ok(names
+        .into_iter()
+        .map(|name| Profile {
+            active: name == active_profile,
+            name,
+        })
+        .collect())
+}
+
+/// Create a new empty profile database without switching to it
+#[tauri::command]
+pub async fn create_profile(app_handle: AppHandle, name: String) -> Result<Profile, String> {
+    validate_profile_name(&name)?;
+
+    let app_dir = database::get_app_dir(&app_handle)?;
+    let db_path = database::profile_db_path(&app_dir, &name);
+
+    if db_path.exists() {
+        return Err(format!("Profile '{}' already exists", name));
+    }
+
+    // Opening the pool creates the file and schema; it is dropped right away
+    database::open_pool(&db_path)?;
+
+    Ok(Profile { name, active: false })
+}
+
+/// Switch the active database to another profile
+#[tauri::command]
+pub async fn switch_profile(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    name: String,
+) -> Result<Profile, String> {
+    if name != DEFAULT_PROFILE {
+        validate_profile_name(&name)?;
+    }
+
+    let app_dir = database::get_app_dir(&app_handle)?;
+    let db_path = database::profile_db_path(&app_dir, &name);
+
+    if name != DEFAULT_PROFILE && !db_path.exists() {
+        return Err(format!("Profile '{}' does not exist", name));
+    }
+
+    let pool = database::open_pool(&db_path)?;
+
+    // Tauri can't replace managed state, so swap the pool inside it instead
+    state.db.replace(name.clone(), pool);
+    database::write_active_profile(&app_dir, &name)?;
+
+    // Deleted goals belong to the previous profile's database
+    if let Some(deleted_goals) = app_handle.try_state::<DeletedGoals>() {
+        deleted_goals.clear();
+    }
+    refresh_close_behavior(&state, &app_handle)?;
+    refresh_sync_server(&app_handle);
+
+    app_handle
+        .emit("profile-switched", &name)
+        .map_err(|e| format!("Failed to emit profile-switched event: {}", e))?;
+
+    Ok(Profile { name, active: true })
+}
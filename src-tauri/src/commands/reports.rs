@@ -0,0 +1,112 @@
+use crate::commands::goals::{calculate_goal_progress, Goal};
+use crate::commands::habit_completions::{calculate_current_streak, count_completed_in_range};
+use crate::commands::habits::Habit;
+use crate::commands::settings::{configured_streak_grace_days, load_settings_from_db};
+use crate::database::{with_connection, AppState};
+use crate::timing::CommandTimer;
+use rusqlite::params;
+
+/// Escape characters that would break a Markdown table cell or list item
+fn escape_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Generate a shareable Markdown progress report for a date range
+#[tauri::command]
+pub async fn generate_markdown_report(
+    state: tauri::State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<String, String> {
+    let _timer = CommandTimer::start("generate_markdown_report");
+    if start_date > end_date {
+        return Err("Start date must not be after end date".to_string());
+    }
+
+    let grace_days = configured_streak_grace_days(load_settings_from_db(&state)?.as_ref());
+
+    with_connection(&state, |db| {
+        let mut report = format!("# Loomra Progress Report\n\n_{} to {}_\n\n", start_date, end_date);
+
+        // Goals and their progress
+        let mut stmt = db
+            .prepare("SELECT * FROM goals ORDER BY created_at DESC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let goals = stmt
+            .query_map([], Goal::from_row)
+            .map_err(|e| format!("Failed to query goals: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect goals: {}", e))?;
+
+        report.push_str("## Goals\n\n");
+        if goals.is_empty() {
+            report.push_str("No goals yet.\n\n");
+        } else {
+            for goal in &goals {
+                let progress = calculate_goal_progress(db, &goal.id)?;
+                report.push_str(&format!(
+                    "- **{}** ({}) — {}%\n",
+                    escape_cell(&goal.title), goal.status, progress
+                ));
+            }
+            report.push('\n');
+        }
+
+        // Tasks completed in the range
+        let mut stmt = db
+            .prepare(
+                "SELECT title FROM tasks
+                 WHERE done = 1 AND date(updated_at) BETWEEN ?1 AND ?2
+                 ORDER BY updated_at ASC",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let completed_tasks = stmt
+            .query_map(params![start_date, end_date], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("Failed to query tasks: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+
+        report.push_str("## Completed Tasks\n\n");
+        if completed_tasks.is_empty() {
+            report.push_str("No tasks completed in this period.\n\n");
+        } else {
+            for title in &completed_tasks {
+                report.push_str(&format!("- [x] {}\n", escape_cell(title)));
+            }
+            report.push('\n');
+        }
+
+        // Habit completion counts and streaks
+        let mut stmt = db
+            .prepare("SELECT * FROM habits ORDER BY name ASC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let habits = stmt
+            .query_map([], Habit::from_row)
+            .map_err(|e| format!("Failed to query habits: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect habits: {}", e))?;
+
+        report.push_str("## Habits\n\n");
+        if habits.is_empty() {
+            report.push_str("No habits yet.\n");
+        } else {
+            report.push_str("| Habit | Completions | Current Streak |\n");
+            report.push_str("|-------|-------------|----------------|\n");
+            for habit in &habits {
+                let completions = count_completed_in_range(db, &habit.id, &start_date, &end_date)?;
+                let streak = calculate_current_streak(db, &habit.id, grace_days);
+                report.push_str(&format!(
+                    "| {} | {} | {} |\n",
+                    escape_cell(&habit.name),
+                    completions,
+                    streak
+                ));
+            }
+        }
+
+        Ok(report)
+    })
+}
@@ -1,6 +1,20 @@
+use chrono::Datelike;
+use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
-use tauri::State;
-use crate::database::AppState;
+use std::collections::HashSet;
+use std::sync::RwLock;
+use tauri::{AppHandle, Manager, State};
+use tauri_plugin_autostart::ManagerExt;
+use crate::commands::attachments::delete_orphaned_attachments;
+use crate::commands::focus_sessions::detach_orphaned_focus_sessions;
+use crate::commands::habit_completions::{deserialize_moods, moods_from_column, moods_to_column};
+use crate::commands::habits::default_completion_threshold;
+use crate::commands::notifications::restore_habit_reminder;
+use crate::commands::sync::refresh_sync_server;
+use crate::crypto;
+use crate::database::{with_connection, AppState, DatabaseError};
+use crate::timing::CommandTimer;
+use crate::validation::{parse_time_of_day, parse_weekday, ValidationError};
 
 // ============================================================================
 // SETTINGS STRUCTURES
@@ -14,12 +28,36 @@ pub struct AppearanceSettings {
     pub timezone: String,
 }
 
+impl Default for AppearanceSettings {
+    fn default() -> Self {
+        Self {
+            theme: "system".to_string(),
+            week_starts_on: "sunday".to_string(),
+            timezone: "auto".to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HabitSettings {
     pub default_reminder: bool,
     pub default_reminder_time: String,
     pub default_priority: String,
+    /// Missed days a streak tolerates in a row before it breaks
+    #[serde(default)]
+    pub streak_grace_days: u32,
+}
+
+impl Default for HabitSettings {
+    fn default() -> Self {
+        Self {
+            default_reminder: false,
+            default_reminder_time: "09:00".to_string(),
+            default_priority: "medium".to_string(),
+            streak_grace_days: 0,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,12 +68,98 @@ pub struct GoalSettings {
     pub show_progress_percentage: bool,
 }
 
+impl Default for GoalSettings {
+    fn default() -> Self {
+        Self {
+            deadline_warning_days: 30,
+            default_category: "Productivity".to_string(),
+            show_progress_percentage: true,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct NotificationSettings {
     pub habit_reminders: bool,
     pub goal_deadlines: bool,
     pub streak_reminders: bool,
+    /// Missing from settings saved before quiet hours existed
+    #[serde(default)]
+    pub quiet_hours: QuietHours,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            habit_reminders: true,
+            goal_deadlines: true,
+            streak_reminders: true,
+            quiet_hours: QuietHours::default(),
+        }
+    }
+}
+
+/// A daily window, in local time, during which notifications are held back
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuietHours {
+    pub enabled: bool,
+    /// `HH:MM`. A start later than the end makes the window run past midnight.
+    pub start: String,
+    /// `HH:MM`
+    pub end: String,
+    /// Lowercase weekday names the window starts on. Empty means every day.
+    #[serde(default)]
+    pub days: Vec<String>,
+}
+
+impl Default for QuietHours {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start: "22:00".to_string(),
+            end: "07:00".to_string(),
+            days: Vec::new(),
+        }
+    }
+}
+
+impl QuietHours {
+    /// When the quiet window containing `at` ends, or None if `at` falls outside every window
+    pub(crate) fn window_end(&self, at: chrono::NaiveDateTime) -> Option<chrono::NaiveDateTime> {
+        if !self.enabled {
+            return None;
+        }
+
+        let start = parse_time_of_day(&self.start).ok()?;
+        let end = parse_time_of_day(&self.end).ok()?;
+        if start == end {
+            return None;
+        }
+
+        // A window past midnight that started yesterday can still cover `at`
+        let today = at.date();
+        [today.pred_opt()?, today].into_iter().find_map(|day| {
+            let starts_today = self.days.is_empty()
+                || self
+                    .days
+                    .iter()
+                    .any(|name| parse_weekday(name).is_ok_and(|weekday| weekday == day.weekday()));
+            if !starts_today {
+                return None;
+            }
+
+            let window_start = day.and_time(start);
+            let window_end = if start < end {
+                day.and_time(end)
+            } else {
+                day.succ_opt()?.and_time(end)
+            };
+
+            (window_start <= at && at < window_end).then_some(window_end)
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,15 +167,183 @@ pub struct NotificationSettings {
 pub struct DataSettings {
     pub auto_backup: bool,
     pub backup_frequency: String,
+    /// Most goals, tasks or habits that can be created, to stop a runaway caller. None disables the cap.
+    #[serde(default)]
+    pub max_rows_per_table: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Default for DataSettings {
+    fn default() -> Self {
+        Self {
+            auto_backup: true,
+            backup_frequency: "weekly".to_string(),
+            max_rows_per_table: None,
+        }
+    }
+}
+
+/// What closing the main window does
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CloseBehavior {
+    #[default]
+    Tray,
+    Quit,
+    Ask,
+}
+
+/// Release channel the updater checks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemSettings {
+    /// Mirrors the OS launch-on-login registration
+    pub launch_on_login: bool,
+    #[serde(default)]
+    pub close_behavior: CloseBehavior,
+    #[serde(default)]
+    pub update_channel: UpdateChannel,
+}
+
+/// Default port for the LAN sync server
+pub const DEFAULT_SYNC_PORT: u16 = 47600;
+
+/// Local HTTP server for pulling data between devices. The token is required on every request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncSettings {
+    pub enabled: bool,
+    /// Listen on all interfaces instead of only localhost
+    pub allow_lan: bool,
+    pub port: u16,
+    pub token: String,
+}
+
+impl Default for SyncSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allow_lan: false,
+            port: DEFAULT_SYNC_PORT,
+            token: String::new(),
+        }
+    }
+}
+
+/// Close behavior cached in managed state so the run-event handler can read it
+/// without touching the database
+#[derive(Debug, Default)]
+pub struct CloseBehaviorState(RwLock<CloseBehavior>);
+
+impl CloseBehaviorState {
+    pub fn get(&self) -> CloseBehavior {
+        *self.0.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn set(&self, behavior: CloseBehavior) {
+        *self.0.write().unwrap_or_else(|e| e.into_inner()) = behavior;
+    }
+}
+
+/// Longest deadline warning the goal settings accept, in days
+const MAX_DEADLINE_WARNING_DAYS: u32 = 365;
+
+/// Most missed days in a row a streak can be set to tolerate
+const MAX_STREAK_GRACE_DAYS: u32 = 30;
+
+/// Describe `value` as invalid unless it's one of `allowed`
+fn check_one_of(problems: &mut Vec<String>, field: &str, value: &str, allowed: &[&str]) {
+    if !allowed.contains(&value) {
+        problems.push(format!("{} '{}' must be one of {}", field, value, allowed.join(", ")));
+    }
+}
+
+/// Every field of `settings` outside its allowed values or range, named by its camelCase path
+fn settings_problems(settings: &AppSettings) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let appearance = &settings.appearance;
+    check_one_of(&mut problems, "appearance.theme", &appearance.theme, &["light", "dark", "system"]);
+    check_one_of(&mut problems, "appearance.weekStartsOn", &appearance.week_starts_on, &["sunday", "monday"]);
+    if appearance.timezone != "auto" && appearance.timezone.parse::<chrono_tz::Tz>().is_err() {
+        problems.push(format!("appearance.timezone '{}' is not a known timezone", appearance.timezone));
+    }
+
+    let habits = &settings.habits;
+    check_one_of(&mut problems, "habits.defaultPriority", &habits.default_priority, &["low", "medium", "high"]);
+    if let Err(e) = parse_time_of_day(&habits.default_reminder_time) {
+        problems.push(format!("habits.defaultReminderTime: {}", e));
+    }
+    if habits.streak_grace_days > MAX_STREAK_GRACE_DAYS {
+        problems.push(format!(
+            "habits.streakGraceDays {} must be at most {}",
+            habits.streak_grace_days, MAX_STREAK_GRACE_DAYS
+        ));
+    }
+
+    if settings.goals.deadline_warning_days > MAX_DEADLINE_WARNING_DAYS {
+        problems.push(format!(
+            "goals.deadlineWarningDays {} must be at most {}",
+            settings.goals.deadline_warning_days, MAX_DEADLINE_WARNING_DAYS
+        ));
+    }
+
+    let quiet_hours = &settings.notifications.quiet_hours;
+    for time in [&quiet_hours.start, &quiet_hours.end] {
+        if let Err(e) = parse_time_of_day(time) {
+            problems.push(format!("notifications.quietHours: {}", e));
+        }
+    }
+    for day in &quiet_hours.days {
+        if let Err(e) = parse_weekday(day) {
+            problems.push(format!("notifications.quietHours: {}", e));
+        }
+    }
+
+    let backup_frequency = &settings.data.backup_frequency;
+    check_one_of(&mut problems, "data.backupFrequency", backup_frequency, &["daily", "weekly", "monthly"]);
+    if settings.data.max_rows_per_table == Some(0) {
+        problems.push("data.maxRowsPerTable must be at least 1".to_string());
+    }
+
+    if settings.sync.port == 0 {
+        problems.push("sync.port must be between 1 and 65535".to_string());
+    }
+
+    problems
+}
+
+/// Reject settings with any field outside its allowed values, listing every one
+pub(crate) fn validate_settings(settings: &AppSettings) -> Result<(), ValidationError> {
+    let problems = settings_problems(settings);
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidSettings(problems))
+    }
+}
+
+/// Defaults match the frontend's DEFAULT_SETTINGS
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct AppSettings {
     pub appearance: AppearanceSettings,
     pub habits: HabitSettings,
     pub goals: GoalSettings,
     pub notifications: NotificationSettings,
     pub data: DataSettings,
+    /// Missing from settings saved before system settings existed
+    #[serde(default)]
+    pub system: SystemSettings,
+    /// Missing from settings saved before LAN sync existed
+    #[serde(default)]
+    pub sync: SyncSettings,
 }
 
 // ============================================================================
@@ -65,6 +357,31 @@ pub struct ExportData {
     pub tasks: Vec<TaskData>,
     pub habits: Vec<HabitData>,
     pub habit_completions: Vec<HabitCompletionData>,
+    /// Missing from exports made before habits could be paused
+    #[serde(default)]
+    pub habit_pauses: Vec<HabitPauseData>,
+    /// Missing from exports made before frequency changes were recorded
+    #[serde(default)]
+    pub habit_frequency_history: Vec<HabitFrequencyChangeData>,
+    /// Missing from exports made before goals and tasks had attachments
+    #[serde(default)]
+    pub attachments: Vec<AttachmentData>,
+    /// Missing from exports made before focus sessions were timed
+    #[serde(default)]
+    pub focus_sessions: Vec<FocusSessionData>,
+    /// Missing from exports made before reminders were backed up
+    #[serde(default)]
+    pub notification_schedules: Vec<NotificationScheduleData>,
+    #[serde(default)]
+    pub notification_history: Vec<NotificationHistoryData>,
+    pub export_metadata: ExportMetadata,
+}
+
+/// A single habit and its completions, exported for sharing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HabitExport {
+    pub habit: HabitData,
+    pub habit_completions: Vec<HabitCompletionData>,
     pub export_metadata: ExportMetadata,
 }
 
@@ -82,6 +399,9 @@ pub struct GoalData {
     pub deadline: Option<String>,
     pub created_at: String,
     pub updated_at: String,
+    /// Missing from exports made before goal completion was timestamped
+    #[serde(default)]
+    pub completed_at: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -116,6 +436,9 @@ pub struct HabitData {
     pub reminder_time: String,
     pub created_at: String,
     pub updated_at: String,
+    /// Missing from exports made before habits had a completion threshold
+    #[serde(default = "default_completion_threshold")]
+    pub completion_threshold: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,25 +451,176 @@ pub struct HabitCompletionData {
     pub target_amount: f64,
     pub completed_at: Option<String>,
     pub note: String,
-    pub mood: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_moods")]
+    pub mood: Vec<String>,
     pub difficulty: Option<String>,
     pub skipped: bool,
     pub created_at: String,
     pub updated_at: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HabitPauseData {
+    pub id: i64,
+    pub habit_id: String,
+    pub start_date: String,
+    pub end_date: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HabitFrequencyChangeData {
+    pub id: i64,
+    pub habit_id: String,
+    pub frequency_type: String,
+    pub frequency_value: String,
+    pub effective_from: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AttachmentData {
+    pub id: i64,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub kind: String,
+    pub value: String,
+    pub label: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusSessionData {
+    pub id: i64,
+    pub task_id: Option<String>,
+    pub started_at: String,
+    pub ended_at: Option<String>,
+    pub duration_seconds: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationScheduleData {
+    pub id: i64,
+    pub habit_id: String,
+    pub habit_name: String,
+    pub scheduled_time: String,
+    pub notification_type: String,
+    pub is_recurring: bool,
+    pub schedule_data: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationHistoryData {
+    pub id: String,
+    pub habit_id: String,
+    pub sent_at: String,
+    pub notification_type: String,
+    pub opened: bool,
+    pub action_taken: Option<String>,
+    pub payload_data: String,
+    pub created_at: String,
+}
+
+/// How an import treats existing data
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    /// Wipe existing data and settings, then load the import
+    #[default]
+    Replace,
+    /// Keep existing data and settings. Records are matched by id and the newer `updated_at` wins.
+    Merge,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    pub goals: usize,
+    pub tasks: usize,
+    pub habits: usize,
+    pub completions: usize,
+    pub settings_applied: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportValidation {
+    pub valid: bool,
+    /// Version of the app that made the export
+    pub version: String,
+    pub format_version: u32,
+    pub goals: usize,
+    pub tasks: usize,
+    pub habits: usize,
+    pub completions: usize,
+    pub duplicate_ids: Vec<String>,
+    pub errors: Vec<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExportMetadata {
     pub export_date: String,
-    pub version: String,
+    /// Version of the app that made the export, for information only
+    #[serde(rename = "version")]
+    pub app_version: String,
+    /// Layout of the file, see `EXPORT_FORMAT_VERSION`.
+    /// Missing from exports made before the layout was versioned on its own
+    #[serde(default = "legacy_export_format_version")]
+    pub format_version: u32,
+    /// Missing from exports made before the app name was recorded
+    #[serde(default)]
+    pub app_name: Option<String>,
     pub total_records: usize,
+    /// Set when the export only holds part of the data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filters: Option<ExportOptions>,
+}
+
+/// Kinds of records a filtered export can hold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportEntity {
+    Goals,
+    Tasks,
+    Habits,
+    HabitCompletions,
+    HabitPauses,
+    HabitFrequencyHistory,
+    Attachments,
+    FocusSessions,
+    NotificationSchedules,
+    NotificationHistory,
+}
+
+/// Which slice of the data `export_filtered` writes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportOptions {
+    /// Kinds of records to include, all of them when empty
+    #[serde(default)]
+    pub entities: Vec<ExportEntity>,
+    /// Earliest day to include (YYYY-MM-DD). Completions are matched by their date and
+    /// everything else by the day it was created.
+    #[serde(default)]
+    pub start_date: Option<String>,
+    /// Last day to include (YYYY-MM-DD)
+    #[serde(default)]
+    pub end_date: Option<String>,
+}
+
+impl ExportOptions {
+    fn includes(&self, entity: ExportEntity) -> bool {
+        self.entities.is_empty() || self.entities.contains(&entity)
+    }
 }
 
 // ============================================================================
 // DATABASE HELPER FUNCTIONS
 // ============================================================================
 
-fn save_settings_to_db_impl(conn: &rusqlite::Connection, settings: &AppSettings) -> Result<(), String> {
+pub(crate) fn save_settings_to_db_impl(conn: &rusqlite::Connection, settings: &AppSettings) -> Result<(), String> {
     let json_data = serde_json::to_string(settings)
         .map_err(|e| format!("Failed to serialize settings: {}", e))?;
 
@@ -163,41 +637,136 @@ fn save_settings_to_db_impl(conn: &rusqlite::Connection, settings: &AppSettings)
     Ok(())
 }
 
-fn load_settings_from_db(state: &State<AppState>) -> Result<Option<AppSettings>, String> {
-    let conn = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+/// Store the default settings unless a settings row already exists, so saved settings are never overwritten
+pub(crate) fn ensure_default_settings(conn: &rusqlite::Connection) -> rusqlite::Result<()> {
+    let json_data = serde_json::to_string(&AppSettings::default())
+        .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
 
-    let mut stmt = conn.prepare("SELECT data FROM settings WHERE id = 1")
-        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    conn.execute(
+        "INSERT INTO settings (id, data, updated_at)
+         SELECT 1, ?1, datetime('now')
+         WHERE NOT EXISTS (SELECT 1 FROM settings WHERE id = 1)",
+        rusqlite::params![json_data],
+    )?;
 
-    let result = stmt.query_row([], |row| {
-        let json_data: String = row.get(0)?;
-        Ok(json_data)
-    });
+    Ok(())
+}
 
-    match result {
-        Ok(json_data) => {
-            let settings: AppSettings = serde_json::from_str(&json_data)
-                .map_err(|e| format!("Failed to deserialize settings: {}", e))?;
-            Ok(Some(settings))
+pub(crate) fn load_settings_from_db(state: &State<AppState>) -> Result<Option<AppSettings>, String> {
+    with_connection(state, |conn| {
+        let mut stmt = conn.prepare("SELECT data FROM settings WHERE id = 1")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let result = stmt.query_row([], |row| {
+            let json_data: String = row.get(0)?;
+            Ok(json_data)
+        });
+
+        match result {
+            Ok(json_data) => {
+                let settings: AppSettings = serde_json::from_str(&json_data)
+                    .map_err(|e| format!("Failed to deserialize settings: {}", e))?;
+                Ok(Some(settings))
+            }
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(format!("Database error: {}", e)),
         }
-        Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
-        Err(e) => Err(format!("Database error: {}", e)),
+    })
+}
+
+/// The configured timezone. None for "auto", unknown zones and missing settings,
+/// which all mean the system's local time.
+fn configured_timezone(settings: Option<&AppSettings>) -> Option<chrono_tz::Tz> {
+    settings
+        .map(|settings| settings.appearance.timezone.as_str())
+        .filter(|timezone| *timezone != "auto")
+        .and_then(|timezone| timezone.parse::<chrono_tz::Tz>().ok())
+}
+
+/// Current wall-clock time in the configured timezone.
+/// "auto", unknown zones and missing settings fall back to the system's local time.
+pub(crate) fn configured_now(settings: Option<&AppSettings>) -> chrono::NaiveDateTime {
+    match configured_timezone(settings) {
+        Some(timezone) => chrono::Utc::now().with_timezone(&timezone).naive_local(),
+        None => chrono::Local::now().naive_local(),
+    }
+}
+
+/// Instant `date` begins in the configured timezone
+pub(crate) fn configured_day_start(
+    settings: Option<&AppSettings>,
+    date: chrono::NaiveDate,
+) -> chrono::DateTime<chrono::Utc> {
+    let midnight = date.and_time(chrono::NaiveTime::MIN);
+
+    match configured_timezone(settings) {
+        Some(timezone) => local_to_utc(&timezone, midnight),
+        None => local_to_utc(&chrono::Local, midnight),
+    }
+}
+
+/// Resolve a wall-clock time, moving past a daylight saving gap that skips it
+fn local_to_utc<Tz: chrono::TimeZone>(timezone: &Tz, local: chrono::NaiveDateTime) -> chrono::DateTime<chrono::Utc> {
+    timezone
+        .from_local_datetime(&local)
+        .earliest()
+        .or_else(|| timezone.from_local_datetime(&(local + chrono::Duration::hours(1))).earliest())
+        .map_or_else(|| local.and_utc(), |time| time.with_timezone(&chrono::Utc))
+}
+
+/// First day of the week from the appearance settings, Sunday unless set to Monday
+pub(crate) fn configured_week_start(settings: Option<&AppSettings>) -> chrono::Weekday {
+    match settings.map(|settings| settings.appearance.week_starts_on.as_str()) {
+        Some("monday") => chrono::Weekday::Mon,
+        _ => chrono::Weekday::Sun,
+    }
+}
+
+/// Missed days a streak tolerates from the habit settings, none by default
+pub(crate) fn configured_streak_grace_days(settings: Option<&AppSettings>) -> u32 {
+    settings.map_or(0, |settings| settings.habits.streak_grace_days)
+}
+
+/// Row cap for the create commands from the data settings, None when disabled
+pub(crate) fn configured_row_limit(settings: Option<&AppSettings>) -> Option<u32> {
+    settings.and_then(|settings| settings.data.max_rows_per_table)
+}
+
+/// Reload the cached close behavior from the stored settings
+pub fn refresh_close_behavior(state: &State<AppState>, app_handle: &AppHandle) -> Result<(), String> {
+    let behavior = load_settings_from_db(state)?
+        .map(|settings| settings.system.close_behavior)
+        .unwrap_or_default();
+
+    if let Some(cache) = app_handle.try_state::<CloseBehaviorState>() {
+        cache.set(behavior);
     }
+
+    Ok(())
 }
 
 // ============================================================================
 // DATA EXPORT FUNCTIONS
 // ============================================================================
 
-fn export_goals_data(conn: &rusqlite::Connection) -> Result<Vec<GoalData>, String> {
+/// Inclusive YYYY-MM-DD bounds on the rows an export reads, each optional
+#[derive(Debug, Clone, Copy, Default)]
+struct DateRange<'a> {
+    start: Option<&'a str>,
+    end: Option<&'a str>,
+}
+
+fn export_goals_data(conn: &rusqlite::Connection, range: DateRange) -> Result<Vec<GoalData>, String> {
     let mut stmt = conn.prepare(
-        "SELECT id, title, description, notes, category, priority, status, color, icon, deadline, created_at, updated_at
-         FROM goals"
+        "SELECT id, title, description, notes, category, priority, status, color, icon, deadline, created_at, updated_at,
+                completed_at
+         FROM goals
+         WHERE (?1 IS NULL OR substr(created_at, 1, 10) >= ?1)
+           AND (?2 IS NULL OR substr(created_at, 1, 10) <= ?2)"
     )
     .map_err(|e| format!("Failed to prepare goals statement: {}", e))?;
 
-    let goal_iter = stmt.query_map([], |row| {
+    let goal_iter = stmt.query_map([range.start, range.end], |row| {
         Ok(GoalData {
             id: row.get(0)?,
             title: row.get(1)?,
@@ -211,6 +780,7 @@ fn export_goals_data(conn: &rusqlite::Connection) -> Result<Vec<GoalData>, Strin
             deadline: row.get(9)?,
             created_at: row.get(10)?,
             updated_at: row.get(11)?,
+            completed_at: row.get(12)?,
         })
     })
     .map_err(|e| format!("Failed to query goals: {}", e))?;
@@ -219,14 +789,16 @@ fn export_goals_data(conn: &rusqlite::Connection) -> Result<Vec<GoalData>, Strin
         .map_err(|e| format!("Failed to collect goals: {}", e))
 }
 
-fn export_tasks_data(conn: &rusqlite::Connection) -> Result<Vec<TaskData>, String> {
+fn export_tasks_data(conn: &rusqlite::Connection, range: DateRange) -> Result<Vec<TaskData>, String> {
     let mut stmt = conn.prepare(
         "SELECT id, title, done, goal_id, parent_task_id, due_date, priority, created_at, updated_at
-         FROM tasks"
+         FROM tasks
+         WHERE (?1 IS NULL OR substr(created_at, 1, 10) >= ?1)
+           AND (?2 IS NULL OR substr(created_at, 1, 10) <= ?2)"
     )
     .map_err(|e| format!("Failed to prepare tasks statement: {}", e))?;
 
-    let task_iter = stmt.query_map([], |row| {
+    let task_iter = stmt.query_map([range.start, range.end], |row| {
         Ok(TaskData {
             id: row.get(0)?,
             title: row.get(1)?,
@@ -245,15 +817,24 @@ fn export_tasks_data(conn: &rusqlite::Connection) -> Result<Vec<TaskData>, Strin
         .map_err(|e| format!("Failed to collect tasks: {}", e))
 }
 
-fn export_habits_data(conn: &rusqlite::Connection) -> Result<Vec<HabitData>, String> {
+/// Export every habit, or only the one with `habit_id`
+fn export_habits_data(
+    conn: &rusqlite::Connection,
+    habit_id: Option<&str>,
+    range: DateRange,
+) -> Result<Vec<HabitData>, String> {
     let mut stmt = conn.prepare(
         "SELECT id, name, category, icon, color, target_amount, unit, frequency_type, frequency_value,
-                priority, notes, linked_goals, start_date, reminder_enabled, reminder_time, created_at, updated_at
-         FROM habits"
+                priority, notes, linked_goals, start_date, reminder_enabled, reminder_time, created_at, updated_at,
+                completion_threshold
+         FROM habits
+         WHERE (?1 IS NULL OR id = ?1)
+           AND (?2 IS NULL OR substr(created_at, 1, 10) >= ?2)
+           AND (?3 IS NULL OR substr(created_at, 1, 10) <= ?3)"
     )
     .map_err(|e| format!("Failed to prepare habits statement: {}", e))?;
 
-    let habit_iter = stmt.query_map([], |row| {
+    let habit_iter = stmt.query_map([habit_id, range.start, range.end], |row| {
         Ok(HabitData {
             id: row.get(0)?,
             name: row.get(1)?,
@@ -272,6 +853,7 @@ fn export_habits_data(conn: &rusqlite::Connection) -> Result<Vec<HabitData>, Str
             reminder_time: row.get(14)?,
             created_at: row.get(15)?,
             updated_at: row.get(16)?,
+            completion_threshold: row.get(17)?,
         })
     })
     .map_err(|e| format!("Failed to query habits: {}", e))?;
@@ -280,15 +862,23 @@ fn export_habits_data(conn: &rusqlite::Connection) -> Result<Vec<HabitData>, Str
         .map_err(|e| format!("Failed to collect habits: {}", e))
 }
 
-fn export_habit_completions_data(conn: &rusqlite::Connection) -> Result<Vec<HabitCompletionData>, String> {
+/// Export every completion, or only those of the habit with `habit_id`
+fn export_habit_completions_data(
+    conn: &rusqlite::Connection,
+    habit_id: Option<&str>,
+    range: DateRange,
+) -> Result<Vec<HabitCompletionData>, String> {
     let mut stmt = conn.prepare(
         "SELECT id, habit_id, date, completed, actual_amount, target_amount, completed_at, note,
                 mood, difficulty, skipped, created_at, updated_at
-         FROM habit_completions"
+         FROM habit_completions
+         WHERE (?1 IS NULL OR habit_id = ?1)
+           AND (?2 IS NULL OR date >= ?2)
+           AND (?3 IS NULL OR date <= ?3)"
     )
     .map_err(|e| format!("Failed to prepare habit completions statement: {}", e))?;
 
-    let completion_iter = stmt.query_map([], |row| {
+    let completion_iter = stmt.query_map([habit_id, range.start, range.end], |row| {
         Ok(HabitCompletionData {
             id: row.get(0)?,
             habit_id: row.get(1)?,
@@ -298,7 +888,7 @@ fn export_habit_completions_data(conn: &rusqlite::Connection) -> Result<Vec<Habi
             target_amount: row.get(5)?,
             completed_at: row.get(6)?,
             note: row.get(7)?,
-            mood: row.get(8)?,
+            mood: moods_from_column(row.get(8)?),
             difficulty: row.get(9)?,
             skipped: row.get::<_, i64>(10)? != 0,
             created_at: row.get(11)?,
@@ -311,110 +901,768 @@ fn export_habit_completions_data(conn: &rusqlite::Connection) -> Result<Vec<Habi
         .map_err(|e| format!("Failed to collect habit completions: {}", e))
 }
 
-// ============================================================================
-// DATA IMPORT FUNCTIONS
-// ============================================================================
-
-fn import_goals_data(conn: &rusqlite::Transaction, goals: &[GoalData]) -> Result<(), String> {
-    // Clear existing data (tasks first due to foreign key)
-    conn.execute("DELETE FROM tasks", [])
-        .map_err(|e| format!("Failed to clear tasks: {}", e))?;
-    conn.execute("DELETE FROM goals", [])
-        .map_err(|e| format!("Failed to clear goals: {}", e))?;
-
+fn export_habit_pauses_data(conn: &rusqlite::Connection, range: DateRange) -> Result<Vec<HabitPauseData>, String> {
     let mut stmt = conn.prepare(
-        "INSERT INTO goals (id, title, description, notes, category, priority, status, color, icon, deadline, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)"
+        "SELECT id, habit_id, start_date, end_date, created_at FROM habit_pauses
+         WHERE (?1 IS NULL OR substr(created_at, 1, 10) >= ?1)
+           AND (?2 IS NULL OR substr(created_at, 1, 10) <= ?2)"
     )
-    .map_err(|e| format!("Failed to prepare goals insert statement: {}", e))?;
+    .map_err(|e| format!("Failed to prepare habit pauses statement: {}", e))?;
 
-    for goal in goals {
-        stmt.execute(rusqlite::params![
-            goal.id, goal.title, goal.description, goal.notes, goal.category, goal.priority,
-            goal.status, goal.color, goal.icon, goal.deadline, goal.created_at, goal.updated_at
-        ])
-        .map_err(|e| format!("Failed to insert goal {}: {}", goal.id, e))?;
-    }
+    let pause_iter = stmt.query_map([range.start, range.end], |row| {
+        Ok(HabitPauseData {
+            id: row.get(0)?,
+            habit_id: row.get(1)?,
+            start_date: row.get(2)?,
+            end_date: row.get(3)?,
+            created_at: row.get(4)?,
+        })
+    })
+    .map_err(|e| format!("Failed to query habit pauses: {}", e))?;
 
-    Ok(())
+    pause_iter.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect habit pauses: {}", e))
 }
 
-fn import_tasks_data(conn: &rusqlite::Transaction, tasks: &[TaskData]) -> Result<(), String> {
+fn export_habit_frequency_history_data(
+    conn: &rusqlite::Connection,
+    range: DateRange,
+) -> Result<Vec<HabitFrequencyChangeData>, String> {
     let mut stmt = conn.prepare(
-        "INSERT INTO tasks (id, title, done, goal_id, parent_task_id, due_date, priority, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+        "SELECT id, habit_id, frequency_type, frequency_value, effective_from, created_at
+         FROM habit_frequency_history
+         WHERE (?1 IS NULL OR substr(created_at, 1, 10) >= ?1)
+           AND (?2 IS NULL OR substr(created_at, 1, 10) <= ?2)"
     )
-    .map_err(|e| format!("Failed to prepare tasks insert statement: {}", e))?;
+    .map_err(|e| format!("Failed to prepare habit frequency history statement: {}", e))?;
 
-    for task in tasks {
-        stmt.execute(rusqlite::params![
-            task.id,
-            task.title,
-            task.done as i64,
-            task.goal_id,
-            task.parent_task_id,
-            task.due_date,
-            task.priority,
-            task.created_at,
-            task.updated_at
-        ])
-        .map_err(|e| format!("Failed to insert task {}: {}", task.id, e))?;
-    }
+    let change_iter = stmt.query_map([range.start, range.end], |row| {
+        Ok(HabitFrequencyChangeData {
+            id: row.get(0)?,
+            habit_id: row.get(1)?,
+            frequency_type: row.get(2)?,
+            frequency_value: row.get(3)?,
+            effective_from: row.get(4)?,
+            created_at: row.get(5)?,
+        })
+    })
+    .map_err(|e| format!("Failed to query habit frequency history: {}", e))?;
 
-    Ok(())
+    change_iter.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect habit frequency history: {}", e))
 }
 
-fn import_habits_data(conn: &rusqlite::Transaction, habits: &[HabitData]) -> Result<(), String> {
-    // Clear existing data (completions first due to foreign key)
-    conn.execute("DELETE FROM habit_completions", [])
-        .map_err(|e| format!("Failed to clear habit completions: {}", e))?;
-    conn.execute("DELETE FROM habits", [])
-        .map_err(|e| format!("Failed to clear habits: {}", e))?;
-
+fn export_attachments_data(conn: &rusqlite::Connection, range: DateRange) -> Result<Vec<AttachmentData>, String> {
     let mut stmt = conn.prepare(
-        "INSERT INTO habits (id, name, category, icon, color, target_amount, unit, frequency_type, frequency_value,
-                            priority, notes, linked_goals, start_date, reminder_enabled, reminder_time, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)"
+        "SELECT id, entity_type, entity_id, kind, value, label, created_at FROM attachments
+         WHERE (?1 IS NULL OR substr(created_at, 1, 10) >= ?1)
+           AND (?2 IS NULL OR substr(created_at, 1, 10) <= ?2)"
     )
-    .map_err(|e| format!("Failed to prepare habits insert statement: {}", e))?;
+    .map_err(|e| format!("Failed to prepare attachments statement: {}", e))?;
 
-    for habit in habits {
-        stmt.execute(rusqlite::params![
-            habit.id, habit.name, habit.category, habit.icon, habit.color, habit.target_amount,
-            habit.unit, habit.frequency_type, habit.frequency_value, habit.priority, habit.notes,
-            habit.linked_goals, habit.start_date, habit.reminder_enabled as i64, habit.reminder_time,
-            habit.created_at, habit.updated_at
-        ])
-        .map_err(|e| format!("Failed to insert habit {}: {}", habit.id, e))?;
-    }
+    let attachment_iter = stmt.query_map([range.start, range.end], |row| {
+        Ok(AttachmentData {
+            id: row.get(0)?,
+            entity_type: row.get(1)?,
+            entity_id: row.get(2)?,
+            kind: row.get(3)?,
+            value: row.get(4)?,
+            label: row.get(5)?,
+            created_at: row.get(6)?,
+        })
+    })
+    .map_err(|e| format!("Failed to query attachments: {}", e))?;
 
-    Ok(())
+    attachment_iter.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect attachments: {}", e))
 }
 
-fn import_habit_completions_data(conn: &rusqlite::Transaction, completions: &[HabitCompletionData]) -> Result<(), String> {
+fn export_focus_sessions_data(conn: &rusqlite::Connection, range: DateRange) -> Result<Vec<FocusSessionData>, String> {
     let mut stmt = conn.prepare(
-        "INSERT INTO habit_completions (id, habit_id, date, completed, actual_amount, target_amount, completed_at,
-                                      note, mood, difficulty, skipped, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)"
+        "SELECT id, task_id, started_at, ended_at, duration_seconds FROM focus_sessions
+         WHERE (?1 IS NULL OR substr(started_at, 1, 10) >= ?1)
+           AND (?2 IS NULL OR substr(started_at, 1, 10) <= ?2)"
     )
-    .map_err(|e| format!("Failed to prepare habit completions insert statement: {}", e))?;
+    .map_err(|e| format!("Failed to prepare focus sessions statement: {}", e))?;
 
-    for completion in completions {
-        stmt.execute(rusqlite::params![
-            completion.id, completion.habit_id, completion.date, completion.completed as i64,
-            completion.actual_amount, completion.target_amount, completion.completed_at,
-            completion.note, completion.mood, completion.difficulty, completion.skipped as i64,
-            completion.created_at, completion.updated_at
-        ])
-        .map_err(|e| format!("Failed to insert habit completion {}: {}", completion.id, e))?;
-    }
+    let session_iter = stmt.query_map([range.start, range.end], |row| {
+        Ok(FocusSessionData {
+            id: row.get(0)?,
+            task_id: row.get(1)?,
+            started_at: row.get(2)?,
+            ended_at: row.get(3)?,
+            duration_seconds: row.get(4)?,
+        })
+    })
+    .map_err(|e| format!("Failed to query focus sessions: {}", e))?;
 
-    Ok(())
+    session_iter.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect focus sessions: {}", e))
 }
 
-// ============================================================================
-// TAURI COMMANDS
-// ============================================================================
+fn export_notification_schedules_data(
+    conn: &rusqlite::Connection,
+    range: DateRange,
+) -> Result<Vec<NotificationScheduleData>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, habit_id, habit_name, scheduled_time, notification_type, is_recurring, schedule_data,
+                created_at, updated_at
+         FROM notification_schedules
+         WHERE (?1 IS NULL OR substr(created_at, 1, 10) >= ?1)
+           AND (?2 IS NULL OR substr(created_at, 1, 10) <= ?2)"
+    )
+    .map_err(|e| format!("Failed to prepare notification schedules statement: {}", e))?;
+
+    let schedule_iter = stmt.query_map([range.start, range.end], |row| {
+        Ok(NotificationScheduleData {
+            id: row.get(0)?,
+            habit_id: row.get(1)?,
+            habit_name: row.get(2)?,
+            scheduled_time: row.get(3)?,
+            notification_type: row.get(4)?,
+            is_recurring: row.get::<_, i64>(5)? != 0,
+            schedule_data: row.get(6)?,
+            created_at: row.get(7)?,
+            updated_at: row.get(8)?,
+        })
+    })
+    .map_err(|e| format!("Failed to query notification schedules: {}", e))?;
+
+    schedule_iter.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect notification schedules: {}", e))
+}
+
+fn export_notification_history_data(
+    conn: &rusqlite::Connection,
+    range: DateRange,
+) -> Result<Vec<NotificationHistoryData>, String> {
+    let mut stmt = conn.prepare(
+        "SELECT id, habit_id, sent_at, notification_type, opened, action_taken, payload_data, created_at
+         FROM notification_history
+         WHERE (?1 IS NULL OR substr(created_at, 1, 10) >= ?1)
+           AND (?2 IS NULL OR substr(created_at, 1, 10) <= ?2)"
+    )
+    .map_err(|e| format!("Failed to prepare notification history statement: {}", e))?;
+
+    let history_iter = stmt.query_map([range.start, range.end], |row| {
+        Ok(NotificationHistoryData {
+            id: row.get(0)?,
+            habit_id: row.get(1)?,
+            sent_at: row.get(2)?,
+            notification_type: row.get(3)?,
+            opened: row.get::<_, i64>(4)? != 0,
+            action_taken: row.get(5)?,
+            payload_data: row.get(6)?,
+            created_at: row.get(7)?,
+        })
+    })
+    .map_err(|e| format!("Failed to query notification history: {}", e))?;
+
+    history_iter.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect notification history: {}", e))
+}
+
+// ============================================================================
+// DATA IMPORT FUNCTIONS
+// ============================================================================
+
+/// Layout version written to export files. Raise it when a change means older apps can't read them.
+/// Every format up to this one can be imported.
+/// 2: notification schedules and history, which older apps would drop without a word.
+pub(crate) const EXPORT_FORMAT_VERSION: u32 = 2;
+
+/// Format of exports made before `ExportMetadata.format_version` existed
+fn legacy_export_format_version() -> u32 {
+    1
+}
+
+/// Check that this app understands an export's layout, returning the problem if not
+fn check_format_version(metadata: &ExportMetadata) -> Result<(), String> {
+    if metadata.format_version > EXPORT_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported export format {} from app version {} (this app reads up to format {})",
+            metadata.format_version, metadata.app_version, EXPORT_FORMAT_VERSION
+        ));
+    }
+
+    Ok(())
+}
+
+/// Collect ids that appear more than once, recording each duplicate once
+fn find_duplicates<'a>(ids: impl Iterator<Item = &'a str>, duplicates: &mut Vec<String>) -> HashSet<&'a str> {
+    let mut seen = HashSet::new();
+    for id in ids {
+        if !seen.insert(id) && !duplicates.iter().any(|d| d == id) {
+            duplicates.push(id.to_string());
+        }
+    }
+    seen
+}
+
+/// Check an import file without touching the database
+fn validate_export_data(data: &ExportData) -> ImportValidation {
+    let mut errors = Vec::new();
+    let mut duplicate_ids = Vec::new();
+
+    if let Err(e) = check_format_version(&data.export_metadata) {
+        errors.push(e);
+    }
+
+    let goal_ids = find_duplicates(data.goals.iter().map(|g| g.id.as_str()), &mut duplicate_ids);
+    let task_ids = find_duplicates(data.tasks.iter().map(|t| t.id.as_str()), &mut duplicate_ids);
+    let habit_ids = find_duplicates(data.habits.iter().map(|h| h.id.as_str()), &mut duplicate_ids);
+    find_duplicates(data.habit_completions.iter().map(|c| c.id.as_str()), &mut duplicate_ids);
+    find_duplicates(data.notification_history.iter().map(|n| n.id.as_str()), &mut duplicate_ids);
+
+    for id in &duplicate_ids {
+        errors.push(format!("Duplicate id {}", id));
+    }
+
+    // A filtered export may reference records it left out that already exist on this device.
+    // Those are merged in, so foreign keys are checked against the database at commit instead.
+    let partial = data.export_metadata.filters.is_some();
+
+    for task in &data.tasks {
+        if partial {
+            continue;
+        }
+        if let Some(goal_id) = &task.goal_id {
+            if !goal_ids.contains(goal_id.as_str()) {
+                errors.push(format!("Task {} references missing goal {}", task.id, goal_id));
+            }
+        }
+        if let Some(parent_id) = &task.parent_task_id {
+            if !task_ids.contains(parent_id.as_str()) {
+                errors.push(format!("Task {} references missing parent task {}", task.id, parent_id));
+            }
+        }
+    }
+
+    let mut completion_days = HashSet::new();
+    for pause in &data.habit_pauses {
+        if !partial && !habit_ids.contains(pause.habit_id.as_str()) {
+            errors.push(format!(
+                "Pause {} references missing habit {}",
+                pause.id, pause.habit_id
+            ));
+        }
+    }
+
+    for completion in &data.habit_completions {
+        if !partial && !habit_ids.contains(completion.habit_id.as_str()) {
+            errors.push(format!(
+                "Completion {} references missing habit {}",
+                completion.id, completion.habit_id
+            ));
+        }
+        if !completion_days.insert((completion.habit_id.as_str(), completion.date.as_str())) {
+            errors.push(format!(
+                "Habit {} has more than one completion on {}",
+                completion.habit_id, completion.date
+            ));
+        }
+    }
+
+    let mut frequency_days = HashSet::new();
+    for change in &data.habit_frequency_history {
+        if !partial && !habit_ids.contains(change.habit_id.as_str()) {
+            errors.push(format!(
+                "Frequency change {} references missing habit {}",
+                change.id, change.habit_id
+            ));
+        }
+        if !frequency_days.insert((change.habit_id.as_str(), change.effective_from.as_str())) {
+            errors.push(format!(
+                "Habit {} has more than one frequency change on {}",
+                change.habit_id, change.effective_from
+            ));
+        }
+    }
+
+    for attachment in &data.attachments {
+        let owner_exists = match attachment.entity_type.as_str() {
+            "goal" => goal_ids.contains(attachment.entity_id.as_str()),
+            "task" => task_ids.contains(attachment.entity_id.as_str()),
+            other => {
+                errors.push(format!("Attachment {} has unknown entity type {}", attachment.id, other));
+                continue;
+            }
+        };
+        if !partial && !owner_exists {
+            errors.push(format!(
+                "Attachment {} references missing {} {}",
+                attachment.id, attachment.entity_type, attachment.entity_id
+            ));
+        }
+    }
+
+    for session in &data.focus_sessions {
+        if let Some(task_id) = &session.task_id {
+            if !partial && !task_ids.contains(task_id.as_str()) {
+                errors.push(format!("Focus session {} references missing task {}", session.id, task_id));
+            }
+        }
+    }
+
+    let mut schedule_slots = HashSet::new();
+    for schedule in &data.notification_schedules {
+        if !partial && !habit_ids.contains(schedule.habit_id.as_str()) {
+            errors.push(format!(
+                "Notification schedule {} references missing habit {}",
+                schedule.id, schedule.habit_id
+            ));
+        }
+        if !schedule_slots.insert((schedule.habit_id.as_str(), schedule.scheduled_time.as_str())) {
+            errors.push(format!(
+                "Habit {} has more than one notification scheduled at {}",
+                schedule.habit_id, schedule.scheduled_time
+            ));
+        }
+    }
+
+    for notification in &data.notification_history {
+        if !partial && !habit_ids.contains(notification.habit_id.as_str()) {
+            errors.push(format!(
+                "Notification {} references missing habit {}",
+                notification.id, notification.habit_id
+            ));
+        }
+    }
+
+    ImportValidation {
+        valid: errors.is_empty(),
+        version: data.export_metadata.app_version.clone(),
+        format_version: data.export_metadata.format_version,
+        goals: data.goals.len(),
+        tasks: data.tasks.len(),
+        habits: data.habits.len(),
+        completions: data.habit_completions.len(),
+        duplicate_ids,
+        errors,
+    }
+}
+
+/// Remove existing records, children before parents so foreign keys stay satisfied.
+/// Every child table is cleared explicitly rather than left to ON DELETE actions.
+fn clear_existing_data(conn: &rusqlite::Transaction) -> Result<(), String> {
+    conn.execute("DELETE FROM habit_completions", [])
+        .map_err(|e| format!("Failed to clear habit completions: {}", e))?;
+    conn.execute("DELETE FROM habit_pauses", [])
+        .map_err(|e| format!("Failed to clear habit pauses: {}", e))?;
+    conn.execute("DELETE FROM habit_frequency_history", [])
+        .map_err(|e| format!("Failed to clear habit frequency history: {}", e))?;
+    conn.execute("DELETE FROM habit_goals", [])
+        .map_err(|e| format!("Failed to clear habit goal links: {}", e))?;
+    conn.execute("DELETE FROM focus_sessions", [])
+        .map_err(|e| format!("Failed to clear focus sessions: {}", e))?;
+    conn.execute("DELETE FROM notification_history", [])
+        .map_err(|e| format!("Failed to clear notification history: {}", e))?;
+    conn.execute("DELETE FROM notification_schedules", [])
+        .map_err(|e| format!("Failed to clear notification schedules: {}", e))?;
+    conn.execute("DELETE FROM attachments", [])
+        .map_err(|e| format!("Failed to clear attachments: {}", e))?;
+    conn.execute("DELETE FROM tasks", [])
+        .map_err(|e| format!("Failed to clear tasks: {}", e))?;
+    conn.execute("DELETE FROM habits", [])
+        .map_err(|e| format!("Failed to clear habits: {}", e))?;
+    conn.execute("DELETE FROM goals", [])
+        .map_err(|e| format!("Failed to clear goals: {}", e))?;
+
+    Ok(())
+}
+
+/// Completed goals from older exports take their last update as the completion time
+fn import_goals_data(conn: &rusqlite::Transaction, goals: &[GoalData]) -> Result<(), String> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO goals (id, title, description, notes, category, priority, status, color, icon, deadline, created_at, updated_at,
+                            completed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, COALESCE(?13, CASE WHEN ?7 = 'completed' THEN ?12 END))
+         ON CONFLICT(id) DO UPDATE SET
+            title = excluded.title, description = excluded.description, notes = excluded.notes,
+            category = excluded.category, priority = excluded.priority, status = excluded.status,
+            color = excluded.color, icon = excluded.icon, deadline = excluded.deadline,
+            created_at = excluded.created_at, updated_at = excluded.updated_at,
+            completed_at = excluded.completed_at
+         WHERE excluded.updated_at > goals.updated_at"
+    )
+    .map_err(|e| format!("Failed to prepare goals insert statement: {}", e))?;
+
+    for goal in goals {
+        stmt.execute(rusqlite::params![
+            goal.id, goal.title, goal.description, goal.notes, goal.category, goal.priority,
+            goal.status, goal.color, goal.icon, goal.deadline, goal.created_at, goal.updated_at,
+            goal.completed_at
+        ])
+        .map_err(|e| format!("Failed to insert goal {}: {}", goal.id, e))?;
+    }
+
+    Ok(())
+}
+
+fn import_tasks_data(conn: &rusqlite::Transaction, tasks: &[TaskData]) -> Result<(), String> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO tasks (id, title, done, goal_id, parent_task_id, due_date, priority, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT(id) DO UPDATE SET
+            title = excluded.title, done = excluded.done, goal_id = excluded.goal_id,
+            parent_task_id = excluded.parent_task_id, due_date = excluded.due_date,
+            priority = excluded.priority, created_at = excluded.created_at, updated_at = excluded.updated_at
+         WHERE excluded.updated_at > tasks.updated_at"
+    )
+    .map_err(|e| format!("Failed to prepare tasks insert statement: {}", e))?;
+
+    for task in tasks {
+        stmt.execute(rusqlite::params![
+            task.id,
+            task.title,
+            task.done as i64,
+            task.goal_id,
+            task.parent_task_id,
+            task.due_date,
+            task.priority,
+            task.created_at,
+            task.updated_at
+        ])
+        .map_err(|e| format!("Failed to insert task {}: {}", task.id, e))?;
+    }
+
+    Ok(())
+}
+
+fn import_habits_data(conn: &rusqlite::Transaction, habits: &[HabitData]) -> Result<(), String> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO habits (id, name, category, icon, color, target_amount, unit, frequency_type, frequency_value,
+                            priority, notes, linked_goals, start_date, reminder_enabled, reminder_time, created_at, updated_at,
+                            completion_threshold)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)
+         ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name, category = excluded.category, icon = excluded.icon, color = excluded.color,
+            target_amount = excluded.target_amount, unit = excluded.unit,
+            frequency_type = excluded.frequency_type, frequency_value = excluded.frequency_value,
+            priority = excluded.priority, notes = excluded.notes, linked_goals = excluded.linked_goals,
+            start_date = excluded.start_date, reminder_enabled = excluded.reminder_enabled,
+            reminder_time = excluded.reminder_time, created_at = excluded.created_at,
+            updated_at = excluded.updated_at, completion_threshold = excluded.completion_threshold
+         WHERE excluded.updated_at > habits.updated_at"
+    )
+    .map_err(|e| format!("Failed to prepare habits insert statement: {}", e))?;
+
+    for habit in habits {
+        stmt.execute(rusqlite::params![
+            habit.id, habit.name, habit.category, habit.icon, habit.color, habit.target_amount,
+            habit.unit, habit.frequency_type, habit.frequency_value, habit.priority, habit.notes,
+            habit.linked_goals, habit.start_date, habit.reminder_enabled as i64, habit.reminder_time,
+            habit.created_at, habit.updated_at, habit.completion_threshold
+        ])
+        .map_err(|e| format!("Failed to insert habit {}: {}", habit.id, e))?;
+    }
+
+    Ok(())
+}
+
+/// Each device may have logged the same day under its own id, so completions are also
+/// matched by habit and date
+fn import_habit_completions_data(conn: &rusqlite::Transaction, completions: &[HabitCompletionData]) -> Result<(), String> {
+    let mut stmt = conn.prepare(
+        "INSERT INTO habit_completions (id, habit_id, date, completed, actual_amount, target_amount, completed_at,
+                                      note, mood, difficulty, skipped, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+         ON CONFLICT(id) DO UPDATE SET
+            completed = excluded.completed, actual_amount = excluded.actual_amount,
+            target_amount = excluded.target_amount, completed_at = excluded.completed_at,
+            note = excluded.note, mood = excluded.mood, difficulty = excluded.difficulty,
+            skipped = excluded.skipped, updated_at = excluded.updated_at
+         WHERE excluded.updated_at > habit_completions.updated_at
+         ON CONFLICT(habit_id, date) DO UPDATE SET
+            completed = excluded.completed, actual_amount = excluded.actual_amount,
+            target_amount = excluded.target_amount, completed_at = excluded.completed_at,
+            note = excluded.note, mood = excluded.mood, difficulty = excluded.difficulty,
+            skipped = excluded.skipped, updated_at = excluded.updated_at
+         WHERE excluded.updated_at > habit_completions.updated_at"
+    )
+    .map_err(|e| format!("Failed to prepare habit completions insert statement: {}", e))?;
+
+    for completion in completions {
+        stmt.execute(rusqlite::params![
+            completion.id, completion.habit_id, completion.date, completion.completed as i64,
+            completion.actual_amount, completion.target_amount, completion.completed_at,
+            completion.note, moods_to_column(&completion.mood), completion.difficulty, completion.skipped as i64,
+            completion.created_at, completion.updated_at
+        ])
+        .map_err(|e| format!("Failed to insert habit completion {}: {}", completion.id, e))?;
+    }
+
+    Ok(())
+}
+
+fn import_habit_pauses_data(
+    conn: &rusqlite::Transaction,
+    pauses: &[HabitPauseData],
+    mode: ImportMode,
+) -> Result<(), String> {
+    // Pause ids are per-device row ids, so merged pauses get fresh ids and are matched
+    // by habit and start date instead
+    let sql = match mode {
+        ImportMode::Replace => {
+            "INSERT INTO habit_pauses (habit_id, start_date, end_date, created_at, id)
+             VALUES (?1, ?2, ?3, ?4, ?5)"
+        }
+        ImportMode::Merge => {
+            "INSERT INTO habit_pauses (habit_id, start_date, end_date, created_at)
+             SELECT ?1, ?2, ?3, ?4
+             WHERE NOT EXISTS (SELECT 1 FROM habit_pauses WHERE habit_id = ?1 AND start_date = ?2)"
+        }
+    };
+
+    let mut stmt = conn.prepare(sql)
+        .map_err(|e| format!("Failed to prepare habit pauses insert statement: {}", e))?;
+
+    for pause in pauses {
+        let result = match mode {
+            ImportMode::Replace => stmt.execute(rusqlite::params![
+                pause.habit_id, pause.start_date, pause.end_date, pause.created_at, pause.id
+            ]),
+            ImportMode::Merge => stmt.execute(rusqlite::params![
+                pause.habit_id, pause.start_date, pause.end_date, pause.created_at
+            ]),
+        };
+
+        result.map_err(|e| format!("Failed to insert habit pause {}: {}", pause.id, e))?;
+    }
+
+    Ok(())
+}
+
+fn import_habit_frequency_history_data(
+    conn: &rusqlite::Transaction,
+    changes: &[HabitFrequencyChangeData],
+    mode: ImportMode,
+) -> Result<(), String> {
+    // Ids are per-device row ids, so merged changes get fresh ids. A change this device
+    // already has for the same day is kept.
+    let sql = match mode {
+        ImportMode::Replace => {
+            "INSERT INTO habit_frequency_history (habit_id, frequency_type, frequency_value, effective_from,
+                                                 created_at, id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)"
+        }
+        ImportMode::Merge => {
+            "INSERT INTO habit_frequency_history (habit_id, frequency_type, frequency_value, effective_from,
+                                                 created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(habit_id, effective_from) DO NOTHING"
+        }
+    };
+
+    let mut stmt = conn.prepare(sql)
+        .map_err(|e| format!("Failed to prepare habit frequency history insert statement: {}", e))?;
+
+    for change in changes {
+        let result = match mode {
+            ImportMode::Replace => stmt.execute(rusqlite::params![
+                change.habit_id, change.frequency_type, change.frequency_value, change.effective_from,
+                change.created_at, change.id
+            ]),
+            ImportMode::Merge => stmt.execute(rusqlite::params![
+                change.habit_id, change.frequency_type, change.frequency_value, change.effective_from,
+                change.created_at
+            ]),
+        };
+
+        result.map_err(|e| format!("Failed to insert frequency change {}: {}", change.id, e))?;
+    }
+
+    Ok(())
+}
+
+fn import_attachments_data(
+    conn: &rusqlite::Transaction,
+    attachments: &[AttachmentData],
+    mode: ImportMode,
+) -> Result<(), String> {
+    // Attachment ids are per-device row ids too, so merged attachments get fresh ids
+    // and are matched by owner and value instead
+    let sql = match mode {
+        ImportMode::Replace => {
+            "INSERT INTO attachments (entity_type, entity_id, kind, value, label, created_at, id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)"
+        }
+        ImportMode::Merge => {
+            "INSERT INTO attachments (entity_type, entity_id, kind, value, label, created_at)
+             SELECT ?1, ?2, ?3, ?4, ?5, ?6
+             WHERE NOT EXISTS (
+                SELECT 1 FROM attachments WHERE entity_type = ?1 AND entity_id = ?2 AND value = ?4
+             )"
+        }
+    };
+
+    let mut stmt = conn.prepare(sql)
+        .map_err(|e| format!("Failed to prepare attachments insert statement: {}", e))?;
+
+    for attachment in attachments {
+        let result = match mode {
+            ImportMode::Replace => stmt.execute(rusqlite::params![
+                attachment.entity_type, attachment.entity_id, attachment.kind, attachment.value,
+                attachment.label, attachment.created_at, attachment.id
+            ]),
+            ImportMode::Merge => stmt.execute(rusqlite::params![
+                attachment.entity_type, attachment.entity_id, attachment.kind, attachment.value,
+                attachment.label, attachment.created_at
+            ]),
+        };
+
+        result.map_err(|e| format!("Failed to insert attachment {}: {}", attachment.id, e))?;
+    }
+
+    Ok(())
+}
+
+fn import_focus_sessions_data(
+    conn: &rusqlite::Transaction,
+    sessions: &[FocusSessionData],
+    mode: ImportMode,
+) -> Result<(), String> {
+    // Session ids are per-device row ids, so merged sessions get fresh ids and are matched
+    // by when they started instead
+    let sql = match mode {
+        ImportMode::Replace => {
+            "INSERT INTO focus_sessions (task_id, started_at, ended_at, duration_seconds, id)
+             VALUES (?1, ?2, ?3, ?4, ?5)"
+        }
+        ImportMode::Merge => {
+            "INSERT INTO focus_sessions (task_id, started_at, ended_at, duration_seconds)
+             SELECT ?1, ?2, ?3, ?4
+             WHERE NOT EXISTS (SELECT 1 FROM focus_sessions WHERE started_at = ?2)"
+        }
+    };
+
+    let mut stmt = conn.prepare(sql)
+        .map_err(|e| format!("Failed to prepare focus sessions insert statement: {}", e))?;
+
+    for session in sessions {
+        let result = match mode {
+            ImportMode::Replace => stmt.execute(rusqlite::params![
+                session.task_id, session.started_at, session.ended_at, session.duration_seconds, session.id
+            ]),
+            ImportMode::Merge => stmt.execute(rusqlite::params![
+                session.task_id, session.started_at, session.ended_at, session.duration_seconds
+            ]),
+        };
+
+        result.map_err(|e| format!("Failed to insert focus session {}: {}", session.id, e))?;
+    }
+
+    Ok(())
+}
+
+fn import_notification_schedules_data(
+    conn: &rusqlite::Transaction,
+    schedules: &[NotificationScheduleData],
+    mode: ImportMode,
+) -> Result<(), String> {
+    // Like pauses, schedule ids are per-device row ids, so merged schedules get fresh ids
+    // and are matched by habit and time instead
+    let sql = match mode {
+        ImportMode::Replace => {
+            "INSERT INTO notification_schedules (habit_id, habit_name, scheduled_time, notification_type, is_recurring,
+                                                schedule_data, created_at, updated_at, id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)"
+        }
+        ImportMode::Merge => {
+            "INSERT INTO notification_schedules (habit_id, habit_name, scheduled_time, notification_type, is_recurring,
+                                                schedule_data, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+             ON CONFLICT(habit_id, scheduled_time) DO UPDATE SET
+                habit_name = excluded.habit_name, notification_type = excluded.notification_type,
+                is_recurring = excluded.is_recurring, schedule_data = excluded.schedule_data,
+                updated_at = excluded.updated_at
+             WHERE excluded.updated_at > notification_schedules.updated_at"
+        }
+    };
+
+    let mut stmt = conn.prepare(sql)
+        .map_err(|e| format!("Failed to prepare notification schedules insert statement: {}", e))?;
+
+    for schedule in schedules {
+        let result = match mode {
+            ImportMode::Replace => stmt.execute(rusqlite::params![
+                schedule.habit_id, schedule.habit_name, schedule.scheduled_time, schedule.notification_type,
+                schedule.is_recurring as i64, schedule.schedule_data, schedule.created_at, schedule.updated_at,
+                schedule.id
+            ]),
+            ImportMode::Merge => stmt.execute(rusqlite::params![
+                schedule.habit_id, schedule.habit_name, schedule.scheduled_time, schedule.notification_type,
+                schedule.is_recurring as i64, schedule.schedule_data, schedule.created_at, schedule.updated_at
+            ]),
+        };
+
+        result.map_err(|e| format!("Failed to insert notification schedule {}: {}", schedule.id, e))?;
+    }
+
+    Ok(())
+}
+
+/// History is never edited once sent, so notifications already on this device are kept
+fn import_notification_history_data(
+    conn: &rusqlite::Transaction,
+    history: &[NotificationHistoryData],
+) -> Result<(), String> {
+    let mut stmt = conn.prepare(
+        "INSERT OR IGNORE INTO notification_history (id, habit_id, sent_at, notification_type, opened, action_taken,
+                                                     payload_data, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)"
+    )
+    .map_err(|e| format!("Failed to prepare notification history insert statement: {}", e))?;
+
+    for notification in history {
+        stmt.execute(rusqlite::params![
+            notification.id, notification.habit_id, notification.sent_at, notification.notification_type,
+            notification.opened as i64, notification.action_taken, notification.payload_data,
+            notification.created_at
+        ])
+        .map_err(|e| format!("Failed to insert notification {}: {}", notification.id, e))?;
+    }
+
+    Ok(())
+}
+
+/// After a replace, check that every imported record landed by comparing each table's row
+/// count with the import. Run inside the transaction so a mismatch rolls the import back.
+fn verify_import_counts(conn: &rusqlite::Transaction, data: &ExportData) -> Result<(), String> {
+    let expected = [
+        ("goals", data.goals.len()),
+        ("tasks", data.tasks.len()),
+        ("habits", data.habits.len()),
+        ("habit_completions", data.habit_completions.len()),
+        ("habit_pauses", data.habit_pauses.len()),
+        ("habit_frequency_history", data.habit_frequency_history.len()),
+        ("attachments", data.attachments.len()),
+        ("focus_sessions", data.focus_sessions.len()),
+        ("notification_schedules", data.notification_schedules.len()),
+        ("notification_history", data.notification_history.len()),
+    ];
+
+    let mut mismatches = Vec::new();
+    for (table, expected) in expected {
+        let actual: i64 = conn
+            .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+            .map_err(|e| format!("Failed to count {}: {}", table, e))?;
+
+        if actual as usize != expected {
+            mismatches.push(format!("{} has {} rows, expected {}", table, actual, expected));
+        }
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("Import verification failed: {}", mismatches.join("; ")))
+    }
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
 
 /// Get current settings. Returns None if no settings exist (first run).
 #[tauri::command]
@@ -422,17 +1670,112 @@ pub async fn get_settings(state: State<'_, AppState>) -> Result<Option<AppSettin
     load_settings_from_db(&state)
 }
 
+/// Get settings, storing and returning the defaults on first run so the row always exists afterwards
+#[tauri::command]
+pub async fn get_settings_or_default(state: State<'_, AppState>) -> Result<AppSettings, String> {
+    if let Some(settings) = load_settings_from_db(&state)? {
+        return Ok(settings);
+    }
+
+    let settings = AppSettings::default();
+    with_connection(&state, |conn| save_settings_to_db_impl(conn, &settings))?;
+
+    Ok(settings)
+}
+
 /// Save complete settings object
 #[tauri::command]
 pub async fn save_settings(
     settings: AppSettings,
     state: State<'_, AppState>,
+    app_handle: AppHandle,
 ) -> Result<AppSettings, String> {
-    let conn = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    validate_settings(&settings)?;
 
-    save_settings_to_db_impl(&conn, &settings)?;
-    Ok(settings)
+    with_connection(&state, |conn| {
+        save_settings_to_db_impl(conn, &settings)?;
+        refresh_close_behavior(&state, &app_handle)?;
+        refresh_sync_server(&app_handle);
+        Ok(settings)
+    })
+}
+
+/// JSON type name of a value, for patch errors
+fn json_kind(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Null => "null",
+        serde_json::Value::Bool(_) => "a boolean",
+        serde_json::Value::Number(_) => "a number",
+        serde_json::Value::String(_) => "a string",
+        serde_json::Value::Array(_) => "an array",
+        serde_json::Value::Object(_) => "an object",
+    }
+}
+
+/// Deep-merge `patch` into `target`, rejecting unknown keys and values whose type doesn't match.
+/// `path` is the dotted name of `target`, used in errors.
+fn merge_settings_patch(
+    target: &mut serde_json::Value,
+    patch: serde_json::Value,
+    path: &str,
+) -> Result<(), String> {
+    match (target, patch) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                let field = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                let existing = target
+                    .get_mut(&key)
+                    .ok_or_else(|| format!("Unknown setting '{}'", field))?;
+
+                merge_settings_patch(existing, value, &field)?;
+            }
+            Ok(())
+        }
+        (target, patch) => {
+            // Optional settings are stored as null, so any type may replace or clear them
+            if !target.is_null() && !patch.is_null() && json_kind(target) != json_kind(&patch) {
+                return Err(format!(
+                    "Invalid value for '{}': expected {}, got {}",
+                    path,
+                    json_kind(target),
+                    json_kind(&patch)
+                ));
+            }
+
+            *target = patch;
+            Ok(())
+        }
+    }
+}
+
+/// Change only the settings named in `patch`, e.g. `{ "appearance": { "theme": "dark" } }`
+#[tauri::command]
+pub async fn patch_settings(
+    patch: serde_json::Value,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<AppSettings, String> {
+    if !patch.is_object() {
+        return Err("Settings patch must be a JSON object".to_string());
+    }
+
+    let current = load_settings_from_db(&state)?
+        .ok_or_else(|| "Settings not initialized".to_string())?;
+
+    let mut merged = serde_json::to_value(&current)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    merge_settings_patch(&mut merged, patch, "")?;
+
+    let settings: AppSettings = serde_json::from_value(merged)
+        .map_err(|e| format!("Invalid settings: {}", e))?;
+    validate_settings(&settings)?;
+
+    with_connection(&state, |conn| {
+        save_settings_to_db_impl(conn, &settings)?;
+        refresh_close_behavior(&state, &app_handle)?;
+        refresh_sync_server(&app_handle);
+        Ok(settings)
+    })
 }
 
 /// Update only appearance settings
@@ -445,12 +1788,12 @@ pub async fn update_appearance_settings(
         .ok_or_else(|| "Settings not initialized".to_string())?;
 
     settings.appearance = appearance;
+    validate_settings(&settings)?;
 
-    let conn = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    save_settings_to_db_impl(&conn, &settings)?;
-    Ok(settings)
+    with_connection(&state, |conn| {
+        save_settings_to_db_impl(conn, &settings)?;
+        Ok(settings)
+    })
 }
 
 /// Update only habit settings
@@ -463,12 +1806,12 @@ pub async fn update_habit_settings(
         .ok_or_else(|| "Settings not initialized".to_string())?;
 
     settings.habits = habits;
+    validate_settings(&settings)?;
 
-    let conn = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    save_settings_to_db_impl(&conn, &settings)?;
-    Ok(settings)
+    with_connection(&state, |conn| {
+        save_settings_to_db_impl(conn, &settings)?;
+        Ok(settings)
+    })
 }
 
 /// Update only goal settings
@@ -481,12 +1824,12 @@ pub async fn update_goal_settings(
         .ok_or_else(|| "Settings not initialized".to_string())?;
 
     settings.goals = goals;
+    validate_settings(&settings)?;
 
-    let conn = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    save_settings_to_db_impl(&conn, &settings)?;
-    Ok(settings)
+    with_connection(&state, |conn| {
+        save_settings_to_db_impl(conn, &settings)?;
+        Ok(settings)
+    })
 }
 
 /// Update only notification settings
@@ -499,12 +1842,12 @@ pub async fn update_notification_settings(
         .ok_or_else(|| "Settings not initialized".to_string())?;
 
     settings.notifications = notifications;
+    validate_settings(&settings)?;
 
-    let conn = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    save_settings_to_db_impl(&conn, &settings)?;
-    Ok(settings)
+    with_connection(&state, |conn| {
+        save_settings_to_db_impl(conn, &settings)?;
+        Ok(settings)
+    })
 }
 
 /// Update only data settings
@@ -517,12 +1860,12 @@ pub async fn update_data_settings(
         .ok_or_else(|| "Settings not initialized".to_string())?;
 
     settings.data = data;
+    validate_settings(&settings)?;
 
-    let conn = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    save_settings_to_db_impl(&conn, &settings)?;
-    Ok(settings)
+    with_connection(&state, |conn| {
+        save_settings_to_db_impl(conn, &settings)?;
+        Ok(settings)
+    })
 }
 
 /// Reset settings - requires frontend to provide default settings
@@ -530,49 +1873,267 @@ pub async fn update_data_settings(
 pub async fn reset_settings(
     default_settings: AppSettings,
     state: State<'_, AppState>,
+    app_handle: AppHandle,
 ) -> Result<AppSettings, String> {
-    let conn = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    validate_settings(&default_settings)?;
 
-    save_settings_to_db_impl(&conn, &default_settings)?;
-    Ok(default_settings)
+    with_connection(&state, |conn| {
+        save_settings_to_db_impl(conn, &default_settings)?;
+        refresh_close_behavior(&state, &app_handle)?;
+        refresh_sync_server(&app_handle);
+        Ok(default_settings)
+    })
 }
 
 // ============================================================================
 // EXPORT/IMPORT COMMANDS
 // ============================================================================
 
-/// Export all app data (settings + database)
-#[tauri::command]
-pub async fn export_all_data(state: State<'_, AppState>) -> Result<String, String> {
-    let conn = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+/// Gather settings and database records into an export.
+/// Without `filters` every record is included, otherwise only the selected kinds within its date range.
+pub(crate) fn build_export_data(
+    state: &State<AppState>,
+    app_handle: &AppHandle,
+    filters: Option<ExportOptions>,
+) -> Result<ExportData, String> {
+    with_connection(state, |conn| {
+        // Get settings - return error if not initialized
+        let mut settings = load_settings_from_db(state)?
+            .ok_or_else(|| "Settings not initialized".to_string())?;
+        // Imports keep their own sync settings, and the token would let anyone holding
+        // the file read this device's data over the LAN
+        settings.sync = SyncSettings::default();
+
+        let options = filters.clone().unwrap_or_default();
+        let range = DateRange {
+            start: options.start_date.as_deref(),
+            end: options.end_date.as_deref(),
+        };
+
+        // Get the selected data from database
+        let goals = if options.includes(ExportEntity::Goals) {
+            export_goals_data(conn, range)?
+        } else {
+            Vec::new()
+        };
+        let tasks = if options.includes(ExportEntity::Tasks) {
+            export_tasks_data(conn, range)?
+        } else {
+            Vec::new()
+        };
+        let habits = if options.includes(ExportEntity::Habits) {
+            export_habits_data(conn, None, range)?
+        } else {
+            Vec::new()
+        };
+        let habit_completions = if options.includes(ExportEntity::HabitCompletions) {
+            export_habit_completions_data(conn, None, range)?
+        } else {
+            Vec::new()
+        };
+        let habit_pauses = if options.includes(ExportEntity::HabitPauses) {
+            export_habit_pauses_data(conn, range)?
+        } else {
+            Vec::new()
+        };
+        let habit_frequency_history = if options.includes(ExportEntity::HabitFrequencyHistory) {
+            export_habit_frequency_history_data(conn, range)?
+        } else {
+            Vec::new()
+        };
+        let attachments = if options.includes(ExportEntity::Attachments) {
+            export_attachments_data(conn, range)?
+        } else {
+            Vec::new()
+        };
+        let focus_sessions = if options.includes(ExportEntity::FocusSessions) {
+            export_focus_sessions_data(conn, range)?
+        } else {
+            Vec::new()
+        };
+        let notification_schedules = if options.includes(ExportEntity::NotificationSchedules) {
+            export_notification_schedules_data(conn, range)?
+        } else {
+            Vec::new()
+        };
+        let notification_history = if options.includes(ExportEntity::NotificationHistory) {
+            export_notification_history_data(conn, range)?
+        } else {
+            Vec::new()
+        };
+
+        let total_records = goals.len()
+            + tasks.len()
+            + habits.len()
+            + habit_completions.len()
+            + habit_pauses.len()
+            + habit_frequency_history.len()
+            + attachments.len()
+            + focus_sessions.len()
+            + notification_schedules.len()
+            + notification_history.len();
+        let package_info = app_handle.package_info();
+
+        Ok(ExportData {
+            settings,
+            goals,
+            tasks,
+            habits,
+            habit_completions,
+            habit_pauses,
+            habit_frequency_history,
+            attachments,
+            focus_sessions,
+            notification_schedules,
+            notification_history,
+            export_metadata: ExportMetadata {
+                export_date: chrono::Utc::now().to_rfc3339(),
+                app_version: package_info.version.to_string(),
+                format_version: EXPORT_FORMAT_VERSION,
+                app_name: Some(package_info.name.clone()),
+                total_records,
+                filters,
+            },
+        })
+    })
+}
 
-    // Get settings - return error if not initialized
-    let settings = load_settings_from_db(&state)?
-        .ok_or_else(|| "Settings not initialized".to_string())?;
+/// Write validated import data inside `tx`, replacing or merging with what's there
+fn write_import(tx: &rusqlite::Transaction, import_data: &ExportData, mode: ImportMode) -> Result<(), String> {
+    // Subtasks may appear before their parent in the file, so check foreign keys at commit
+    tx.pragma_update(None, "defer_foreign_keys", "ON")
+        .map_err(|e| format!("Failed to defer foreign keys: {}", e))?;
+
+    // Replacing clears everything first. Parents are inserted before children either way.
+    if mode == ImportMode::Replace {
+        clear_existing_data(tx)?;
+    }
+    import_goals_data(tx, &import_data.goals)?;
+    import_tasks_data(tx, &import_data.tasks)?;
+    import_habits_data(tx, &import_data.habits)?;
+    import_habit_completions_data(tx, &import_data.habit_completions)?;
+    import_habit_pauses_data(tx, &import_data.habit_pauses, mode)?;
+    import_habit_frequency_history_data(tx, &import_data.habit_frequency_history, mode)?;
+    import_attachments_data(tx, &import_data.attachments, mode)?;
+    // A filtered export can attach to goals or tasks this device doesn't have
+    delete_orphaned_attachments(tx)?;
+    import_focus_sessions_data(tx, &import_data.focus_sessions, mode)?;
+    // Likewise a filtered export can time tasks this device doesn't have
+    detach_orphaned_focus_sessions(tx)?;
+    import_notification_schedules_data(tx, &import_data.notification_schedules, mode)?;
+    import_notification_history_data(tx, &import_data.notification_history)?;
+
+    // Merging keeps this device's settings, and a merge adds to existing rows,
+    // so only a replace can be checked against the import
+    if mode == ImportMode::Replace {
+        save_settings_to_db_impl(tx, &import_data.settings)?;
+        verify_import_counts(tx, import_data)?;
+    }
+
+    // Older backups carry no schedules, so give their habits back the reminder they were set to
+    for habit in &import_data.habits {
+        restore_habit_reminder(tx, &habit.id)?;
+    }
+
+    Ok(())
+}
+
+/// Validate and import parsed export data
+pub(crate) fn apply_import(
+    state: &State<AppState>,
+    app_handle: &AppHandle,
+    mut import_data: ExportData,
+    mode: ImportMode,
+) -> Result<ImportSummary, String> {
+    with_connection(state, |conn| {
+        let validation = validate_export_data(&import_data);
+        if !validation.valid {
+            return Err(format!("Invalid import data: {}", validation.errors.join("; ")));
+        }
+
+        // A filtered export only holds part of the data, so it is always merged in
+        let mode = if import_data.export_metadata.filters.is_some() {
+            ImportMode::Merge
+        } else {
+            mode
+        };
+
+        // Merging keeps this device's settings, so only a replace needs them to be valid.
+        // Never adopt another device's sync server or token from a backup.
+        if mode == ImportMode::Replace {
+            import_data.settings.sync = load_settings_from_db(state)?
+                .map(|settings| settings.sync)
+                .unwrap_or_default();
+            validate_settings(&import_data.settings)?;
+        }
+
+        // Use a single transaction for atomicity
+        let tx = conn.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        // If any step fails, the transaction is automatically rolled back on drop
+        write_import(&tx, &import_data, mode)?;
+
+        // Commit everything - if this fails, transaction is rolled back
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        refresh_close_behavior(state, app_handle)?;
+
+        // Merging keeps this device's settings
+        let settings_applied = mode == ImportMode::Replace;
+
+        Ok(ImportSummary {
+            goals: import_data.goals.len(),
+            tasks: import_data.tasks.len(),
+            habits: import_data.habits.len(),
+            completions: import_data.habit_completions.len(),
+            settings_applied,
+        })
+    })
+}
+
+/// Export all app data (settings + database). Pretty-printed unless `pretty` is false.
+#[tauri::command]
+pub async fn export_all_data(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+    pretty: Option<bool>,
+) -> Result<String, String> {
+    let _timer = CommandTimer::start("export_all_data");
+    let export_data = build_export_data(&state, &app_handle, None)?;
 
-    // Get all data from database
-    let goals = export_goals_data(&conn)?;
-    let tasks = export_tasks_data(&conn)?;
-    let habits = export_habits_data(&conn)?;
-    let habit_completions = export_habit_completions_data(&conn)?;
-
-    let total_records = goals.len() + tasks.len() + habits.len() + habit_completions.len();
-
-    let export_data = ExportData {
-        settings,
-        goals,
-        tasks,
-        habits,
-        habit_completions,
-        export_metadata: ExportMetadata {
-            export_date: chrono::Utc::now().to_rfc3339(),
-            version: "1.0.0".to_string(),
-            total_records,
-        },
+    let json = if pretty.unwrap_or(true) {
+        serde_json::to_string_pretty(&export_data)
+    } else {
+        serde_json::to_string(&export_data)
     };
 
+    json.map_err(|e| format!("Failed to serialize export data: {}", e))
+}
+
+/// Export only the selected kinds of records created (or, for completions, dated) within a date range.
+/// The filters are recorded in the metadata so importing it merges instead of replacing.
+#[tauri::command]
+pub async fn export_filtered(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+    options: ExportOptions,
+) -> Result<String, String> {
+    let _timer = CommandTimer::start("export_filtered");
+
+    for date in options.start_date.iter().chain(options.end_date.iter()) {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| ValidationError::InvalidDate(date.clone()))?;
+    }
+    if let (Some(start), Some(end)) = (&options.start_date, &options.end_date) {
+        if start > end {
+            return Err("Start date must not be after end date".to_string());
+        }
+    }
+
+    let export_data = build_export_data(&state, &app_handle, Some(options))?;
+
     serde_json::to_string_pretty(&export_data)
         .map_err(|e| format!("Failed to serialize export data: {}", e))
 }
@@ -582,51 +2143,231 @@ pub async fn export_all_data(state: State<'_, AppState>) -> Result<String, Strin
 pub async fn import_all_data(
     json_data: String,
     state: State<'_, AppState>,
-) -> Result<String, String> {
-    let mut conn = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
+    app_handle: AppHandle,
+) -> Result<ImportSummary, String> {
     // Parse the import data
     let import_data: ExportData = serde_json::from_str(&json_data)
         .map_err(|e| format!("Failed to parse import data: {}", e))?;
 
-    // Use a single transaction for atomicity
-    let tx = conn.transaction()
-        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+    apply_import(&state, &app_handle, import_data, ImportMode::Replace)
+}
+
+/// Export all app data encrypted with a passphrase
+#[tauri::command]
+pub async fn export_all_data_encrypted(
+    passphrase: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<Vec<u8>, String> {
+    let _timer = CommandTimer::start("export_all_data_encrypted");
+    let export_data = build_export_data(&state, &app_handle, None)?;
+
+    let json = serde_json::to_vec(&export_data)
+        .map_err(|e| format!("Failed to serialize export data: {}", e))?;
+
+    Ok(crypto::encrypt(&json, &passphrase)?)
+}
+
+/// Import app data from an encrypted export
+#[tauri::command]
+pub async fn import_all_data_encrypted(
+    bytes: Vec<u8>,
+    passphrase: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<ImportSummary, String> {
+    let json = crypto::decrypt(&bytes, &passphrase)?;
+
+    let import_data: ExportData = serde_json::from_slice(&json)
+        .map_err(|e| format!("Failed to parse import data: {}", e))?;
+
+    apply_import(&state, &app_handle, import_data, ImportMode::Replace)
+}
+
+/// Validate an import file without writing anything
+#[tauri::command]
+pub async fn validate_import(json_data: String) -> Result<ImportValidation, String> {
+    let import_data: ExportData = serde_json::from_str(&json_data)
+        .map_err(|e| format!("Failed to parse import data: {}", e))?;
+
+    Ok(validate_export_data(&import_data))
+}
+
+/// Export one habit with all of its completions
+#[tauri::command]
+pub async fn export_habit(
+    habit_id: String,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<String, String> {
+    let _timer = CommandTimer::start("export_habit");
+    let habit_export = with_connection(&state, |conn| {
+        let habit = export_habits_data(conn, Some(&habit_id), DateRange::default())?
+            .pop()
+            .ok_or_else(|| DatabaseError::not_found("Habit", &habit_id))?;
+        let habit_completions = export_habit_completions_data(conn, Some(&habit_id), DateRange::default())?;
+        let package_info = app_handle.package_info();
+
+        Ok(HabitExport {
+            export_metadata: ExportMetadata {
+                export_date: chrono::Utc::now().to_rfc3339(),
+                app_version: package_info.version.to_string(),
+                format_version: EXPORT_FORMAT_VERSION,
+                app_name: Some(package_info.name.clone()),
+                total_records: 1 + habit_completions.len(),
+                filters: None,
+            },
+            habit,
+            habit_completions,
+        })
+    })?;
+
+    serde_json::to_string_pretty(&habit_export)
+        .map_err(|e| format!("Failed to serialize habit export: {}", e))
+}
 
-    // Import all data within the transaction - if any fails, transaction is automatically rolled back on drop
-    if let Err(e) = import_goals_data(&tx, &import_data.goals) {
-        return Err(e);
+/// Check a habit export without touching the database, returning the problems found
+fn validate_habit_export(habit_export: &HabitExport) -> Vec<String> {
+    let mut errors = Vec::new();
+    let mut duplicate_ids = Vec::new();
+
+    if let Err(e) = check_format_version(&habit_export.export_metadata) {
+        errors.push(e);
     }
 
-    if let Err(e) = import_tasks_data(&tx, &import_data.tasks) {
-        return Err(e);
+    find_duplicates(habit_export.habit_completions.iter().map(|c| c.id.as_str()), &mut duplicate_ids);
+    for id in &duplicate_ids {
+        errors.push(format!("Duplicate id {}", id));
     }
 
-    if let Err(e) = import_habits_data(&tx, &import_data.habits) {
-        return Err(e);
+    let mut completion_days = HashSet::new();
+    for completion in &habit_export.habit_completions {
+        if completion.habit_id != habit_export.habit.id {
+            errors.push(format!(
+                "Completion {} references habit {} instead of {}",
+                completion.id, completion.habit_id, habit_export.habit.id
+            ));
+        }
+        if !completion_days.insert(completion.date.as_str()) {
+            errors.push(format!(
+                "Habit {} has more than one completion on {}",
+                habit_export.habit.id, completion.date
+            ));
+        }
     }
 
-    if let Err(e) = import_habit_completions_data(&tx, &import_data.habit_completions) {
-        return Err(e);
+    errors
+}
+
+/// Keep only links to goals that exist here, since the export may come from someone else's data
+fn drop_unknown_goal_links(conn: &rusqlite::Connection, habit: &mut HabitData) -> Result<(), String> {
+    let linked_goals: Vec<String> = serde_json::from_str(&habit.linked_goals).unwrap_or_default();
+    let mut known_goals = Vec::with_capacity(linked_goals.len());
+
+    for goal_id in linked_goals {
+        let exists = conn
+            .query_row("SELECT 1 FROM goals WHERE id = ?1", [&goal_id], |_| Ok(()))
+            .optional()
+            .map_err(|e| format!("Failed to check goal {}: {}", goal_id, e))?
+            .is_some();
+        if exists {
+            known_goals.push(goal_id);
+        }
     }
 
-    // Save settings within the transaction
-    if let Err(e) = save_settings_to_db_impl(&tx, &import_data.settings) {
-        return Err(e);
+    habit.linked_goals = serde_json::to_string(&known_goals)
+        .map_err(|e| format!("Failed to serialize linked goals: {}", e))?;
+
+    Ok(())
+}
+
+/// Import a habit exported with `export_habit`, creating or updating it and its completions.
+/// Rows already here are only replaced by newer ones, and nothing else is touched.
+#[tauri::command]
+pub async fn import_habit(
+    json_data: String,
+    state: State<'_, AppState>,
+) -> Result<ImportSummary, String> {
+    let mut habit_export: HabitExport = serde_json::from_str(&json_data)
+        .map_err(|e| format!("Failed to parse habit export: {}", e))?;
+
+    let errors = validate_habit_export(&habit_export);
+    if !errors.is_empty() {
+        return Err(format!("Invalid habit export: {}", errors.join("; ")));
     }
 
-    // Commit everything - if this fails, transaction is rolled back
-    tx.commit()
-        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+    with_connection(&state, |conn| {
+        let tx = conn.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        drop_unknown_goal_links(&tx, &mut habit_export.habit)?;
+        import_habits_data(&tx, std::slice::from_ref(&habit_export.habit))?;
+        import_habit_completions_data(&tx, &habit_export.habit_completions)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
 
-    Ok(format!(
-        "Successfully imported {} goals, {} tasks, {} habits, and {} habit completions",
-        import_data.goals.len(),
-        import_data.tasks.len(),
-        import_data.habits.len(),
-        import_data.habit_completions.len()
-    ))
+        Ok(ImportSummary {
+            goals: 0,
+            tasks: 0,
+            habits: 1,
+            completions: habit_export.habit_completions.len(),
+            settings_applied: false,
+        })
+    })
+}
+
+// ============================================================================
+// AUTOSTART COMMANDS
+// ============================================================================
+
+/// Read the OS launch-on-login state and update the stored setting to match it
+pub fn sync_autostart_setting(state: &State<AppState>, app_handle: &AppHandle) -> Result<bool, String> {
+    let enabled = app_handle
+        .autolaunch()
+        .is_enabled()
+        .map_err(|e| format!("Failed to read autostart state: {}", e))?;
+
+    if let Some(mut settings) = load_settings_from_db(state)? {
+        if settings.system.launch_on_login != enabled {
+            settings.system.launch_on_login = enabled;
+
+            with_connection(state, |conn| save_settings_to_db_impl(conn, &settings))?;
+        }
+    }
+
+    Ok(enabled)
+}
+
+/// Get whether the app launches on login
+#[tauri::command]
+pub async fn get_autostart(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<bool, String> {
+    sync_autostart_setting(&state, &app_handle)
+}
+
+/// Register or unregister the app to launch on login. Returns the resulting OS state.
+#[tauri::command]
+pub async fn set_autostart(
+    enabled: bool,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<bool, String> {
+    let autolaunch = app_handle.autolaunch();
+
+    if enabled {
+        autolaunch
+            .enable()
+            .map_err(|e| format!("Failed to enable autostart: {}", e))?;
+    } else {
+        autolaunch
+            .disable()
+            .map_err(|e| format!("Failed to disable autostart: {}", e))?;
+    }
+
+    sync_autostart_setting(&state, &app_handle)
 }
 
 // ============================================================================
@@ -636,8 +2377,11 @@ pub async fn import_all_data(
 /// Export settings only
 #[tauri::command]
 pub async fn export_settings(state: State<'_, AppState>) -> Result<String, String> {
-    let settings = load_settings_from_db(&state)?
+    let _timer = CommandTimer::start("export_settings");
+    let mut settings = load_settings_from_db(&state)?
         .ok_or_else(|| "Settings not initialized".to_string())?;
+    // Never write the sync token into a file, as with full exports
+    settings.sync = SyncSettings::default();
 
     serde_json::to_string_pretty(&settings)
         .map_err(|e| format!("Failed to serialize settings: {}", e))
@@ -648,13 +2392,217 @@ pub async fn export_settings(state: State<'_, AppState>) -> Result<String, Strin
 pub async fn import_settings(
     json_data: String,
     state: State<'_, AppState>,
+    app_handle: AppHandle,
 ) -> Result<AppSettings, String> {
-    let imported_settings: AppSettings = serde_json::from_str(&json_data)
+    let mut imported_settings: AppSettings = serde_json::from_str(&json_data)
         .map_err(|e| format!("Failed to parse settings: {}", e))?;
 
-    let conn = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    // Keep this device's sync server and token, as with full imports
+    imported_settings.sync = load_settings_from_db(&state)?
+        .map(|settings| settings.sync)
+        .unwrap_or_default();
+
+    // Checked before anything is saved, so a bad file leaves the current settings alone
+    validate_settings(&imported_settings)?;
+
+    with_connection(&state, |conn| {
+        save_settings_to_db_impl(conn, &imported_settings)?;
+        refresh_close_behavior(&state, &app_handle)?;
+        Ok(imported_settings)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{insert_test_habit, test_connection, ALL_WEEKDAYS};
+
+    fn task(id: &str, parent_task_id: Option<&str>, updated_at: &str) -> TaskData {
+        TaskData {
+            id: id.to_string(),
+            title: id.to_string(),
+            done: false,
+            goal_id: None,
+            parent_task_id: parent_task_id.map(str::to_string),
+            due_date: None,
+            priority: "medium".to_string(),
+            created_at: "2024-01-01T00:00:00.000Z".to_string(),
+            updated_at: updated_at.to_string(),
+        }
+    }
+
+    #[test]
+    fn tasks_round_trip_keeps_hierarchy_and_timestamps() {
+        let mut conn = test_connection();
+        // The child comes first, as it can in an export
+        let tasks = [
+            task("child", Some("parent"), "2024-03-02T10:00:00.000Z"),
+            task("parent", None, "2024-03-01T09:00:00.000Z"),
+        ];
+
+        let tx = conn.transaction().unwrap();
+        tx.pragma_update(None, "defer_foreign_keys", "ON").unwrap();
+        import_tasks_data(&tx, &tasks).unwrap();
+        tx.commit().unwrap();
+
+        let exported = export_tasks_data(&conn, DateRange::default()).unwrap();
+        assert_eq!(exported.len(), 2);
+
+        let tx = conn.transaction().unwrap();
+        tx.pragma_update(None, "defer_foreign_keys", "ON").unwrap();
+        clear_existing_data(&tx).unwrap();
+        import_tasks_data(&tx, &exported).unwrap();
+        tx.commit().unwrap();
+
+        let restored = export_tasks_data(&conn, DateRange::default()).unwrap();
+        let find = |id: &str| restored.iter().find(|t| t.id == id).unwrap();
+        assert_eq!(find("child").parent_task_id.as_deref(), Some("parent"));
+        assert_eq!(find("child").updated_at, "2024-03-02T10:00:00.000Z");
+        assert_eq!(find("parent").parent_task_id, None);
+        assert_eq!(find("parent").updated_at, "2024-03-01T09:00:00.000Z");
+    }
 
-    save_settings_to_db_impl(&conn, &imported_settings)?;
-    Ok(imported_settings)
+    /// Everything in `conn`, as a full export would hold it
+    fn export_everything(conn: &rusqlite::Connection) -> ExportData {
+        let range = DateRange::default();
+        ExportData {
+            settings: AppSettings::default(),
+            goals: export_goals_data(conn, range).unwrap(),
+            tasks: export_tasks_data(conn, range).unwrap(),
+            habits: export_habits_data(conn, None, range).unwrap(),
+            habit_completions: export_habit_completions_data(conn, None, range).unwrap(),
+            habit_pauses: export_habit_pauses_data(conn, range).unwrap(),
+            habit_frequency_history: export_habit_frequency_history_data(conn, range).unwrap(),
+            attachments: export_attachments_data(conn, range).unwrap(),
+            focus_sessions: export_focus_sessions_data(conn, range).unwrap(),
+            notification_schedules: export_notification_schedules_data(conn, range).unwrap(),
+            notification_history: export_notification_history_data(conn, range).unwrap(),
+            export_metadata: ExportMetadata {
+                export_date: "2024-06-01T00:00:00Z".to_string(),
+                app_version: "1.0.0".to_string(),
+                format_version: EXPORT_FORMAT_VERSION,
+                app_name: None,
+                total_records: 0,
+                filters: None,
+            },
+        }
+    }
+
+    /// A goal with a task and a habit linked to it, with a row in each child table
+    fn seed(conn: &rusqlite::Connection, prefix: &str) {
+        conn.execute_batch(&format!(
+            "INSERT INTO goals (id, title, description, notes, category, priority, status, color, icon,
+                                created_at, updated_at)
+             VALUES ('{p}goal', 'Goal', '', '', 'Health', 'medium', 'active', '#000000', 'Target', '2024', '2024');
+             INSERT INTO tasks (id, title, goal_id, priority, created_at, updated_at)
+             VALUES ('{p}task', 'Task', '{p}goal', 'medium', '2024', '2024');",
+            p = prefix
+        ))
+        .unwrap();
+        insert_test_habit(conn, &format!("{}habit", prefix), &ALL_WEEKDAYS);
+        conn.execute_batch(&format!(
+            "UPDATE habits SET linked_goals = '[\"{p}goal\"]' WHERE id = '{p}habit';
+             INSERT INTO habit_completions (id, habit_id, date, completed, created_at, updated_at)
+             VALUES ('{p}done', '{p}habit', '2024-01-02', 1, '2024', '2024');
+             INSERT INTO habit_pauses (habit_id, start_date) VALUES ('{p}habit', '2024-02-01');
+             INSERT INTO habit_frequency_history (habit_id, frequency_type, frequency_value, effective_from)
+             VALUES ('{p}habit', 'daily', '[\"monday\"]', '2024-01-01');
+             INSERT INTO focus_sessions (task_id, started_at) VALUES ('{p}task', '2024-01-02T09:00:00Z');
+             INSERT INTO attachments (entity_type, entity_id, kind, value) VALUES ('goal', '{p}goal', 'url', 'https://example.com');
+             INSERT INTO notification_schedules (habit_id, habit_name, scheduled_time, notification_type, schedule_data)
+             VALUES ('{p}habit', 'Habit', '09:00', 'habit_reminder', '{{}}');
+             INSERT INTO notification_history (id, habit_id, sent_at, notification_type, payload_data)
+             VALUES ('{p}sent', '{p}habit', '2024-01-02T09:00:00Z', 'habit_reminder', '{{}}');",
+            p = prefix
+        ))
+        .unwrap();
+    }
+
+    #[test]
+    fn replace_import_into_existing_data_leaves_no_orphans() {
+        let source = test_connection();
+        seed(&source, "new-");
+        let data = export_everything(&source);
+
+        let mut conn = test_connection();
+        seed(&conn, "old-");
+        // Clearing must not lean on ON DELETE actions to reach child tables
+        conn.pragma_update(None, "foreign_keys", "OFF").unwrap();
+
+        let tx = conn.transaction().unwrap();
+        write_import(&tx, &data, ImportMode::Replace).unwrap();
+        tx.commit().unwrap();
+
+        let violations: i64 = conn
+            .query_row("SELECT COUNT(*) FROM pragma_foreign_key_check", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(violations, 0);
+
+        for (table, column) in [
+            ("goals", "id"),
+            ("tasks", "id"),
+            ("habits", "id"),
+            ("habit_completions", "habit_id"),
+            ("habit_pauses", "habit_id"),
+            ("habit_frequency_history", "habit_id"),
+            ("habit_goals", "habit_id"),
+            ("focus_sessions", "task_id"),
+            ("attachments", "entity_id"),
+            ("notification_schedules", "habit_id"),
+            ("notification_history", "habit_id"),
+        ] {
+            let old: i64 = conn
+                .query_row(
+                    &format!("SELECT COUNT(*) FROM {} WHERE {} LIKE 'old-%'", table, column),
+                    [],
+                    |row| row.get(0),
+                )
+                .unwrap();
+            assert_eq!(old, 0, "{} kept rows from before the import", table);
+        }
+
+        let links: String = conn
+            .query_row("SELECT group_concat(goal_id) FROM habit_goals", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(links, "new-goal");
+    }
+
+    #[test]
+    fn import_is_gated_on_the_format_not_the_app_version() {
+        // Made before the format was recorded, by a 1.x app
+        let legacy: ExportMetadata = serde_json::from_str(
+            r#"{"export_date": "2024-06-01T00:00:00Z", "version": "1.0.0", "total_records": 0}"#,
+        )
+        .unwrap();
+        assert_eq!(legacy.format_version, legacy_export_format_version());
+        assert!(check_format_version(&legacy).is_ok());
+
+        let from_a_later_major = ExportMetadata {
+            app_version: "7.0.0".to_string(),
+            format_version: EXPORT_FORMAT_VERSION,
+            ..legacy.clone()
+        };
+        assert!(check_format_version(&from_a_later_major).is_ok());
+
+        let newer_format = ExportMetadata { format_version: EXPORT_FORMAT_VERSION + 1, ..legacy };
+        assert!(check_format_version(&newer_format).is_err());
+    }
+
+    #[test]
+    fn day_starts_at_midnight_in_the_configured_timezone() {
+        let mut settings = AppSettings::default();
+        let date = chrono::NaiveDate::from_ymd_opt(2024, 1, 5).unwrap();
+
+        settings.appearance.timezone = "Asia/Tokyo".to_string();
+        assert_eq!(
+            configured_day_start(Some(&settings), date).to_rfc3339(),
+            "2024-01-04T15:00:00+00:00"
+        );
+
+        settings.appearance.timezone = "America/New_York".to_string();
+        assert_eq!(
+            configured_day_start(Some(&settings), date).to_rfc3339(),
+            "2024-01-05T05:00:00+00:00"
+        );
+    }
 }
\ No newline at end of file
@@ -0,0 +1,284 @@
+use crate::commands::settings::{
+    apply_import, build_export_data, load_settings_from_db, save_settings_to_db_impl, ExportData,
+    ImportMode, SyncSettings,
+};
+use crate::database::{with_connection, AppState};
+use crate::tray;
+use rand::{rngs::OsRng, RngCore};
+use serde::Serialize;
+use std::io::{Cursor, Read};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use tauri::{AppHandle, Emitter, Manager, State};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+/// Largest import body accepted, well above any realistic export
+const MAX_IMPORT_BYTES: u64 = 64 * 1024 * 1024;
+
+struct RunningServer {
+    server: Arc<Server>,
+    worker: Option<JoinHandle<()>>,
+    settings: SyncSettings,
+    address: String,
+}
+
+impl Drop for RunningServer {
+    fn drop(&mut self) {
+        // Ends the request loop, and waiting for it frees the port before any rebind
+        self.server.unblock();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// The sync server while it's running
+#[derive(Default)]
+pub struct SyncServerState(Mutex<Option<RunningServer>>);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatus {
+    pub running: bool,
+    /// Address the server listens on, while running
+    pub address: Option<String>,
+    pub settings: SyncSettings,
+}
+
+fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Compare without exiting early, so response timing doesn't reveal how much of the token matched
+fn tokens_match(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn bearer_token(request: &Request) -> Option<&str> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Authorization"))
+        .and_then(|header| header.value.as_str().strip_prefix("Bearer "))
+}
+
+fn json_response(status: u16, body: String) -> Response<Cursor<Vec<u8>>> {
+    let content_type = Header::from_bytes("Content-Type", "application/json")
+        .expect("Content-Type header is valid");
+
+    Response::from_string(body)
+        .with_status_code(status)
+        .with_header(content_type)
+}
+
+fn error_response(status: u16, message: &str) -> Response<Cursor<Vec<u8>>> {
+    json_response(status, serde_json::json!({ "error": message }).to_string())
+}
+
+fn export_json(app: &AppHandle) -> Result<String, String> {
+    let export_data = build_export_data(&app.state::<AppState>(), app, None)?;
+
+    serde_json::to_string(&export_data)
+        .map_err(|e| format!("Failed to serialize export data: {}", e))
+}
+
+fn merge_import(app: &AppHandle, request: &mut Request) -> Result<String, String> {
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_IMPORT_BYTES + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| format!("Failed to read request body: {}", e))?;
+
+    if body.len() as u64 > MAX_IMPORT_BYTES {
+        return Err("Import data is too large".to_string());
+    }
+
+    let import_data: ExportData = serde_json::from_slice(&body)
+        .map_err(|e| format!("Failed to parse import data: {}", e))?;
+
+    let summary = apply_import(&app.state::<AppState>(), app, import_data, ImportMode::Merge)?;
+
+    // The open window is showing stale data until it reloads
+    tray::refresh_status(app);
+    let _ = app.emit("sync-imported", &summary);
+
+    serde_json::to_string(&summary)
+        .map_err(|e| format!("Failed to serialize import summary: {}", e))
+}
+
+fn handle_request(app: &AppHandle, token: &str, mut request: Request) {
+    let authorized = bearer_token(&request).is_some_and(|given| tokens_match(given, token));
+    let method = request.method().clone();
+    let path = request.url().split('?').next().unwrap_or_default().to_string();
+
+    let response = match (authorized, method, path.as_str()) {
+        (false, _, _) => error_response(401, "Missing or invalid token"),
+        (true, Method::Get, "/export") => match export_json(app) {
+            Ok(body) => json_response(200, body),
+            Err(e) => {
+                log::error!("Sync export failed: {}", e);
+                error_response(500, &e)
+            }
+        },
+        (true, Method::Post, "/import") => match merge_import(app, &mut request) {
+            Ok(body) => json_response(200, body),
+            Err(e) => {
+                log::warn!("Sync import failed: {}", e);
+                error_response(400, &e)
+            }
+        },
+        _ => error_response(404, "Not found"),
+    };
+
+    if let Err(e) = request.respond(response) {
+        log::warn!("Failed to send sync response: {}", e);
+    }
+}
+
+fn start_server(app: &AppHandle, settings: &SyncSettings) -> Result<RunningServer, String> {
+    let host = if settings.allow_lan { "0.0.0.0" } else { "127.0.0.1" };
+    let address = format!("{}:{}", host, settings.port);
+
+    let server = Server::http(&address)
+        .map(Arc::new)
+        .map_err(|e| format!("Failed to start sync server on {}: {}", address, e))?;
+
+    let worker = {
+        let server = Arc::clone(&server);
+        let app = app.clone();
+        let token = settings.token.clone();
+
+        thread::spawn(move || {
+            for request in server.incoming_requests() {
+                handle_request(&app, &token, request);
+            }
+        })
+    };
+
+    log::info!("Sync server listening on {}", address);
+
+    Ok(RunningServer {
+        server,
+        worker: Some(worker),
+        settings: settings.clone(),
+        address,
+    })
+}
+
+/// Start, restart or stop the server to match the stored sync settings
+fn try_refresh_sync_server(app: &AppHandle) -> Result<(), String> {
+    let (Some(state), Some(server_state)) =
+        (app.try_state::<AppState>(), app.try_state::<SyncServerState>())
+    else {
+        return Ok(());
+    };
+
+    let settings = load_settings_from_db(&state)?
+        .map(|settings| settings.sync)
+        .unwrap_or_default();
+
+    // Never serve without a token, since an empty one would match an empty bearer header
+    let wanted = settings.enabled && !settings.token.is_empty();
+
+    let mut running = server_state.0.lock().unwrap_or_else(|e| e.into_inner());
+    if wanted && running.as_ref().is_some_and(|server| server.settings == settings) {
+        return Ok(());
+    }
+
+    *running = None;
+    if wanted {
+        *running = Some(start_server(app, &settings)?);
+    }
+
+    Ok(())
+}
+
+/// Bring the sync server in line with the stored settings, logging any failure
+pub fn refresh_sync_server(app: &AppHandle) {
+    if let Err(e) = try_refresh_sync_server(app) {
+        log::warn!("Failed to refresh sync server: {}", e);
+    }
+}
+
+fn sync_status(state: &State<AppState>, app_handle: &AppHandle) -> Result<SyncStatus, String> {
+    let settings = load_settings_from_db(state)?
+        .map(|settings| settings.sync)
+        .unwrap_or_default();
+
+    let address = app_handle.try_state::<SyncServerState>().and_then(|server_state| {
+        let running = server_state.0.lock().unwrap_or_else(|e| e.into_inner());
+        running.as_ref().map(|server| server.address.clone())
+    });
+
+    Ok(SyncStatus {
+        running: address.is_some(),
+        address,
+        settings,
+    })
+}
+
+/// Apply a change to the stored sync settings and restart the server to match
+fn update_sync_settings(
+    state: &State<AppState>,
+    app_handle: &AppHandle,
+    update: impl FnOnce(&mut SyncSettings),
+) -> Result<SyncStatus, String> {
+    let mut settings = load_settings_from_db(state)?
+        .ok_or_else(|| "Settings not initialized".to_string())?;
+
+    update(&mut settings.sync);
+    if settings.sync.token.is_empty() {
+        settings.sync.token = generate_token();
+    }
+
+    with_connection(state, |conn| save_settings_to_db_impl(conn, &settings))?;
+
+    try_refresh_sync_server(app_handle)?;
+    sync_status(state, app_handle)
+}
+
+/// Get the sync settings and whether the server is running
+#[tauri::command]
+pub async fn get_sync_status(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<SyncStatus, String> {
+    sync_status(&state, &app_handle)
+}
+
+/// Enable or disable the sync server. A token is generated the first time it's needed.
+#[tauri::command]
+pub async fn set_sync_settings(
+    enabled: bool,
+    allow_lan: bool,
+    port: u16,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<SyncStatus, String> {
+    if port == 0 {
+        return Err("Sync port must be between 1 and 65535".to_string());
+    }
+
+    update_sync_settings(&state, &app_handle, |sync| {
+        sync.enabled = enabled;
+        sync.allow_lan = allow_lan;
+        sync.port = port;
+    })
+}
+
+/// Replace the sync token, locking out devices using the old one
+#[tauri::command]
+pub async fn regenerate_sync_token(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<SyncStatus, String> {
+    update_sync_settings(&state, &app_handle, |sync| sync.token = generate_token())
+}
@@ -1,6 +1,16 @@
-use crate::database::AppState;
-use rusqlite::{params, OptionalExtension, Row};
+use crate::commands::attachments::delete_orphaned_attachments;
+use crate::commands::focus_sessions::detach_orphaned_focus_sessions;
+use crate::commands::idempotency::{find_idempotent_id, remember_idempotent_id};
+use crate::commands::settings::{configured_now, configured_row_limit, load_settings_from_db};
+use crate::database::{ensure_below_row_limit, with_connection, AppState, DatabaseError};
+use crate::events::{emit_data_changed, Action, Entity};
+use crate::timing::CommandTimer;
+use crate::tray;
+use crate::validation::ValidationError;
+use chrono::NaiveDate;
+use rusqlite::{params, Connection, OptionalExtension, Row};
 use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -16,8 +26,16 @@ pub struct Task {
     pub updated_at: String,
 }
 
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskWithSubtaskCounts {
+    pub task: Task,
+    pub subtask_count: i64,
+    pub completed_subtask_count: i64,
+}
+
 impl Task {
-    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+    pub(crate) fn from_row(row: &Row) -> rusqlite::Result<Self> {
         Ok(Self {
             id: row.get(0)?,
             title: row.get(1)?,
@@ -30,102 +48,193 @@ impl Task {
             updated_at: row.get(8)?,
         })
     }
+
+    /// Insert this task as a new row
+    pub(crate) fn insert(&self, conn: &Connection) -> Result<(), String> {
+        conn.execute(
+            "INSERT INTO tasks (id, title, done, goal_id, parent_task_id, due_date, priority, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                self.id,
+                self.title,
+                self.done as i32,
+                self.goal_id,
+                self.parent_task_id,
+                self.due_date,
+                self.priority,
+                self.created_at,
+                self.updated_at,
+            ],
+        )
+        .map_err(|e| format!("Failed to create task: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Look up a task by id
+    pub(crate) fn find(conn: &Connection, id: &str) -> Result<Option<Self>, String> {
+        conn.query_row("SELECT * FROM tasks WHERE id = ?1", params![id], Self::from_row)
+            .optional()
+            .map_err(|e| format!("Failed to query task: {}", e))
+    }
 }
 
+/// Create a task. Retrying with the same `idempotency_key` within a day returns the
+/// task the first call created instead of adding another.
 #[tauri::command]
 pub async fn create_task(
     state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
     task: Task,
+    idempotency_key: Option<String>,
 ) -> Result<Task, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    db.execute(
-        "INSERT INTO tasks (id, title, done, goal_id, parent_task_id, due_date, priority, created_at, updated_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-        params![
-            task.id,
-            task.title,
-            task.done as i32,
-            task.goal_id,
-            task.parent_task_id,
-            task.due_date,
-            task.priority,
-            task.created_at,
-            task.updated_at,
-        ],
-    )
-    .map_err(|e| format!("Failed to create task: {}", e))?;
-
-    Ok(task)
+    let row_limit = configured_row_limit(load_settings_from_db(&state)?.as_ref());
+    let key = idempotency_key.as_deref();
+
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        if let Some(id) = find_idempotent_id(&tx, key, "task")? {
+            if let Some(existing) = Task::find(&tx, &id)? {
+                return Ok(existing);
+            }
+        }
+
+        ensure_below_row_limit(&tx, "tasks", row_limit)?;
+        task.insert(&tx)?;
+        remember_idempotent_id(&tx, key, "task", &task.id)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        emit_data_changed(&app_handle, Entity::Task, Action::Create, &task.id);
+
+        Ok(task)
+    })
 }
 
 #[tauri::command]
 pub async fn update_task(
     state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
     task: Task,
+    expected_updated_at: Option<String>,
 ) -> Result<Task, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    let rows = db.execute(
-        "UPDATE tasks SET
-            title = ?1, done = ?2, goal_id = ?3, parent_task_id = ?4,
-            due_date = ?5, priority = ?6, updated_at = ?7
-         WHERE id = ?8",
-        params![
-            task.title,
-            task.done as i32,
-            task.goal_id,
-            task.parent_task_id,
-            task.due_date,
-            task.priority,
-            task.updated_at,
-            task.id,
-        ],
-    )
-    .map_err(|e| format!("Failed to update task: {}", e))?;
-
-    if rows == 0 {
-        return Err(format!("Task with id '{}' not found", task.id));
-    }
+    with_connection(&state, |db| {
+        let rows = db.execute(
+            "UPDATE tasks SET
+                title = ?1, done = ?2, goal_id = ?3, parent_task_id = ?4,
+                due_date = ?5, priority = ?6, updated_at = ?7
+             WHERE id = ?8 AND (?9 IS NULL OR updated_at = ?9)",
+            params![
+                task.title,
+                task.done as i32,
+                task.goal_id,
+                task.parent_task_id,
+                task.due_date,
+                task.priority,
+                task.updated_at,
+                task.id,
+                expected_updated_at,
+            ],
+        )
+        .map_err(|e| format!("Failed to update task: {}", e))?;
 
-    Ok(task)
+        if rows == 0 {
+            let expected = expected_updated_at.as_deref();
+            return Err(DatabaseError::update_missed(db, "tasks", "Task", &task.id, expected).into());
+        }
+
+        tray::refresh_status(&app_handle);
+        emit_data_changed(&app_handle, Entity::Task, Action::Update, &task.id);
+
+        Ok(task)
+    })
 }
 
 #[tauri::command]
 pub async fn delete_task(
     state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
     id: String,
 ) -> Result<bool, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        let rows_affected = tx
+            .execute("DELETE FROM tasks WHERE id = ?1", params![id])
+            .map_err(|e| format!("Failed to delete task: {}", e))?;
+
+        // The task's attachments, plus those of subtasks removed by ON DELETE CASCADE
+        delete_orphaned_attachments(&tx)?;
+        detach_orphaned_focus_sessions(&tx)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
 
-    let rows_affected = db
-        .execute("DELETE FROM tasks WHERE id = ?1", params![id])
-        .map_err(|e| format!("Failed to delete task: {}", e))?;
+        if rows_affected > 0 {
+            emit_data_changed(&app_handle, Entity::Task, Action::Delete, &id);
+        }
 
-    Ok(rows_affected > 0)
+        Ok(rows_affected > 0)
+    })
 }
 
 #[tauri::command]
 pub async fn get_all_tasks(
     state: tauri::State<'_, AppState>,
 ) -> Result<Vec<Task>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    let mut stmt = db
-        .prepare("SELECT * FROM tasks ORDER BY created_at DESC")
-        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
-
-    let tasks = stmt
-        .query_map([], Task::from_row)
-        .map_err(|e| format!("Failed to query tasks: {}", e))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+    let _timer = CommandTimer::start("get_all_tasks");
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare("SELECT * FROM tasks ORDER BY created_at DESC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let tasks = stmt
+            .query_map([], Task::from_row)
+            .map_err(|e| format!("Failed to query tasks: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+
+        Ok(tasks)
+    })
+}
 
-    Ok(tasks)
+/// Get top-level tasks with their total and completed subtask counts in a single query
+#[tauri::command]
+pub async fn get_all_tasks_with_subtask_counts(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<TaskWithSubtaskCounts>, String> {
+    let _timer = CommandTimer::start("get_all_tasks_with_subtask_counts");
+    with_connection(&state, |db| {
+        // The join is served by idx_tasks_parent_done (parent_task_id, done)
+        let mut stmt = db
+            .prepare(
+                "SELECT t.*, COUNT(s.id), COALESCE(SUM(s.done), 0)
+                 FROM tasks t
+                 LEFT JOIN tasks s ON s.parent_task_id = t.id
+                 WHERE t.parent_task_id IS NULL
+                 GROUP BY t.id
+                 ORDER BY t.created_at DESC",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let tasks = stmt
+            .query_map([], |row| {
+                Ok(TaskWithSubtaskCounts {
+                    task: Task::from_row(row)?,
+                    subtask_count: row.get(9)?,
+                    completed_subtask_count: row.get(10)?,
+                })
+            })
+            .map_err(|e| format!("Failed to query tasks: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+
+        Ok(tasks)
+    })
 }
 
 #[tauri::command]
@@ -133,19 +242,8 @@ pub async fn get_task_by_id(
     state: tauri::State<'_, AppState>,
     id: String,
 ) -> Result<Option<Task>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    let task = db
-        .query_row(
-            "SELECT * FROM tasks WHERE id = ?1",
-            params![id],
-            Task::from_row,
-        )
-        .optional()
-        .map_err(|e| format!("Failed to query task: {}", e))?;
-
-    Ok(task)
+    let _timer = CommandTimer::start("get_task_by_id");
+    with_connection(&state, |db| Task::find(db, &id))
 }
 
 #[tauri::command]
@@ -153,15 +251,56 @@ pub async fn get_tasks_by_goal_id(
     state: tauri::State<'_, AppState>,
     goal_id: String,
 ) -> Result<Vec<Task>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    let _timer = CommandTimer::start("get_tasks_by_goal_id");
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare("SELECT * FROM tasks WHERE goal_id = ?1 ORDER BY created_at DESC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let tasks = stmt
+            .query_map(params![goal_id], Task::from_row)
+            .map_err(|e| format!("Failed to query tasks: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+
+        Ok(tasks)
+    })
+}
+
+#[tauri::command]
+pub async fn get_tasks_by_status(
+    state: tauri::State<'_, AppState>,
+    done: bool,
+) -> Result<Vec<Task>, String> {
+    let _timer = CommandTimer::start("get_tasks_by_status");
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare("SELECT * FROM tasks WHERE done = ?1 ORDER BY created_at DESC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let tasks = stmt
+            .query_map(params![done as i32], Task::from_row)
+            .map_err(|e| format!("Failed to query tasks: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+
+        Ok(tasks)
+    })
+}
 
-    let mut stmt = db
-        .prepare("SELECT * FROM tasks WHERE goal_id = ?1 ORDER BY created_at DESC")
+/// Get open tasks whose due date falls on `date` (YYYY-MM-DD) in local time
+pub(crate) fn tasks_due_on(conn: &Connection, date: &str) -> Result<Vec<Task>, String> {
+    // Due dates may be full ISO timestamps, so compare the local calendar day like the frontend does
+    let mut stmt = conn
+        .prepare(
+            "SELECT * FROM tasks
+             WHERE done = 0 AND due_date IS NOT NULL AND date(due_date, 'localtime') = ?1
+             ORDER BY created_at DESC",
+        )
         .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
     let tasks = stmt
-        .query_map(params![goal_id], Task::from_row)
+        .query_map(params![date], Task::from_row)
         .map_err(|e| format!("Failed to query tasks: {}", e))?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| format!("Failed to collect tasks: {}", e))?;
@@ -169,20 +308,18 @@ pub async fn get_tasks_by_goal_id(
     Ok(tasks)
 }
 
-#[tauri::command]
-pub async fn get_tasks_by_status(
-    state: tauri::State<'_, AppState>,
-    done: bool,
-) -> Result<Vec<Task>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
-
-    let mut stmt = db
-        .prepare("SELECT * FROM tasks WHERE done = ?1 ORDER BY created_at DESC")
+/// Get open tasks whose due date is before `date` (YYYY-MM-DD) in local time, oldest first
+pub(crate) fn tasks_overdue_on(conn: &Connection, date: &str) -> Result<Vec<Task>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT * FROM tasks
+             WHERE done = 0 AND due_date IS NOT NULL AND date(due_date, 'localtime') < ?1
+             ORDER BY due_date ASC",
+        )
         .map_err(|e| format!("Failed to prepare statement: {}", e))?;
 
     let tasks = stmt
-        .query_map(params![done as i32], Task::from_row)
+        .query_map(params![date], Task::from_row)
         .map_err(|e| format!("Failed to query tasks: {}", e))?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| format!("Failed to collect tasks: {}", e))?;
@@ -190,52 +327,209 @@ pub async fn get_tasks_by_status(
     Ok(tasks)
 }
 
+/// Get tasks due between two YYYY-MM-DD dates, both inclusive, soonest first and then by priority.
+/// Tasks without a due date are left out.
 #[tauri::command]
-pub async fn get_subtasks(
+pub async fn get_tasks_due_between(
     state: tauri::State<'_, AppState>,
-    parent_task_id: String,
+    start_date: String,
+    end_date: String,
+    include_done: bool,
 ) -> Result<Vec<Task>, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    let _timer = CommandTimer::start("get_tasks_due_between");
+    for date in [&start_date, &end_date] {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| ValidationError::InvalidDate(date.clone()))?;
+    }
+    if start_date > end_date {
+        return Err("Start date must not be after end date".to_string());
+    }
 
-    let mut stmt = db
-        .prepare("SELECT * FROM tasks WHERE parent_task_id = ?1 ORDER BY created_at ASC")
-        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+    with_connection(&state, |db| {
+        // Compares the stored value so idx_tasks_due_date applies. Due dates may carry a time,
+        // so the range runs up to the start of the day after end_date.
+        let mut stmt = db
+            .prepare(
+                "SELECT * FROM tasks
+                 WHERE due_date >= ?1 AND due_date < date(?2, '+1 day')
+                   AND (?3 OR done = 0)
+                 ORDER BY due_date ASC,
+                    CASE priority WHEN 'high' THEN 0 WHEN 'medium' THEN 1 WHEN 'low' THEN 2 ELSE 3 END",
+            )
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let tasks = stmt
+            .query_map(params![start_date, end_date, include_done], Task::from_row)
+            .map_err(|e| format!("Failed to query tasks: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+
+        Ok(tasks)
+    })
+}
 
-    let tasks = stmt
-        .query_map(params![parent_task_id], Task::from_row)
-        .map_err(|e| format!("Failed to query subtasks: {}", e))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Failed to collect subtasks: {}", e))?;
+/// Get open tasks due today in the configured timezone
+#[tauri::command]
+pub async fn get_tasks_due_today(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<Task>, String> {
+    let _timer = CommandTimer::start("get_tasks_due_today");
+    let today = configured_now(load_settings_from_db(&state)?.as_ref())
+        .date()
+        .format("%Y-%m-%d")
+        .to_string();
 
-    Ok(tasks)
+    with_connection(&state, |db| tasks_due_on(db, &today))
+}
+
+#[tauri::command]
+pub async fn get_subtasks(
+    state: tauri::State<'_, AppState>,
+    parent_task_id: String,
+) -> Result<Vec<Task>, String> {
+    let _timer = CommandTimer::start("get_subtasks");
+    with_connection(&state, |db| {
+        let mut stmt = db
+            .prepare("SELECT * FROM tasks WHERE parent_task_id = ?1 ORDER BY created_at ASC")
+            .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+        let tasks = stmt
+            .query_map(params![parent_task_id], Task::from_row)
+            .map_err(|e| format!("Failed to query subtasks: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Failed to collect subtasks: {}", e))?;
+
+        Ok(tasks)
+    })
+}
+
+/// Move tasks to another goal, or to the inbox when `new_goal_id` is None.
+/// Returns how many tasks were moved; unknown task ids are skipped.
+#[tauri::command]
+pub async fn reassign_tasks(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    task_ids: Vec<String>,
+    new_goal_id: Option<String>,
+) -> Result<usize, String> {
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        if let Some(goal_id) = &new_goal_id {
+            let goal_exists = tx
+                .query_row(
+                    "SELECT EXISTS(SELECT 1 FROM goals WHERE id = ?1)",
+                    params![goal_id],
+                    |row| row.get::<_, bool>(0),
+                )
+                .map_err(|e| format!("Failed to check goal: {}", e))?;
+
+            if !goal_exists {
+                return Err(DatabaseError::not_found("Goal", goal_id).into());
+            }
+        }
+
+        let mut moved = Vec::new();
+        {
+            let mut stmt = tx
+                .prepare("UPDATE tasks SET goal_id = ?1, updated_at = datetime('now') WHERE id = ?2")
+                .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+            for task_id in &task_ids {
+                let rows = stmt
+                    .execute(params![new_goal_id, task_id])
+                    .map_err(|e| format!("Failed to reassign task: {}", e))?;
+
+                if rows > 0 {
+                    moved.push(task_id);
+                }
+            }
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        for task_id in &moved {
+            emit_data_changed(&app_handle, Entity::Task, Action::Update, task_id);
+        }
+
+        Ok(moved.len())
+    })
 }
 
 #[tauri::command]
 pub async fn toggle_task_status(
     state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
     id: String,
 ) -> Result<bool, String> {
-    let db = state.db.get()
-        .map_err(|e| format!("Failed to get database connection: {}", e))?;
+    with_connection(&state, |db| {
+        let rows = db.execute(
+            "UPDATE tasks SET done = NOT done, updated_at = datetime('now') WHERE id = ?1",
+            params![id],
+        )
+        .map_err(|e| format!("Failed to toggle task status: {}", e))?;
 
-    let rows = db.execute(
-        "UPDATE tasks SET done = NOT done, updated_at = datetime('now') WHERE id = ?1",
-        params![id],
-    )
-    .map_err(|e| format!("Failed to toggle task status: {}", e))?;
+        if rows == 0 {
+            return Err(DatabaseError::not_found("Task", &id).into());
+        }
 
-    if rows == 0 {
-        return Err(format!("Task with id '{}' not found", id));
-    }
+        let new_status = db
+            .query_row(
+                "SELECT done FROM tasks WHERE id = ?1",
+                params![id],
+                |row| row.get::<_, i32>(0),
+            )
+            .map_err(|e| format!("Failed to get task status: {}", e))?;
 
-    let new_status = db
-        .query_row(
-            "SELECT done FROM tasks WHERE id = ?1",
-            params![id],
-            |row| row.get::<_, i32>(0),
-        )
-        .map_err(|e| format!("Failed to get task status: {}", e))?;
+        tray::refresh_status(&app_handle);
+        emit_data_changed(&app_handle, Entity::Task, Action::Update, &id);
 
-    Ok(new_status != 0)
+        Ok(new_status != 0)
+    })
+}
+
+/// Mark tasks done or not done. Returns how many tasks changed;
+/// unknown ids and tasks already in that state are skipped.
+#[tauri::command]
+pub async fn set_tasks_done(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    ids: Vec<String>,
+    done: bool,
+) -> Result<usize, String> {
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        let mut changed = Vec::new();
+        {
+            let mut stmt = tx
+                .prepare("UPDATE tasks SET done = ?1, updated_at = datetime('now') WHERE id = ?2 AND done != ?1")
+                .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+            for id in &ids {
+                let rows = stmt
+                    .execute(params![done, id])
+                    .map_err(|e| format!("Failed to update task status: {}", e))?;
+
+                if rows > 0 {
+                    changed.push(id);
+                }
+            }
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        if !changed.is_empty() {
+            tray::refresh_status(&app_handle);
+        }
+        for id in &changed {
+            emit_data_changed(&app_handle, Entity::Task, Action::Update, id);
+        }
+
+        Ok(changed.len())
+    })
 }
\ No newline at end of file
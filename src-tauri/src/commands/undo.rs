@@ -0,0 +1,654 @@
+use crate::commands::attachments::{get_attachments_for, Attachment};
+use crate::commands::focus_sessions::get_focus_session_ids_for_task;
+use crate::commands::goals::{
+    delete_goal_tx, find_habits_linked_to_goal, DeleteStrategy, Goal, GOAL_TASK_TREE_CTE,
+};
+use crate::commands::habit_completions::HabitCompletion;
+use crate::commands::habit_frequency_history::{get_frequency_history, FrequencyChange};
+use crate::commands::habit_pauses::{get_pauses_for_habit, HabitPause};
+use crate::commands::habits::{delete_habit_row, Habit};
+use crate::commands::notifications::NotificationHistory;
+use crate::commands::tasks::Task;
+use crate::database::{with_connection, AppState};
+use crate::events::{emit_data_changed, Action, Entity};
+use rusqlite::{params, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+/// How many goal deletions `undo_last_delete` can step back through
+const DELETED_GOALS_CAPACITY: usize = 10;
+
+// ============================================================================
+// SNAPSHOT STRUCTURES
+// ============================================================================
+
+/// A habit's linked_goals array as it was before the goal was removed from it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HabitLinkSnapshot {
+    pub habit_id: String,
+    pub linked_goals: Vec<String>,
+}
+
+/// A focus session that was timed on a task the delete removed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FocusSessionLinkSnapshot {
+    pub session_id: i64,
+    pub task_id: String,
+}
+
+/// A raw notification_schedules row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleSnapshot {
+    pub habit_id: String,
+    pub habit_name: String,
+    pub scheduled_time: String,
+    pub notification_type: String,
+    pub is_recurring: bool,
+    pub schedule_data: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Everything needed to reconstruct the state prior to a delete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum UndoSnapshot {
+    #[serde(rename_all = "camelCase")]
+    Goal {
+        goal: Goal,
+        deleted_tasks: Vec<Task>,
+        nullified_task_ids: Vec<String>,
+        habit_links: Vec<HabitLinkSnapshot>,
+        /// The goal's attachments and those of its deleted tasks
+        attachments: Vec<Attachment>,
+        /// Sessions on the deleted tasks, detached by the delete
+        focus_sessions: Vec<FocusSessionLinkSnapshot>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Habit {
+        habit: Habit,
+        completions: Vec<HabitCompletion>,
+        schedules: Vec<ScheduleSnapshot>,
+        history: Vec<NotificationHistory>,
+        pauses: Vec<HabitPause>,
+        frequency_history: Vec<FrequencyChange>,
+    },
+}
+
+impl UndoSnapshot {
+    /// The goal a goal snapshot restores
+    fn goal(&self) -> Option<&Goal> {
+        match self {
+            UndoSnapshot::Goal { goal, .. } => Some(goal),
+            UndoSnapshot::Habit { .. } => None,
+        }
+    }
+}
+
+/// Snapshots of the goals deleted this session, oldest first.
+/// Kept in memory only, so it starts empty on every launch.
+#[derive(Debug, Default)]
+pub struct DeletedGoals(Mutex<VecDeque<UndoSnapshot>>);
+
+impl DeletedGoals {
+    /// Remember a deleted goal, replacing an earlier deletion of the same goal
+    /// and dropping the oldest once the buffer is full
+    pub(crate) fn push(&self, snapshot: UndoSnapshot) {
+        let mut deleted = self.0.lock().unwrap_or_else(|e| e.into_inner());
+        let id = snapshot.goal().map(|goal| goal.id.clone());
+
+        deleted.retain(|earlier| earlier.goal().map(|goal| &goal.id) != id.as_ref());
+        if deleted.len() == DELETED_GOALS_CAPACITY {
+            deleted.pop_front();
+        }
+        deleted.push_back(snapshot);
+    }
+
+    /// Forget every deletion, e.g. once the database they came from is no longer active
+    pub(crate) fn clear(&self) {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+// ============================================================================
+// SNAPSHOT CAPTURE
+// ============================================================================
+
+/// Capture a goal and every row a delete with the given strategy would touch
+pub(crate) fn capture_goal_snapshot(
+    tx: &Transaction,
+    id: &str,
+    strategy: DeleteStrategy,
+) -> Result<Option<UndoSnapshot>, String> {
+    let goal = tx
+        .query_row("SELECT * FROM goals WHERE id = ?1", params![id], Goal::from_row)
+        .optional()
+        .map_err(|e| format!("Failed to query goal: {}", e))?;
+
+    let Some(goal) = goal else {
+        return Ok(None);
+    };
+
+    let mut deleted_tasks = Vec::new();
+    let mut nullified_task_ids = Vec::new();
+
+    match strategy {
+        DeleteStrategy::Cascade => {
+            // Goal tasks plus their subtasks, which go with them via ON DELETE CASCADE
+            let mut stmt = tx
+                .prepare(&format!(
+                    "{} SELECT * FROM tasks WHERE id IN (SELECT id FROM task_tree)",
+                    GOAL_TASK_TREE_CTE
+                ))
+                .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+            deleted_tasks = stmt
+                .query_map(params![id], Task::from_row)
+                .map_err(|e| format!("Failed to query tasks: {}", e))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+        }
+        DeleteStrategy::Nullify => {
+            let mut stmt = tx
+                .prepare("SELECT id FROM tasks WHERE goal_id = ?1")
+                .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+            nullified_task_ids = stmt
+                .query_map(params![id], |row| row.get(0))
+                .map_err(|e| format!("Failed to query tasks: {}", e))?
+                .collect::<Result<Vec<String>, _>>()
+                .map_err(|e| format!("Failed to collect tasks: {}", e))?;
+        }
+    }
+
+    let mut attachments = get_attachments_for(tx, "goal", id)?;
+    let mut focus_sessions = Vec::new();
+    for task in &deleted_tasks {
+        attachments.extend(get_attachments_for(tx, "task", &task.id)?);
+        focus_sessions.extend(
+            get_focus_session_ids_for_task(tx, &task.id)?
+                .into_iter()
+                .map(|session_id| FocusSessionLinkSnapshot {
+                    session_id,
+                    task_id: task.id.clone(),
+                }),
+        );
+    }
+
+    let habit_links = find_habits_linked_to_goal(tx, id)?
+        .into_iter()
+        .map(|habit| HabitLinkSnapshot {
+            habit_id: habit.id,
+            linked_goals: habit.linked_goals,
+        })
+        .collect();
+
+    Ok(Some(UndoSnapshot::Goal {
+        goal,
+        deleted_tasks,
+        nullified_task_ids,
+        habit_links,
+        attachments,
+        focus_sessions,
+    }))
+}
+
+/// Capture a habit and the rows that ON DELETE CASCADE would remove with it
+fn capture_habit_snapshot(tx: &Transaction, id: &str) -> Result<Option<UndoSnapshot>, String> {
+    let habit = tx
+        .query_row("SELECT * FROM habits WHERE id = ?1", params![id], Habit::from_row)
+        .optional()
+        .map_err(|e| format!("Failed to query habit: {}", e))?;
+
+    let Some(habit) = habit else {
+        return Ok(None);
+    };
+
+    let mut stmt = tx
+        .prepare("SELECT * FROM habit_completions WHERE habit_id = ?1")
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let completions = stmt
+        .query_map(params![id], HabitCompletion::from_row)
+        .map_err(|e| format!("Failed to query habit completions: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect habit completions: {}", e))?;
+
+    let mut stmt = tx
+        .prepare(
+            "SELECT habit_id, habit_name, scheduled_time, notification_type, is_recurring,
+                    schedule_data, created_at, updated_at
+             FROM notification_schedules
+             WHERE habit_id = ?1",
+        )
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let schedules = stmt
+        .query_map(params![id], |row| {
+            Ok(ScheduleSnapshot {
+                habit_id: row.get(0)?,
+                habit_name: row.get(1)?,
+                scheduled_time: row.get(2)?,
+                notification_type: row.get(3)?,
+                is_recurring: row.get::<_, i32>(4)? != 0,
+                schedule_data: row.get(5)?,
+                created_at: row.get(6)?,
+                updated_at: row.get(7)?,
+            })
+        })
+        .map_err(|e| format!("Failed to query schedules: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect schedules: {}", e))?;
+
+    let mut stmt = tx
+        .prepare(
+            "SELECT id, habit_id, sent_at, notification_type, opened, action_taken, payload_data, created_at
+             FROM notification_history
+             WHERE habit_id = ?1",
+        )
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let history = stmt
+        .query_map(params![id], NotificationHistory::from_row)
+        .map_err(|e| format!("Failed to query history: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect history: {}", e))?;
+
+    let pauses = get_pauses_for_habit(tx, id)?;
+    let frequency_history = get_frequency_history(tx, id)?;
+
+    Ok(Some(UndoSnapshot::Habit {
+        habit,
+        completions,
+        schedules,
+        history,
+        pauses,
+        frequency_history,
+    }))
+}
+
+/// Tell listeners about the rows a snapshot covers, once they were deleted or restored.
+/// Rows that go with their goal or habit, such as completions, are covered by its event.
+fn emit_snapshot_changed(app_handle: &AppHandle, snapshot: &UndoSnapshot, action: Action) {
+    match snapshot {
+        UndoSnapshot::Goal { goal, deleted_tasks, .. } => {
+            emit_data_changed(app_handle, Entity::Goal, action, &goal.id);
+            for task in deleted_tasks {
+                emit_data_changed(app_handle, Entity::Task, action, &task.id);
+            }
+        }
+        UndoSnapshot::Habit { habit, .. } => {
+            emit_data_changed(app_handle, Entity::Habit, action, &habit.id);
+        }
+    }
+}
+
+/// Serialize a snapshot into an opaque undo token
+fn encode_token(snapshot: &UndoSnapshot) -> Result<String, String> {
+    serde_json::to_string(snapshot).map_err(|e| format!("Failed to serialize undo token: {}", e))
+}
+
+// ============================================================================
+// SNAPSHOT RESTORE
+// ============================================================================
+
+/// Re-insert the rows captured in a snapshot (within transaction)
+fn restore_snapshot_tx(tx: &Transaction, snapshot: &UndoSnapshot) -> Result<(), String> {
+    // Subtasks may be listed before their parents; check constraints at commit instead
+    tx.pragma_update(None, "defer_foreign_keys", "ON")
+        .map_err(|e| format!("Failed to defer foreign keys: {}", e))?;
+
+    match snapshot {
+        UndoSnapshot::Goal {
+            goal,
+            deleted_tasks,
+            nullified_task_ids,
+            habit_links,
+            attachments,
+            focus_sessions,
+        } => {
+            goal.insert(tx)?;
+
+            for task in deleted_tasks {
+                task.insert(tx)?;
+            }
+
+            for task_id in nullified_task_ids {
+                tx.execute(
+                    "UPDATE tasks SET goal_id = ?1 WHERE id = ?2 AND goal_id IS NULL",
+                    params![goal.id, task_id],
+                )
+                .map_err(|e| format!("Failed to restore task goal reference: {}", e))?;
+            }
+
+            for link in habit_links {
+                restore_habit_link_tx(tx, link, &goal.id)?;
+            }
+
+            for attachment in attachments {
+                attachment.restore(tx)?;
+            }
+
+            for link in focus_sessions {
+                tx.execute(
+                    "UPDATE focus_sessions SET task_id = ?1 WHERE id = ?2 AND task_id IS NULL",
+                    params![link.task_id, link.session_id],
+                )
+                .map_err(|e| format!("Failed to restore focus session task: {}", e))?;
+            }
+        }
+        UndoSnapshot::Habit {
+            habit,
+            completions,
+            schedules,
+            history,
+            pauses,
+            frequency_history,
+        } => {
+            habit.insert(tx)?;
+
+            for completion in completions {
+                completion.upsert(tx)?;
+            }
+
+            for schedule in schedules {
+                tx.execute(
+                    "INSERT OR REPLACE INTO notification_schedules (
+                        habit_id, habit_name, scheduled_time, notification_type, is_recurring,
+                        schedule_data, created_at, updated_at
+                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                    params![
+                        schedule.habit_id,
+                        schedule.habit_name,
+                        schedule.scheduled_time,
+                        schedule.notification_type,
+                        schedule.is_recurring as i32,
+                        schedule.schedule_data,
+                        schedule.created_at,
+                        schedule.updated_at,
+                    ],
+                )
+                .map_err(|e| format!("Failed to restore notification schedule: {}", e))?;
+            }
+
+            for entry in history {
+                tx.execute(
+                    "INSERT OR IGNORE INTO notification_history (
+                        id, habit_id, sent_at, notification_type, opened, action_taken, payload_data,
+                        created_at
+                    ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, COALESCE(?8, datetime('now')))",
+                    params![
+                        entry.id,
+                        entry.habit_id,
+                        entry.sent_at,
+                        entry.notification_type,
+                        entry.opened as i32,
+                        entry.action_taken,
+                        entry.payload_data,
+                        entry.created_at,
+                    ],
+                )
+                .map_err(|e| format!("Failed to restore notification history: {}", e))?;
+            }
+
+            for pause in pauses {
+                tx.execute(
+                    "INSERT OR IGNORE INTO habit_pauses (id, habit_id, start_date, end_date, created_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![
+                        pause.id,
+                        pause.habit_id,
+                        pause.start_date,
+                        pause.end_date,
+                        pause.created_at,
+                    ],
+                )
+                .map_err(|e| format!("Failed to restore habit pause: {}", e))?;
+            }
+
+            for change in frequency_history {
+                change.restore(tx)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Put a goal ID back into a habit's linked_goals at its original position
+fn restore_habit_link_tx(
+    tx: &Transaction,
+    link: &HabitLinkSnapshot,
+    goal_id: &str,
+) -> Result<(), String> {
+    let current = tx
+        .query_row(
+            "SELECT linked_goals FROM habits WHERE id = ?1",
+            params![link.habit_id],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|e| format!("Failed to query habit: {}", e))?;
+
+    // The habit may have been deleted since
+    let Some(current) = current else {
+        return Ok(());
+    };
+
+    let mut linked_goals: Vec<String> = serde_json::from_str(&current).unwrap_or_default();
+    if linked_goals.iter().any(|g| g == goal_id) {
+        return Ok(());
+    }
+
+    let position = link
+        .linked_goals
+        .iter()
+        .position(|g| g == goal_id)
+        .unwrap_or(linked_goals.len())
+        .min(linked_goals.len());
+    linked_goals.insert(position, goal_id.to_string());
+
+    let updated_json = serde_json::to_string(&linked_goals)
+        .map_err(|e| format!("Failed to serialize linked goals: {}", e))?;
+
+    tx.execute(
+        "UPDATE habits SET linked_goals = ?1 WHERE id = ?2",
+        params![updated_json, link.habit_id],
+    )
+    .map_err(|e| format!("Failed to update habit linked goals: {}", e))?;
+
+    Ok(())
+}
+
+// ============================================================================
+// TAURI COMMANDS
+// ============================================================================
+
+/// Delete a goal and return an undo token, or None if the goal did not exist
+#[tauri::command]
+pub async fn delete_goal_with_undo(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    id: String,
+    delete_strategy: Option<DeleteStrategy>,
+) -> Result<Option<String>, String> {
+    with_connection(&state, |db| {
+        let strategy = delete_strategy.unwrap_or(DeleteStrategy::Nullify);
+
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        let Some(snapshot) = capture_goal_snapshot(&tx, &id, strategy)? else {
+            return Ok(None);
+        };
+
+        delete_goal_tx(&tx, &id, strategy)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        emit_snapshot_changed(&app_handle, &snapshot, Action::Delete);
+        encode_token(&snapshot).map(Some)
+    })
+}
+
+/// Delete a habit and return an undo token, or None if the habit did not exist
+#[tauri::command]
+pub async fn delete_habit_with_undo(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    id: String,
+) -> Result<Option<String>, String> {
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        let Some(snapshot) = capture_habit_snapshot(&tx, &id)? else {
+            return Ok(None);
+        };
+
+        delete_habit_row(&tx, &id)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        emit_snapshot_changed(&app_handle, &snapshot, Action::Delete);
+        encode_token(&snapshot).map(Some)
+    })
+}
+
+/// Restore the rows captured in an undo token
+#[tauri::command]
+pub async fn restore_from_undo(
+    state: tauri::State<'_, AppState>,
+    app_handle: AppHandle,
+    token: String,
+) -> Result<(), String> {
+    let snapshot: UndoSnapshot = serde_json::from_str(&token)
+        .map_err(|e| format!("Invalid undo token: {}", e))?;
+
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        restore_snapshot_tx(&tx, &snapshot)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+        emit_snapshot_changed(&app_handle, &snapshot, Action::Create);
+        Ok(())
+    })
+}
+
+/// Restore the most recently deleted goal from this session, with its tasks and habit links
+#[tauri::command]
+pub async fn undo_last_delete(
+    state: tauri::State<'_, AppState>,
+    deleted_goals: tauri::State<'_, DeletedGoals>,
+    app_handle: AppHandle,
+) -> Result<Goal, String> {
+    // Taken off under the lock so two undos can't replay the same deletion. The lock is
+    // released before the restore, which can wait on a busy database. A snapshot that fails
+    // to restore is dropped rather than left to fail every later undo.
+    let snapshot = deleted_goals
+        .0
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .pop_back()
+        .ok_or_else(|| "Nothing to undo".to_string())?;
+    let goal = snapshot.goal().cloned().ok_or_else(|| "Nothing to undo".to_string())?;
+
+    with_connection(&state, |db| {
+        let tx = db.transaction()
+            .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+        restore_snapshot_tx(&tx, &snapshot)?;
+
+        tx.commit()
+            .map_err(|e| format!("Failed to commit transaction: {}", e))
+    })?;
+
+    emit_snapshot_changed(&app_handle, &snapshot, Action::Create);
+
+    Ok(goal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{insert_test_habit, test_connection, ALL_WEEKDAYS};
+
+    #[test]
+    fn habit_undo_restores_frequency_history() {
+        let mut conn = test_connection();
+        insert_test_habit(&conn, "h", &ALL_WEEKDAYS);
+        conn.execute_batch(
+            "INSERT INTO habit_frequency_history (habit_id, frequency_type, frequency_value, effective_from)
+             VALUES ('h', 'daily', '[\"monday\"]', '2024-01-01'),
+                    ('h', 'daily', '[\"monday\",\"tuesday\",\"wednesday\",\"thursday\",\"friday\",\"saturday\",\"sunday\"]', '2024-02-01');",
+        )
+        .unwrap();
+        let before = get_frequency_history(&conn, "h").unwrap();
+
+        let tx = conn.transaction().unwrap();
+        let snapshot = capture_habit_snapshot(&tx, "h").unwrap().unwrap();
+        delete_habit_row(&tx, "h").unwrap();
+        tx.commit().unwrap();
+        assert!(get_frequency_history(&conn, "h").unwrap().is_empty());
+
+        // Through a token, as the frontend hands it back
+        let snapshot: UndoSnapshot = serde_json::from_str(&encode_token(&snapshot).unwrap()).unwrap();
+        let tx = conn.transaction().unwrap();
+        restore_snapshot_tx(&tx, &snapshot).unwrap();
+        tx.commit().unwrap();
+
+        let after = get_frequency_history(&conn, "h").unwrap();
+        assert_eq!(after.len(), before.len());
+        for (restored, original) in after.iter().zip(&before) {
+            assert_eq!(restored.id, original.id);
+            assert_eq!(restored.effective_from, original.effective_from);
+            assert_eq!(restored.frequency.value, original.frequency.value);
+        }
+    }
+
+    #[test]
+    fn goal_undo_relinks_focus_sessions_of_cascaded_tasks() {
+        let mut conn = test_connection();
+        conn.execute_batch(
+            "INSERT INTO goals (id, title, category, priority, status, color, icon, created_at, updated_at)
+             VALUES ('g', 'g', 'Work', 'medium', 'active', '#000000', 'Target', '2024-01-01', '2024-01-01');
+             INSERT INTO tasks (id, title, goal_id, created_at, updated_at)
+             VALUES ('t', 't', 'g', '2024-01-01', '2024-01-01');
+             INSERT INTO tasks (id, title, parent_task_id, created_at, updated_at)
+             VALUES ('sub', 'sub', 't', '2024-01-01', '2024-01-01');
+             INSERT INTO focus_sessions (task_id, started_at)
+             VALUES ('t', '2024-01-01T08:00:00.000Z'), ('sub', '2024-01-01T09:00:00.000Z');",
+        )
+        .unwrap();
+
+        let tx = conn.transaction().unwrap();
+        let snapshot = capture_goal_snapshot(&tx, "g", DeleteStrategy::Cascade).unwrap().unwrap();
+        delete_goal_tx(&tx, "g", DeleteStrategy::Cascade).unwrap();
+        tx.commit().unwrap();
+
+        let linked = |conn: &rusqlite::Connection| -> Vec<Option<String>> {
+            let mut stmt = conn.prepare("SELECT task_id FROM focus_sessions ORDER BY id").unwrap();
+            let rows = stmt.query_map([], |row| row.get(0)).unwrap();
+            rows.collect::<Result<_, _>>().unwrap()
+        };
+        assert_eq!(linked(&conn), [None, None]);
+
+        let snapshot: UndoSnapshot = serde_json::from_str(&encode_token(&snapshot).unwrap()).unwrap();
+        let tx = conn.transaction().unwrap();
+        restore_snapshot_tx(&tx, &snapshot).unwrap();
+        tx.commit().unwrap();
+
+        assert_eq!(linked(&conn), [Some("t".to_string()), Some("sub".to_string())]);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::commands::settings::{load_settings_from_db, save_settings_to_db_impl, UpdateChannel};
+use crate::database::{with_connection, AppState};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager, State, Url};
+use tauri_plugin_updater::{Updater, UpdaterExt};
+
+/// Update manifest for each release channel. Beta builds are published under a rolling `beta` tag.
+const STABLE_ENDPOINT: &str =
+    "https://github.com/MostafaWaleed0/loomra/releases/latest/download/latest.json";
+const BETA_ENDPOINT: &str =
+    "https://github.com/MostafaWaleed0/loomra/releases/download/beta/latest.json";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateProgress {
+    pub downloaded: u64,
+    /// None when the server doesn't report a content length
+    pub total: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateChannelChange {
+    pub channel: UpdateChannel,
+    /// Set when the installed version is newer than the channel's latest release
+    pub warning: Option<String>,
+}
+
+fn channel_endpoint(channel: UpdateChannel) -> &'static str {
+    match channel {
+        UpdateChannel::Stable => STABLE_ENDPOINT,
+        UpdateChannel::Beta => BETA_ENDPOINT,
+    }
+}
+
+/// Read the configured channel, defaulting to stable before settings exist
+fn configured_channel(app: &AppHandle) -> Result<UpdateChannel, String> {
+    let Some(state) = app.try_state::<AppState>() else {
+        return Ok(UpdateChannel::default());
+    };
+
+    Ok(load_settings_from_db(&state)?
+        .map(|settings| settings.system.update_channel)
+        .unwrap_or_default())
+}
+
+/// Build an updater pointed at the configured channel's manifest
+fn build_updater(app: &AppHandle) -> Result<Updater, String> {
+    let endpoint = Url::parse(channel_endpoint(configured_channel(app)?))
+        .map_err(|e| format!("Invalid update endpoint: {}", e))?;
+
+    app.updater_builder()
+        .endpoints(vec![endpoint])
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Failed to build updater: {}", e))
+}
+
+/// Latest stable version, if the installed version is newer than it
+async fn newer_than_stable(app: &AppHandle) -> Result<Option<String>, String> {
+    let endpoint = Url::parse(STABLE_ENDPOINT)
+        .map_err(|e| format!("Invalid update endpoint: {}", e))?;
+
+    // Inverted comparator: only "finds" an update when the remote is older than what's installed
+    let updater = app
+        .updater_builder()
+        .endpoints(vec![endpoint])
+        .map(|builder| builder.version_comparator(|current, remote| current > remote.version))
+        .and_then(|builder| builder.build())
+        .map_err(|e| format!("Failed to build updater: {}", e))?;
+
+    let latest = updater
+        .check()
+        .await
+        .map_err(|e| format!("Update check failed: {}", e))?;
+
+    Ok(latest.map(|update| update.version))
+}
+
+/// Emit an event to the main window, if it exists
+fn emit_to_main<S: Serialize + Clone>(app: &AppHandle, event: &str, payload: S) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.emit(event, payload);
+    }
+}
+
+/// Log an update failure and surface it to the frontend as `update-error`
+fn report_update_error(app: &AppHandle, message: &str) {
+    log::error!("{}", message);
+    emit_to_main(app, "update-error", message.to_string());
+}
+
+/// Check for an update in the background and emit the result to the frontend
+pub fn check_for_updates(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let updater = match build_updater(&app) {
+            Ok(updater) => updater,
+            Err(e) => {
+                report_update_error(&app, &e);
+                return;
+            }
+        };
+
+        match updater.check().await {
+            Ok(Some(update)) => emit_to_main(&app, "update-available", update.version),
+            Ok(None) => emit_to_main(&app, "update-not-available", ()),
+            Err(e) => report_update_error(&app, &format!("Update check failed: {}", e)),
+        }
+    });
+}
+
+async fn install_update(app: &AppHandle) -> Result<bool, String> {
+    let updater = build_updater(app)?;
+
+    let Some(update) = updater
+        .check()
+        .await
+        .map_err(|e| format!("Update check failed: {}", e))?
+    else {
+        return Ok(false);
+    };
+
+    let mut downloaded: u64 = 0;
+
+    update
+        .download_and_install(
+            |chunk_length, content_length| {
+                downloaded += chunk_length as u64;
+                emit_to_main(
+                    app,
+                    "update-progress",
+                    UpdateProgress {
+                        downloaded,
+                        total: content_length,
+                    },
+                );
+            },
+            || log::info!("Update downloaded, installing"),
+        )
+        .await
+        .map_err(|e| format!("Failed to install update: {}", e))?;
+
+    // The new version takes effect after a restart
+    emit_to_main(app, "update-installed", update.version.clone());
+
+    Ok(true)
+}
+
+/// Download and install the available update, emitting `update-progress` while downloading
+/// and `update-installed` when done. Returns false if no update is available.
+#[tauri::command]
+pub async fn download_and_install_update(app_handle: AppHandle) -> Result<bool, String> {
+    let result = install_update(&app_handle).await;
+
+    if let Err(e) = &result {
+        report_update_error(&app_handle, e);
+    }
+
+    result
+}
+
+/// Get the release channel the updater checks
+#[tauri::command]
+pub async fn get_update_channel(app_handle: AppHandle) -> Result<UpdateChannel, String> {
+    configured_channel(&app_handle)
+}
+
+/// Switch the release channel. Moving back to stable warns when the installed
+/// version is newer than the latest stable release, since no update will be offered until then.
+#[tauri::command]
+pub async fn set_update_channel(
+    channel: UpdateChannel,
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+) -> Result<UpdateChannelChange, String> {
+    let mut settings = load_settings_from_db(&state)?
+        .ok_or_else(|| "Settings not initialized".to_string())?;
+
+    let previous = settings.system.update_channel;
+    settings.system.update_channel = channel;
+
+    with_connection(&state, |conn| save_settings_to_db_impl(conn, &settings))?;
+
+    let mut warning = None;
+    if previous == UpdateChannel::Beta && channel == UpdateChannel::Stable {
+        // Failing to reach the server shouldn't block the switch itself
+        match newer_than_stable(&app_handle).await {
+            Ok(Some(latest_stable)) => {
+                warning = Some(format!(
+                    "Installed version {} is newer than the latest stable release {}. \
+                     Updates will resume once a newer stable version is published.",
+                    app_handle.package_info().version,
+                    latest_stable
+                ));
+            }
+            Ok(None) => {}
+            Err(e) => log::warn!("Failed to compare against the stable channel: {}", e),
+        }
+    }
+
+    Ok(UpdateChannelChange { channel, warning })
+}
@@ -1,8 +1,49 @@
-use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tauri::{AppHandle, Manager};
 
+/// Top-level field recording which shape of user data a file follows
+const SCHEMA_VERSION_FIELD: &str = "_schema_version";
+
+/// Shape written by this build. Files from before versioning count as version 1.
+const CURRENT_SCHEMA_VERSION: u64 = 1;
+
+/// Upgrades user data from one schema version to the next
+type Migration = fn(&mut Map<String, Value>);
+
+/// `MIGRATIONS[n]` upgrades version `n + 1` to `n + 2`.
+/// Append a step and bump `CURRENT_SCHEMA_VERSION` whenever the shape changes.
+const MIGRATIONS: &[Migration] = &[];
+
+const _: () = assert!(MIGRATIONS.len() as u64 + 1 == CURRENT_SCHEMA_VERSION);
+
+/// User data field holding the last opened view
+const LAST_VIEW_FIELD: &str = "lastView";
+
+/// View shown on first launch, before any view has been opened
+const DEFAULT_VIEW: &str = "goals";
+
+/// The view open when the app was last used, restored on launch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LastView {
+    pub view: String,
+    /// Frontend-defined extras such as scroll position or selection
+    #[serde(default)]
+    pub view_state: Option<Value>,
+}
+
+impl Default for LastView {
+    fn default() -> Self {
+        Self {
+            view: DEFAULT_VIEW.to_string(),
+            view_state: None,
+        }
+    }
+}
+
 /// Custom error type for user data operations
 #[derive(Debug, thiserror::Error)]
 pub enum UserDataError {
@@ -14,6 +55,9 @@ pub enum UserDataError {
 
     #[error("Path error: {0}")]
     Path(String),
+
+    #[error("User data schema version {0} is newer than this app supports")]
+    UnsupportedVersion(u64),
 }
 
 impl From<UserDataError> for String {
@@ -23,7 +67,7 @@ impl From<UserDataError> for String {
 }
 
 /// Get the path to the user config file
-fn get_user_data_path(app_handle: &AppHandle) -> Result<PathBuf, UserDataError> {
+pub(crate) fn get_user_data_path(app_handle: &AppHandle) -> Result<PathBuf, UserDataError> {
     let app_data_dir = app_handle
         .path()
         .app_data_dir()
@@ -33,50 +77,94 @@ fn get_user_data_path(app_handle: &AppHandle) -> Result<PathBuf, UserDataError>
 }
 
 /// Ensure the parent directory exists
-fn ensure_parent_dir(path: &PathBuf) -> Result<(), UserDataError> {
+fn ensure_parent_dir(path: &Path) -> Result<(), UserDataError> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
     Ok(())
 }
 
-/// Get user data from config file
-#[tauri::command]
-pub async fn get_user_data(app_handle: AppHandle) -> Result<Option<Value>, String> {
-    let path = get_user_data_path(&app_handle)?;
+/// Bring user data written by an older build up to the newest version `migrations` produce.
+/// The app passes `MIGRATIONS`, so the result is at `CURRENT_SCHEMA_VERSION`.
+fn apply_migrations(user_data: &mut Value, migrations: &[Migration]) -> Result<(), UserDataError> {
+    let Some(obj) = user_data.as_object_mut() else {
+        return Ok(());
+    };
+    let current_version = migrations.len() as u64 + 1;
+
+    let version = obj
+        .get(SCHEMA_VERSION_FIELD)
+        .and_then(Value::as_u64)
+        .unwrap_or(1)
+        .max(1);
+
+    if version > current_version {
+        return Err(UserDataError::UnsupportedVersion(version));
+    }
+
+    for migration in &migrations[(version - 1) as usize..] {
+        migration(obj);
+    }
+    obj.insert(SCHEMA_VERSION_FIELD.to_string(), current_version.into());
+
+    Ok(())
+}
+
+/// Read and migrate the user data file, or None if it doesn't exist yet
+fn read_user_data(path: &Path) -> Result<Option<Value>, String> {
+    read_user_data_with(path, MIGRATIONS)
+}
 
+fn read_user_data_with(path: &Path, migrations: &[Migration]) -> Result<Option<Value>, String> {
     if !path.exists() {
         return Ok(None);
     }
 
-    let data = fs::read_to_string(&path)
+    let data = fs::read_to_string(path)
         .map_err(|e| format!("Failed to read user data: {}", e))?;
 
-    let json: Value = serde_json::from_str(&data)
+    let mut json: Value = serde_json::from_str(&data)
         .map_err(|e| format!("Failed to parse user data: {}", e))?;
 
+    apply_migrations(&mut json, migrations)?;
+
     Ok(Some(json))
 }
 
-/// Save complete user data to config file
-#[tauri::command]
-pub async fn save_user_data(
-    app_handle: AppHandle,
-    user_data: Value,
-) -> Result<(), String> {
-    let path = get_user_data_path(&app_handle)?;
+/// Write user data stamped with the current schema version
+fn write_user_data(path: &Path, mut user_data: Value) -> Result<(), String> {
+    if let Some(obj) = user_data.as_object_mut() {
+        obj.insert(SCHEMA_VERSION_FIELD.to_string(), CURRENT_SCHEMA_VERSION.into());
+    }
 
-    ensure_parent_dir(&path)?;
+    ensure_parent_dir(path)?;
 
     let json = serde_json::to_string_pretty(&user_data)
         .map_err(|e| format!("Failed to serialize user data: {}", e))?;
 
-    fs::write(&path, json)
+    fs::write(path, json)
         .map_err(|e| format!("Failed to write user data: {}", e))?;
 
     Ok(())
 }
 
+/// Get user data from config file
+#[tauri::command]
+pub async fn get_user_data(app_handle: AppHandle) -> Result<Option<Value>, String> {
+    let path = get_user_data_path(&app_handle)?;
+    read_user_data(&path)
+}
+
+/// Save complete user data to config file
+#[tauri::command]
+pub async fn save_user_data(
+    app_handle: AppHandle,
+    user_data: Value,
+) -> Result<(), String> {
+    let path = get_user_data_path(&app_handle)?;
+    write_user_data(&path, user_data)
+}
+
 /// Update a specific field in user data
 #[tauri::command]
 pub async fn update_user_data(
@@ -87,14 +175,7 @@ pub async fn update_user_data(
     let path = get_user_data_path(&app_handle)?;
 
     // Load existing data or create new object
-    let mut user_data = if path.exists() {
-        let data = fs::read_to_string(&path)
-            .map_err(|e| format!("Failed to read user data: {}", e))?;
-        serde_json::from_str(&data)
-            .map_err(|e| format!("Failed to parse user data: {}", e))?
-    } else {
-        serde_json::json!({})
-    };
+    let mut user_data = read_user_data(&path)?.unwrap_or_else(|| serde_json::json!({}));
 
     // Update the field
     if let Some(obj) = user_data.as_object_mut() {
@@ -103,15 +184,7 @@ pub async fn update_user_data(
         return Err("User data is not a JSON object".to_string());
     }
 
-    ensure_parent_dir(&path)?;
-
-    let json = serde_json::to_string_pretty(&user_data)
-        .map_err(|e| format!("Failed to serialize user data: {}", e))?;
-
-    fs::write(&path, json)
-        .map_err(|e| format!("Failed to write user data: {}", e))?;
-
-    Ok(())
+    write_user_data(&path, user_data)
 }
 
 /// Update multiple fields in user data at once
@@ -123,14 +196,7 @@ pub async fn update_user_data_batch(
     let path = get_user_data_path(&app_handle)?;
 
     // Load existing data or create new object
-    let mut user_data = if path.exists() {
-        let data = fs::read_to_string(&path)
-            .map_err(|e| format!("Failed to read user data: {}", e))?;
-        serde_json::from_str(&data)
-            .map_err(|e| format!("Failed to parse user data: {}", e))?
-    } else {
-        serde_json::json!({})
-    };
+    let mut user_data = read_user_data(&path)?.unwrap_or_else(|| serde_json::json!({}));
 
     // Merge updates
     if let (Some(data_obj), Some(updates_obj)) = (user_data.as_object_mut(), updates.as_object()) {
@@ -141,15 +207,7 @@ pub async fn update_user_data_batch(
         return Err("Invalid data format for batch update".to_string());
     }
 
-    ensure_parent_dir(&path)?;
-
-    let json = serde_json::to_string_pretty(&user_data)
-        .map_err(|e| format!("Failed to serialize user data: {}", e))?;
-
-    fs::write(&path, json)
-        .map_err(|e| format!("Failed to write user data: {}", e))?;
-
-    Ok(())
+    write_user_data(&path, user_data)
 }
 
 /// Get a specific field from user data
@@ -160,15 +218,9 @@ pub async fn get_user_data_field(
 ) -> Result<Option<Value>, String> {
     let path = get_user_data_path(&app_handle)?;
 
-    if !path.exists() {
+    let Some(json) = read_user_data(&path)? else {
         return Ok(None);
-    }
-
-    let data = fs::read_to_string(&path)
-        .map_err(|e| format!("Failed to read user data: {}", e))?;
-
-    let json: Value = serde_json::from_str(&data)
-        .map_err(|e| format!("Failed to parse user data: {}", e))?;
+    };
 
     if let Some(obj) = json.as_object() {
         Ok(obj.get(&field).cloned())
@@ -195,4 +247,74 @@ pub async fn delete_user_data(app_handle: AppHandle) -> Result<(), String> {
 pub async fn user_data_exists(app_handle: AppHandle) -> Result<bool, String> {
     let path = get_user_data_path(&app_handle)?;
     Ok(path.exists())
+}
+
+/// Remember the open view so the next launch can restore it
+#[tauri::command]
+pub async fn set_last_view(
+    app_handle: AppHandle,
+    view: String,
+    view_state: Option<Value>,
+) -> Result<(), String> {
+    let value = serde_json::to_value(LastView { view, view_state })
+        .map_err(|e| format!("Failed to serialize last view: {}", e))?;
+
+    update_user_data(app_handle, LAST_VIEW_FIELD.to_string(), value).await
+}
+
+/// Get the last opened view, or the default view if none has been stored
+#[tauri::command]
+pub async fn get_last_view(app_handle: AppHandle) -> Result<LastView, String> {
+    let stored = get_user_data_field(app_handle, LAST_VIEW_FIELD.to_string()).await?;
+
+    // A stored value from an older or hand-edited file falls back to the default too
+    Ok(stored
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A v1 to v2 step that renames `theme` to `colorScheme`
+    fn rename_theme(user_data: &mut Map<String, Value>) {
+        if let Some(theme) = user_data.remove("theme") {
+            user_data.insert("colorScheme".to_string(), theme);
+        }
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("loomra-user-data-{}-{}.json", name, std::process::id()))
+    }
+
+    #[test]
+    fn v1_file_is_migrated_to_v2_on_read() {
+        let path = temp_file("v1");
+        fs::write(&path, r#"{"theme": "dark", "lastView": {"view": "habits"}}"#).unwrap();
+
+        let user_data = read_user_data_with(&path, &[rename_theme]).unwrap().unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(user_data[SCHEMA_VERSION_FIELD], 2);
+        assert_eq!(user_data["colorScheme"], "dark");
+        assert!(user_data.get("theme").is_none());
+        assert_eq!(user_data[LAST_VIEW_FIELD]["view"], "habits");
+    }
+
+    #[test]
+    fn current_file_skips_migrations() {
+        let mut user_data = serde_json::json!({ SCHEMA_VERSION_FIELD: 2, "theme": "dark" });
+        apply_migrations(&mut user_data, &[rename_theme]).unwrap();
+        assert_eq!(user_data["theme"], "dark");
+    }
+
+    #[test]
+    fn newer_file_is_rejected() {
+        let mut user_data = serde_json::json!({ SCHEMA_VERSION_FIELD: 3 });
+        assert!(matches!(
+            apply_migrations(&mut user_data, &[rename_theme]),
+            Err(UserDataError::UnsupportedVersion(3))
+        ));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,162 @@
+use crate::commands::auth::argon2id;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::{rngs::OsRng, RngCore};
+
+/// Marks a file as an encrypted Loomra export, followed by a format version byte
+const MAGIC: &[u8; 6] = b"LOOMRA";
+const FORMAT_VERSION: u8 = 1;
+
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+/// Memory cost, time cost and parallelism, each a little-endian u32
+const KDF_PARAMS_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + 1 + KDF_PARAMS_LEN + SALT_LEN + NONCE_LEN;
+
+/// Argon2id memory cost in KiB, passes and parallelism for new exports
+const KDF_MEMORY_COST: u32 = 19 * 1024;
+const KDF_TIME_COST: u32 = 2;
+const KDF_PARALLELISM: u32 = 1;
+
+/// Shortest passphrase accepted, in characters
+const MIN_PASSPHRASE_CHARS: usize = 8;
+
+/// Custom error type for export encryption
+#[derive(Debug, thiserror::Error)]
+pub enum CryptoError {
+    #[error("Passphrase must be at least 8 characters long")]
+    WeakPassphrase,
+
+    #[error("Key derivation failed: {0}")]
+    KeyDerivation(String),
+
+    #[error("Encryption failed")]
+    Encryption,
+
+    #[error("Not an encrypted Loomra export")]
+    InvalidFormat,
+
+    #[error("Unsupported encrypted export version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Incorrect passphrase or corrupted file")]
+    Decryption,
+}
+
+impl From<CryptoError> for String {
+    fn from(err: CryptoError) -> Self {
+        err.to_string()
+    }
+}
+
+/// Derive a 256-bit key from a passphrase with Argon2id and the given costs.
+/// Costs over the password hashing caps are refused, so a crafted header can't stall the app.
+fn derive_key(
+    passphrase: &str,
+    salt: &[u8],
+    [memory_cost, time_cost, parallelism]: [u32; 3],
+) -> Result<Key, CryptoError> {
+    let mut key = Key::default();
+    argon2id(memory_cost, time_cost, parallelism)
+        .map_err(CryptoError::KeyDerivation)?
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| CryptoError::KeyDerivation(e.to_string()))?;
+    Ok(key)
+}
+
+/// Encrypt with ChaCha20-Poly1305.
+/// Output layout: magic, version, key derivation costs, salt, nonce, ciphertext.
+pub fn encrypt(plaintext: &[u8], passphrase: &str) -> Result<Vec<u8>, CryptoError> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(CryptoError::WeakPassphrase);
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let costs = [KDF_MEMORY_COST, KDF_TIME_COST, KDF_PARALLELISM];
+    let key = derive_key(passphrase, &salt, costs)?;
+    let ciphertext = ChaCha20Poly1305::new(&key)
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| CryptoError::Encryption)?;
+
+    let mut output = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+    output.extend_from_slice(MAGIC);
+    output.push(FORMAT_VERSION);
+    for cost in costs {
+        output.extend_from_slice(&cost.to_le_bytes());
+    }
+    output.extend_from_slice(&salt);
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&ciphertext);
+
+    Ok(output)
+}
+
+/// Read the key derivation costs that follow the version byte
+fn read_kdf_costs(header: &[u8]) -> [u32; 3] {
+    let mut costs = [0u32; 3];
+    for (cost, bytes) in costs.iter_mut().zip(header.chunks_exact(4)) {
+        *cost = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    costs
+}
+
+/// Decrypt data produced by `encrypt`. A wrong passphrase fails authentication.
+pub fn decrypt(data: &[u8], passphrase: &str) -> Result<Vec<u8>, CryptoError> {
+    if data.len() < HEADER_LEN || !data.starts_with(MAGIC) {
+        return Err(CryptoError::InvalidFormat);
+    }
+
+    let version = data[MAGIC.len()];
+    if version != FORMAT_VERSION {
+        return Err(CryptoError::UnsupportedVersion(version));
+    }
+
+    let salt_start = MAGIC.len() + 1 + KDF_PARAMS_LEN;
+    let costs = read_kdf_costs(&data[MAGIC.len() + 1..salt_start]);
+    let nonce_start = salt_start + SALT_LEN;
+    let ciphertext_start = nonce_start + NONCE_LEN;
+    let salt = &data[salt_start..nonce_start];
+    let nonce = &data[nonce_start..ciphertext_start];
+
+    let key = derive_key(passphrase, salt, costs)?;
+    ChaCha20Poly1305::new(&key)
+        .decrypt(Nonce::from_slice(nonce), &data[ciphertext_start..])
+        .map_err(|_| CryptoError::Decryption)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_records_the_key_derivation_costs() {
+        let encrypted = encrypt(b"data", "correct horse").unwrap();
+        let version_end = MAGIC.len() + 1;
+
+        assert_eq!(encrypted[MAGIC.len()], FORMAT_VERSION);
+        assert_eq!(
+            read_kdf_costs(&encrypted[version_end..version_end + KDF_PARAMS_LEN]),
+            [KDF_MEMORY_COST, KDF_TIME_COST, KDF_PARALLELISM]
+        );
+        assert_eq!(decrypt(&encrypted, "correct horse").unwrap(), b"data");
+    }
+
+    #[test]
+    fn excessive_costs_in_the_header_are_rejected() {
+        let mut encrypted = encrypt(b"data", "correct horse").unwrap();
+        let version_end = MAGIC.len() + 1;
+        encrypted[version_end..version_end + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+
+        assert!(matches!(decrypt(&encrypted, "correct horse"), Err(CryptoError::KeyDerivation(_))));
+    }
+
+    #[test]
+    fn passphrase_length_counts_characters() {
+        assert!(matches!(encrypt(b"data", "äöü"), Err(CryptoError::WeakPassphrase)));
+        assert!(encrypt(b"data", "äöüäöüäö").is_ok());
+    }
+}
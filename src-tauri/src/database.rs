@@ -1,11 +1,291 @@
-use r2d2::Pool;
+use crate::commands::settings::ensure_default_settings;
+use crate::validation::ValidationError;
+use r2d2::{Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::{Connection, Result as SqlResult};
+use rusqlite::{params, Connection, Result as SqlResult};
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+use std::thread;
+use std::time::Duration;
 use tauri::{AppHandle, Manager};
 
+/// Name of the profile backed by the original, unsuffixed database file
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Version of the schema this build creates, stored in `PRAGMA user_version` once it's applied.
+/// Bump it whenever a table, migration or index changes.
+pub const SCHEMA_VERSION: i32 = 4;
+
+/// File in the app data dir remembering the last active profile
+const ACTIVE_PROFILE_FILE: &str = "active-profile";
+
+/// How many times to try checking out a connection before giving up
+const CONNECTION_ATTEMPTS: u32 = 3;
+
+/// How long each attempt waits for a free connection, keeping all attempts within the pool's timeout
+const CONNECTION_ATTEMPT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pause before the first retry, doubled before each later one
+const CONNECTION_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Journal modes accepted by `LOOMRA_JOURNAL_MODE`
+const JOURNAL_MODES: [&str; 6] = ["DELETE", "TRUNCATE", "PERSIST", "MEMORY", "WAL", "OFF"];
+
+/// Synchronous levels accepted by `LOOMRA_SYNCHRONOUS`
+const SYNCHRONOUS_LEVELS: [&str; 4] = ["OFF", "NORMAL", "FULL", "EXTRA"];
+
+/// Durability pragmas applied to every database.
+/// The defaults suit local disks. Network or cloud-synced folders may need `DELETE` and `FULL`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StoragePragmas {
+    pub journal_mode: &'static str,
+    pub synchronous: &'static str,
+    /// Bytes of the database file to memory-map, 0 to disable
+    pub mmap_size: i64,
+}
+
+impl Default for StoragePragmas {
+    fn default() -> Self {
+        Self {
+            journal_mode: "WAL",
+            synchronous: "NORMAL",
+            mmap_size: 268435456, // 256MB memory-mapped I/O
+        }
+    }
+}
+
+impl StoragePragmas {
+    /// Read overrides from `LOOMRA_JOURNAL_MODE`, `LOOMRA_SYNCHRONOUS` and `LOOMRA_MMAP_SIZE`.
+    /// Values outside the allowed options are logged and the default is kept.
+    fn from_env() -> Self {
+        let defaults = Self::default();
+
+        Self {
+            journal_mode: env_choice("LOOMRA_JOURNAL_MODE", &JOURNAL_MODES)
+                .unwrap_or(defaults.journal_mode),
+            synchronous: env_choice("LOOMRA_SYNCHRONOUS", &SYNCHRONOUS_LEVELS)
+                .unwrap_or(defaults.synchronous),
+            mmap_size: std::env::var("LOOMRA_MMAP_SIZE")
+                .ok()
+                .and_then(|value| match value.trim().parse::<i64>() {
+                    Ok(size) if size >= 0 => Some(size),
+                    _ => {
+                        log::warn!("Ignoring LOOMRA_MMAP_SIZE={:?}: expected a byte count", value);
+                        None
+                    }
+                })
+                .unwrap_or(defaults.mmap_size),
+        }
+    }
+}
+
+/// Read an environment variable that must be one of `allowed`, ignoring case
+fn env_choice(name: &str, allowed: &[&'static str]) -> Option<&'static str> {
+    let value = std::env::var(name).ok()?;
+    let choice = allowed
+        .iter()
+        .find(|option| option.eq_ignore_ascii_case(value.trim()))
+        .copied();
+
+    if choice.is_none() {
+        log::warn!(
+            "Ignoring {}={:?}: expected one of {}",
+            name,
+            value,
+            allowed.join(", ")
+        );
+    }
+
+    choice
+}
+
+/// Storage pragmas for this run, resolved and logged once
+pub fn storage_pragmas() -> StoragePragmas {
+    static PRAGMAS: OnceLock<StoragePragmas> = OnceLock::new();
+
+    *PRAGMAS.get_or_init(|| {
+        let pragmas = StoragePragmas::from_env();
+        log::info!(
+            "Database storage: journal_mode={} synchronous={} mmap_size={}",
+            pragmas.journal_mode,
+            pragmas.synchronous,
+            pragmas.mmap_size
+        );
+        pragmas
+    })
+}
+
 /// Application state holding the database connection pool
 pub struct AppState {
-    pub db: Pool<SqliteConnectionManager>,
+    pub db: DbPool,
+}
+
+/// Connection pool for the active profile that can be swapped at runtime
+pub struct DbPool {
+    active: RwLock<ActiveDatabase>,
+}
+
+struct ActiveDatabase {
+    profile: String,
+    pool: Pool<SqliteConnectionManager>,
+}
+
+impl DbPool {
+    pub fn new(profile: String, pool: Pool<SqliteConnectionManager>) -> Self {
+        Self {
+            active: RwLock::new(ActiveDatabase { profile, pool }),
+        }
+    }
+
+    /// Clone the active pool handle so the lock isn't held while waiting for a connection
+    fn pool(&self) -> Pool<SqliteConnectionManager> {
+        self.active
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .pool
+            .clone()
+    }
+
+    /// Check out a connection from the active profile's pool
+    pub fn get(&self) -> Result<PooledConnection<SqliteConnectionManager>, r2d2::Error> {
+        self.pool().get()
+    }
+
+    /// Check out a connection, waiting at most `timeout` for one to free up
+    pub fn get_timeout(
+        &self,
+        timeout: Duration,
+    ) -> Result<PooledConnection<SqliteConnectionManager>, r2d2::Error> {
+        self.pool().get_timeout(timeout)
+    }
+
+    /// Name of the active profile
+    pub fn profile(&self) -> String {
+        self.active
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .profile
+            .clone()
+    }
+
+    /// Replace the active pool. The old pool closes once outstanding connections are returned.
+    pub fn replace(&self, profile: String, pool: Pool<SqliteConnectionManager>) {
+        let mut active = self.active.write().unwrap_or_else(|e| e.into_inner());
+        *active = ActiveDatabase { profile, pool };
+    }
+}
+
+/// Check out a connection, retrying with backoff while the pool is exhausted
+pub fn get_connection(state: &AppState) -> Result<PooledConnection<SqliteConnectionManager>, String> {
+    checkout_with_retry(&state.db, CONNECTION_ATTEMPT_TIMEOUT)
+}
+
+/// Try `CONNECTION_ATTEMPTS` checkouts, each waiting up to `attempt_timeout`
+fn checkout_with_retry(
+    db: &DbPool,
+    attempt_timeout: Duration,
+) -> Result<PooledConnection<SqliteConnectionManager>, String> {
+    let mut attempt = 1;
+    let mut backoff = CONNECTION_RETRY_BACKOFF;
+
+    loop {
+        match db.get_timeout(attempt_timeout) {
+            Ok(conn) => return Ok(conn),
+            Err(e) if attempt < CONNECTION_ATTEMPTS => {
+                log::warn!("Database connection attempt {} failed, retrying: {}", attempt, e);
+                thread::sleep(backoff);
+                backoff *= 2;
+                attempt += 1;
+            }
+            Err(e) => return Err(format!("Failed to get database connection: {}", e)),
+        }
+    }
+}
+
+/// Run `f` with a pooled connection, retrying the checkout while the pool is exhausted
+pub fn with_connection<F, R>(state: &AppState, f: F) -> Result<R, String>
+where
+    F: FnOnce(&mut Connection) -> Result<R, String>,
+{
+    let mut conn = get_connection(state)?;
+    f(&mut conn)
+}
+
+/// Refuse to add a row to `table` once it holds `limit` rows. No limit means no check.
+pub fn ensure_below_row_limit(
+    conn: &Connection,
+    table: &'static str,
+    limit: Option<u32>,
+) -> Result<(), String> {
+    let Some(limit) = limit else {
+        return Ok(());
+    };
+
+    let count: i64 = conn
+        .query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))
+        .map_err(|e| format!("Failed to count {}: {}", table, e))?;
+
+    if count >= i64::from(limit) {
+        return Err(ValidationError::LimitReached { table, limit }.into());
+    }
+
+    Ok(())
+}
+
+/// Move every row of `table` in category `old` to `new` in one transaction, returning the ids changed.
+/// The new name is trimmed and must not be empty.
+pub fn rename_category(
+    conn: &mut Connection,
+    table: &'static str,
+    old: &str,
+    new: &str,
+) -> Result<Vec<String>, String> {
+    let new = new.trim();
+    if new.is_empty() {
+        return Err(ValidationError::EmptyCategory.into());
+    }
+
+    let tx = conn.transaction()
+        .map_err(|e| format!("Failed to start transaction: {}", e))?;
+
+    // Bump updated_at so synced devices take the new name. Uses idx_{table}_category.
+    let now = chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+    let mut stmt = tx
+        .prepare(&format!(
+            "UPDATE {} SET category = ?2, updated_at = ?3 WHERE category = ?1 RETURNING id",
+            table
+        ))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let ids = stmt
+        .query_map(params![old, new, now], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Failed to rename {} category: {}", table, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to rename {} category: {}", table, e))?;
+    drop(stmt);
+
+    tx.commit()
+        .map_err(|e| format!("Failed to commit transaction: {}", e))?;
+
+    Ok(ids)
+}
+
+/// Distinct non-empty categories used in `table`, sorted. Reads idx_{table}_category.
+pub fn distinct_categories(conn: &Connection, table: &'static str) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT DISTINCT category FROM {} WHERE trim(category) != '' ORDER BY category",
+            table
+        ))
+        .map_err(|e| format!("Failed to prepare statement: {}", e))?;
+
+    let categories = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Failed to query {} categories: {}", table, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to collect {} categories: {}", table, e))?;
+
+    Ok(categories)
 }
 
 /// Custom error type for database operations
@@ -22,6 +302,56 @@ pub enum DatabaseError {
 
     #[error("Pool error: {0}")]
     Pool(String),
+
+    /// A mutation targeted a row that doesn't exist. Lookups return `None` instead.
+    #[error("{entity} with id '{id}' not found")]
+    NotFound { entity: &'static str, id: String },
+
+    /// An update expected a different `updated_at`, so another window saved the row first
+    #[error("Conflict: {entity} with id '{id}' was changed elsewhere, reload it and try again")]
+    Conflict { entity: &'static str, id: String },
+}
+
+impl DatabaseError {
+    pub fn not_found(entity: &'static str, id: &str) -> Self {
+        Self::NotFound {
+            entity,
+            id: id.to_string(),
+        }
+    }
+
+    pub fn conflict(entity: &'static str, id: &str) -> Self {
+        Self::Conflict {
+            entity,
+            id: id.to_string(),
+        }
+    }
+
+    /// Explain an update that matched no row. Without `expected_updated_at` the row must be
+    /// missing; with it, a row that still exists was changed since the caller loaded it.
+    pub fn update_missed(
+        conn: &Connection,
+        table: &str,
+        entity: &'static str,
+        id: &str,
+        expected_updated_at: Option<&str>,
+    ) -> Self {
+        if expected_updated_at.is_none() {
+            return Self::not_found(entity, id);
+        }
+
+        let exists = conn.query_row(
+            &format!("SELECT EXISTS(SELECT 1 FROM {} WHERE id = ?1)", table),
+            [id],
+            |row| row.get::<_, bool>(0),
+        );
+
+        match exists {
+            Ok(true) => Self::conflict(entity, id),
+            Ok(false) => Self::not_found(entity, id),
+            Err(e) => e.into(),
+        }
+    }
 }
 
 impl From<DatabaseError> for String {
@@ -40,8 +370,20 @@ fn get_environment() -> String {
 
 /// Initialize the database with proper error handling and connection pooling
 pub fn init_database(app_handle: &AppHandle) -> Result<(), DatabaseError> {
-    let env_mode = get_environment();
+    let app_dir = get_app_dir(app_handle)?;
+    let profile = read_active_profile(&app_dir);
+
+    let pool = open_pool(&profile_db_path(&app_dir, &profile))?;
+
+    app_handle.manage(AppState {
+        db: DbPool::new(profile, pool),
+    });
+
+    Ok(())
+}
 
+/// Resolve and create the app data directory
+pub fn get_app_dir(app_handle: &AppHandle) -> Result<PathBuf, DatabaseError> {
     let app_dir = app_handle
         .path()
         .app_data_dir()
@@ -49,14 +391,45 @@ pub fn init_database(app_handle: &AppHandle) -> Result<(), DatabaseError> {
 
     std::fs::create_dir_all(&app_dir)?;
 
-    let db_filename = match env_mode.as_str() {
-        "dev" => "loomra-dev.db",
-        _ => "loomra.db",
-    };
+    Ok(app_dir)
+}
+
+/// Database filename prefix for the current environment
+pub fn db_file_prefix() -> &'static str {
+    match get_environment().as_str() {
+        "dev" => "loomra-dev",
+        _ => "loomra",
+    }
+}
 
-    let db_path = app_dir.join(db_filename);
+/// Database path for a profile. The default profile keeps the original filename.
+pub fn profile_db_path(app_dir: &Path, profile: &str) -> PathBuf {
+    if profile == DEFAULT_PROFILE {
+        app_dir.join(format!("{}.db", db_file_prefix()))
+    } else {
+        app_dir.join(format!("{}-{}.db", db_file_prefix(), profile))
+    }
+}
 
-    let manager = SqliteConnectionManager::file(&db_path);
+/// Read the last active profile, falling back to the default profile
+fn read_active_profile(app_dir: &Path) -> String {
+    std::fs::read_to_string(app_dir.join(ACTIVE_PROFILE_FILE))
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty() && profile_db_path(app_dir, name).exists())
+        .unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+}
+
+/// Remember the active profile for the next launch
+pub fn write_active_profile(app_dir: &Path, profile: &str) -> Result<(), DatabaseError> {
+    std::fs::write(app_dir.join(ACTIVE_PROFILE_FILE), profile)?;
+    Ok(())
+}
+
+/// Open a connection pool for a database file, creating the schema if needed.
+/// Every connection the pool opens is configured, since most pragmas only apply to one connection.
+pub fn open_pool(db_path: &Path) -> Result<Pool<SqliteConnectionManager>, DatabaseError> {
+    let manager = SqliteConnectionManager::file(db_path).with_init(|conn| configure_connection(conn));
     let pool = Pool::builder()
         .max_size(10)
         .connection_timeout(std::time::Duration::from_secs(30))
@@ -65,31 +438,93 @@ pub fn init_database(app_handle: &AppHandle) -> Result<(), DatabaseError> {
 
     {
         let conn = pool.get().map_err(|e| DatabaseError::Pool(e.to_string()))?;
-        configure_connection(&conn)?;
         create_schema(&conn)?;
+        ensure_default_settings(&conn)?;
     }
 
-    app_handle.manage(AppState { db: pool });
-
-    Ok(())
+    Ok(pool)
 }
 
 /// Configure SQLite connection with optimal settings
 fn configure_connection(conn: &Connection) -> SqlResult<()> {
-    conn.pragma_update(None, "journal_mode", "WAL")?;
+    let pragmas = storage_pragmas();
+
+    conn.pragma_update(None, "journal_mode", pragmas.journal_mode)?;
     conn.pragma_update(None, "foreign_keys", "ON")?;
-    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    conn.pragma_update(None, "synchronous", pragmas.synchronous)?;
     conn.pragma_update(None, "cache_size", -64000)?; // 64MB cache
     conn.pragma_update(None, "temp_store", "MEMORY")?;
-    conn.pragma_update(None, "mmap_size", 268435456i64)?; // 256MB memory-mapped I/O
+    conn.pragma_update(None, "mmap_size", pragmas.mmap_size)?;
     conn.pragma_update(None, "page_size", 4096)?;
     Ok(())
 }
 
 /// Create all database tables and indexes
 fn create_schema(conn: &Connection) -> SqlResult<()> {
+    let backfill_habit_goals = !table_exists(conn, "habit_goals")?;
+
     create_tables(conn)?;
+    migrate_tables(conn)?;
     create_indexes(conn)?;
+    create_triggers(conn)?;
+
+    // Databases from before the join table existed only have the JSON arrays
+    if backfill_habit_goals {
+        conn.execute(
+            "INSERT OR IGNORE INTO habit_goals (habit_id, goal_id)
+             SELECT h.id, j.value
+             FROM habits h,
+                  json_each(CASE WHEN json_valid(h.linked_goals) THEN h.linked_goals ELSE '[]' END) j
+             WHERE j.type = 'text'",
+            [],
+        )?;
+    }
+
+    conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+
+    Ok(())
+}
+
+/// Schema version recorded in the database, 0 if it was never set
+pub fn schema_version(conn: &Connection) -> SqlResult<i32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+fn table_exists(conn: &Connection, name: &str) -> SqlResult<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+        [name],
+        |row| row.get(0),
+    )
+}
+
+fn column_exists(conn: &Connection, table: &str, column: &str) -> SqlResult<bool> {
+    conn.query_row(
+        "SELECT EXISTS(SELECT 1 FROM pragma_table_info(?1) WHERE name = ?2)",
+        [table, column],
+        |row| row.get(0),
+    )
+}
+
+/// Add columns introduced after a table was first created
+fn migrate_tables(conn: &Connection) -> SqlResult<()> {
+    if !column_exists(conn, "goals", "completed_at")? {
+        conn.execute("ALTER TABLE goals ADD COLUMN completed_at TEXT", [])?;
+
+        // The last update is the best guess for goals completed before this was tracked
+        conn.execute(
+            "UPDATE goals SET completed_at = updated_at WHERE status = 'completed'",
+            [],
+        )?;
+    }
+
+    if !column_exists(conn, "habits", "completion_threshold")? {
+        conn.execute(
+            "ALTER TABLE habits ADD COLUMN completion_threshold REAL NOT NULL DEFAULT 1.0",
+            [],
+        )?;
+    }
+
     Ok(())
 }
 
@@ -109,7 +544,8 @@ fn create_tables(conn: &Connection) -> SqlResult<()> {
             icon TEXT NOT NULL,
             deadline TEXT,
             created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL
+            updated_at TEXT NOT NULL,
+            completed_at TEXT
         )",
         [],
     )?;
@@ -151,7 +587,19 @@ fn create_tables(conn: &Connection) -> SqlResult<()> {
             reminder_enabled INTEGER NOT NULL DEFAULT 0,
             reminder_time TEXT NOT NULL DEFAULT '09:00',
             created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL
+            updated_at TEXT NOT NULL,
+            completion_threshold REAL NOT NULL DEFAULT 1.0
+        )",
+        [],
+    )?;
+
+    // Habit to goal links, kept in sync with habits.linked_goals by triggers
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS habit_goals (
+            habit_id TEXT NOT NULL,
+            goal_id TEXT NOT NULL,
+            PRIMARY KEY (habit_id, goal_id),
+            FOREIGN KEY (habit_id) REFERENCES habits(id) ON DELETE CASCADE
         )",
         [],
     )?;
@@ -178,6 +626,34 @@ fn create_tables(conn: &Connection) -> SqlResult<()> {
         [],
     )?;
 
+    // Habit pause periods. A NULL end_date means paused until resumed
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS habit_pauses (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            habit_id TEXT NOT NULL,
+            start_date TEXT NOT NULL,
+            end_date TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (habit_id) REFERENCES habits(id) ON DELETE CASCADE
+        )",
+        [],
+    )?;
+
+    // Frequencies a habit has followed, each applying from effective_from until the next change
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS habit_frequency_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            habit_id TEXT NOT NULL,
+            frequency_type TEXT NOT NULL,
+            frequency_value TEXT NOT NULL,
+            effective_from TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            FOREIGN KEY (habit_id) REFERENCES habits(id) ON DELETE CASCADE,
+            UNIQUE(habit_id, effective_from)
+        )",
+        [],
+    )?;
+
     // Notification schedules table
     conn.execute(
         "CREATE TABLE IF NOT EXISTS notification_schedules (
@@ -212,6 +688,45 @@ fn create_tables(conn: &Connection) -> SqlResult<()> {
         [],
     )?;
 
+    // Links and local files attached to goals and tasks. entity_type is 'goal' or 'task';
+    // there is no foreign key, so deleting the owner removes these in the same transaction
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS attachments (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            entity_type TEXT NOT NULL,
+            entity_id TEXT NOT NULL,
+            kind TEXT NOT NULL,
+            value TEXT NOT NULL,
+            label TEXT NOT NULL DEFAULT '',
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+        [],
+    )?;
+
+    // Timed focus sessions. Deleting a task keeps its sessions with task_id cleared
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS focus_sessions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            task_id TEXT,
+            started_at TEXT NOT NULL,
+            ended_at TEXT,
+            duration_seconds INTEGER,
+            FOREIGN KEY (task_id) REFERENCES tasks(id) ON DELETE SET NULL
+        )",
+        [],
+    )?;
+
+    // Keys of retried create calls and the entity each one created
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS idempotency (
+            key TEXT PRIMARY KEY,
+            entity_type TEXT NOT NULL,
+            entity_id TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        )",
+        [],
+    )?;
+
     // Settings table
     conn.execute(
         "CREATE TABLE IF NOT EXISTS settings (
@@ -225,8 +740,9 @@ fn create_tables(conn: &Connection) -> SqlResult<()> {
     Ok(())
 }
 
-/// Create all database indexes for optimal query performance
-fn create_indexes(conn: &Connection) -> SqlResult<()> {
+/// Create all database indexes for optimal query performance.
+/// Every index is `IF NOT EXISTS`, so this also adds any an older database is missing.
+pub fn create_indexes(conn: &Connection) -> SqlResult<()> {
     let indexes = [
         // Task indexes
         "CREATE INDEX IF NOT EXISTS idx_tasks_goal_id ON tasks(goal_id)",
@@ -247,6 +763,9 @@ fn create_indexes(conn: &Connection) -> SqlResult<()> {
         "CREATE INDEX IF NOT EXISTS idx_habits_category ON habits(category)",
         "CREATE INDEX IF NOT EXISTS idx_habits_start_date ON habits(start_date)",
 
+        // Habit goal link indexes
+        "CREATE INDEX IF NOT EXISTS idx_habit_goals_goal_id ON habit_goals(goal_id)",
+
         // Habit completion indexes
         "CREATE INDEX IF NOT EXISTS idx_habit_completions_habit_id ON habit_completions(habit_id)",
         "CREATE INDEX IF NOT EXISTS idx_habit_completions_date ON habit_completions(date)",
@@ -254,6 +773,9 @@ fn create_indexes(conn: &Connection) -> SqlResult<()> {
         "CREATE INDEX IF NOT EXISTS idx_habit_completions_habit_completed ON habit_completions(habit_id, completed, date DESC)",
         "CREATE INDEX IF NOT EXISTS idx_habit_completions_streak ON habit_completions(habit_id, date DESC, completed)",
 
+        // Habit pause indexes
+        "CREATE INDEX IF NOT EXISTS idx_habit_pauses_habit_dates ON habit_pauses(habit_id, start_date, end_date)",
+
         // Notification schedule indexes
         "CREATE INDEX IF NOT EXISTS idx_notification_schedules_habit_id ON notification_schedules(habit_id)",
         "CREATE INDEX IF NOT EXISTS idx_notification_schedules_time ON notification_schedules(scheduled_time)",
@@ -263,6 +785,13 @@ fn create_indexes(conn: &Connection) -> SqlResult<()> {
         "CREATE INDEX IF NOT EXISTS idx_notification_history_habit_id ON notification_history(habit_id)",
         "CREATE INDEX IF NOT EXISTS idx_notification_history_sent_at ON notification_history(sent_at)",
         "CREATE INDEX IF NOT EXISTS idx_notification_history_type ON notification_history(notification_type)",
+
+        // Attachment indexes
+        "CREATE INDEX IF NOT EXISTS idx_attachments_entity ON attachments(entity_type, entity_id)",
+
+        // Focus session indexes
+        "CREATE INDEX IF NOT EXISTS idx_focus_sessions_task_id ON focus_sessions(task_id)",
+        "CREATE INDEX IF NOT EXISTS idx_focus_sessions_started_at ON focus_sessions(started_at)",
     ];
 
     for index_sql in indexes {
@@ -270,4 +799,104 @@ fn create_indexes(conn: &Connection) -> SqlResult<()> {
     }
 
     Ok(())
+}
+
+/// Create triggers that mirror habits.linked_goals into the habit_goals join table.
+/// The JSON column stays the source of truth so every write path keeps the links in sync.
+fn create_triggers(conn: &Connection) -> SqlResult<()> {
+    // Malformed or non-string entries are ignored, like Habit::from_row does
+    const INSERT_LINKS: &str = "INSERT OR IGNORE INTO habit_goals (habit_id, goal_id)
+            SELECT NEW.id, value
+            FROM json_each(CASE WHEN json_valid(NEW.linked_goals) THEN NEW.linked_goals ELSE '[]' END)
+            WHERE type = 'text';";
+
+    conn.execute_batch(&format!(
+        "CREATE TRIGGER IF NOT EXISTS trg_habits_linked_goals_insert
+         AFTER INSERT ON habits
+         BEGIN
+            {insert}
+         END;
+
+         CREATE TRIGGER IF NOT EXISTS trg_habits_linked_goals_update
+         AFTER UPDATE OF id, linked_goals ON habits
+         BEGIN
+            DELETE FROM habit_goals WHERE habit_id = OLD.id;
+            {insert}
+         END;
+
+         CREATE TRIGGER IF NOT EXISTS trg_habits_linked_goals_delete
+         AFTER DELETE ON habits
+         BEGIN
+            DELETE FROM habit_goals WHERE habit_id = OLD.id;
+         END;",
+        insert = INSERT_LINKS
+    ))
+}
+
+/// A configured in-memory database with the full schema, for tests
+#[cfg(test)]
+pub(crate) fn test_connection() -> Connection {
+    let conn = Connection::open_in_memory().expect("Failed to open in-memory database");
+    configure_connection(&conn).expect("Failed to configure test database");
+    create_schema(&conn).expect("Failed to create test schema");
+    conn
+}
+
+/// Every day of the week, for test habits scheduled daily
+#[cfg(test)]
+pub(crate) const ALL_WEEKDAYS: [&str; 7] =
+    ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+
+/// Insert a daily habit scheduled on `weekdays`, started on 2024-01-01, for tests
+#[cfg(test)]
+pub(crate) fn insert_test_habit(conn: &Connection, id: &str, weekdays: &[&str]) {
+    let weekdays = serde_json::to_string(weekdays).unwrap();
+    conn.execute(
+        "INSERT INTO habits (id, name, category, icon, color, frequency_type, frequency_value, start_date,
+                             created_at, updated_at)
+         VALUES (?1, ?1, 'Health', 'Check', '#000000', 'daily', ?2, '2024-01-01',
+                 '2024-01-01T00:00:00.000Z', '2024-01-01T00:00:00.000Z')",
+        params![id, weekdays],
+    )
+    .expect("Failed to insert test habit");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const POOL_SIZE: u32 = 2;
+
+    fn exhausted_pool() -> (DbPool, Vec<PooledConnection<SqliteConnectionManager>>) {
+        let pool = Pool::builder()
+            .max_size(POOL_SIZE)
+            .build(SqliteConnectionManager::memory())
+            .unwrap();
+        let held = (0..POOL_SIZE).map(|_| pool.get().unwrap()).collect();
+        (DbPool::new(DEFAULT_PROFILE.to_string(), pool), held)
+    }
+
+    #[test]
+    fn checkout_retries_until_a_connection_frees_up() {
+        let (db, mut held) = exhausted_pool();
+        let released = held.pop().unwrap();
+
+        // Freed after the first attempt has timed out
+        let releaser = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(80));
+            drop(released);
+        });
+
+        let conn = checkout_with_retry(&db, Duration::from_millis(20));
+        releaser.join().unwrap();
+        assert!(conn.is_ok());
+    }
+
+    #[test]
+    fn checkout_fails_once_every_attempt_times_out() {
+        let (db, _held) = exhausted_pool();
+
+        let error = checkout_with_retry(&db, Duration::from_millis(20)).err().unwrap();
+        assert!(error.starts_with("Failed to get database connection"), "{}", error);
+    }
 }
\ No newline at end of file
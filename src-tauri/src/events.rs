@@ -0,0 +1,40 @@
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
+
+/// Sent to every window after a goal, task, habit or completion changes
+pub const DATA_CHANGED_EVENT: &str = "data-changed";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Entity {
+    Goal,
+    Task,
+    Habit,
+    Completion,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Create,
+    Update,
+    Delete,
+}
+
+/// Payload of `data-changed`, e.g. `{ "entity": "task", "action": "create", "id": "..." }`
+#[derive(Debug, Clone, Serialize)]
+pub struct DataChanged<'a> {
+    pub entity: Entity,
+    pub action: Action,
+    pub id: &'a str,
+}
+
+/// Tell listeners a row changed so they can refetch.
+/// The change is already saved, so a failed emit is only logged.
+pub fn emit_data_changed(app: &AppHandle, entity: Entity, action: Action, id: &str) {
+    let payload = DataChanged { entity, action, id };
+
+    if let Err(e) = app.emit(DATA_CHANGED_EVENT, payload) {
+        log::warn!("Failed to emit {}: {}", DATA_CHANGED_EVENT, e);
+    }
+}
@@ -1,25 +1,36 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 mod commands;
+mod crypto;
 mod database;
+mod events;
+mod timing;
+mod tray;
+mod units;
+mod validation;
 
 use tauri::{
-    image::Image,
     menu::{MenuBuilder, MenuItemBuilder},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
     Emitter, Manager, RunEvent, WindowEvent,
 };
-use tauri_plugin_updater::UpdaterExt;
+use commands::settings::{CloseBehavior, CloseBehaviorState};
+use commands::sync::SyncServerState;
+use commands::undo::DeletedGoals;
+use tauri_plugin_autostart::MacosLauncher;
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
 
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_updater::Builder::new().build())
         .setup(setup_app)
         .invoke_handler(tauri::generate_handler![
             // Auth commands
             commands::auth::hash_password,
             commands::auth::verify_password,
+            commands::auth::rehash_password,
             commands::auth::check_password_strength,
             // User data commands
             commands::user_data::get_user_data,
@@ -29,53 +40,114 @@ fn main() {
             commands::user_data::get_user_data_field,
             commands::user_data::delete_user_data,
             commands::user_data::user_data_exists,
+            commands::user_data::set_last_view,
+            commands::user_data::get_last_view,
             // Goal commands
             commands::goals::create_goal,
+            commands::goals::create_goal_with_tasks,
             commands::goals::update_goal,
             commands::goals::delete_goal,
+            commands::goals::preview_goal_deletion,
             commands::goals::get_all_goals,
+            commands::goals::get_goals_sorted,
             commands::goals::get_goal_by_id,
+            commands::goals::get_goal_with_tasks,
             commands::goals::get_goals_by_status,
+            commands::goals::rename_goal_category,
+            commands::goals::get_goal_categories,
+            commands::goals::get_goals_completed_between,
+            commands::goals::get_goal_statistics,
+            commands::goals::get_stale_goals,
+            commands::goals::find_duplicate_goals,
+            commands::goals::merge_goals,
             // Task commands
             commands::tasks::create_task,
             commands::tasks::update_task,
             commands::tasks::delete_task,
             commands::tasks::get_all_tasks,
+            commands::tasks::get_all_tasks_with_subtask_counts,
             commands::tasks::get_task_by_id,
             commands::tasks::get_tasks_by_goal_id,
             commands::tasks::get_tasks_by_status,
             commands::tasks::toggle_task_status,
+            commands::tasks::set_tasks_done,
+            commands::tasks::reassign_tasks,
+            commands::tasks::get_tasks_due_today,
+            commands::tasks::get_tasks_due_between,
             commands::tasks::get_subtasks,
+            // Attachment commands
+            commands::attachments::add_attachment,
+            commands::attachments::remove_attachment,
+            commands::attachments::get_attachments,
+            // Focus session commands
+            commands::focus_sessions::start_focus_session,
+            commands::focus_sessions::end_focus_session,
+            commands::focus_sessions::get_focus_sessions,
+            commands::focus_sessions::get_task_time_spent,
             // Habit commands
             commands::habits::create_habit,
             commands::habits::update_habit,
             commands::habits::delete_habit,
             commands::habits::get_all_habits,
             commands::habits::get_habit_by_id,
+            commands::habits::get_habit_detail,
+            commands::habits::get_consistency_score,
             commands::habits::get_habits_by_category,
+            commands::habits::rename_habit_category,
+            commands::habits::get_habit_categories,
+            commands::habits::get_habits_by_linked_goal,
             // Habit completion commands
             commands::habit_completions::create_habit_completion,
+            commands::habit_completions::create_habit_completions_batch,
+            commands::habit_completions::skip_habits,
             commands::habit_completions::update_habit_completion,
+            commands::habit_completions::log_habit_amount,
+            commands::habit_completions::convert_amount,
             commands::habit_completions::delete_habit_completion,
+            commands::habit_completions::sync_completion_targets,
             commands::habit_completions::get_habit_completions,
+            commands::habit_completions::get_habit_completions_page,
             commands::habit_completions::get_completion_by_date,
+            commands::habit_completions::get_completions_by_dates,
+            commands::habit_completions::get_completions_for_date,
+            commands::habit_completions::get_completions_aggregate,
+            commands::habit_completions::get_completions_for_month,
+            commands::habit_completions::search_completion_notes,
             commands::habit_completions::get_habit_streak,
+            commands::habit_completions::get_all_streaks,
+            commands::habit_completions::get_streak_leaderboard,
+            commands::habit_completions::get_today_progress,
+            // Habit pause commands
+            commands::habit_pauses::pause_habit,
+            commands::habit_pauses::resume_habit,
+            commands::habit_pauses::get_habit_pauses,
+            commands::habit_frequency_history::get_habit_frequency_history,
+            // Habit schedule commands
+            commands::habit_schedule::get_habits_due_today,
+            commands::habit_schedule::get_next_occurrence,
+            commands::habit_schedule::get_missed_dates,
             // Notification commands
             commands::notifications::send_system_notification,
             commands::notifications::schedule_notification,
             commands::notifications::get_scheduled_notifications,
             commands::notifications::get_habit_notifications,
+            commands::notifications::set_habit_reminders,
             commands::notifications::cancel_notification,
+            commands::notifications::cancel_notification_by_id,
             commands::notifications::cancel_all_notifications,
             commands::notifications::record_notification,
             commands::notifications::get_notification_history,
             commands::notifications::mark_notification_opened,
             commands::notifications::clean_notification_history,
+            commands::notifications::cleanup_orphaned_schedules,
+            commands::notifications::cleanup_orphaned_history,
             commands::notifications::check_notification_permission,
             commands::notifications::request_notification_permission,
             // Settings commands
             commands::settings::get_settings,
+            commands::settings::get_settings_or_default,
             commands::settings::save_settings,
+            commands::settings::patch_settings,
             commands::settings::update_appearance_settings,
             commands::settings::update_habit_settings,
             commands::settings::update_goal_settings,
@@ -84,14 +156,57 @@ fn main() {
             commands::settings::reset_settings,
             commands::settings::export_settings,
             commands::settings::import_settings,
+            commands::settings::get_autostart,
+            commands::settings::set_autostart,
             commands::settings::export_all_data,
+            commands::settings::export_filtered,
             commands::settings::import_all_data,
+            commands::settings::export_all_data_encrypted,
+            commands::settings::import_all_data_encrypted,
+            commands::settings::validate_import,
+            commands::settings::export_habit,
+            commands::settings::import_habit,
+            // Sync commands
+            commands::sync::get_sync_status,
+            commands::sync::set_sync_settings,
+            commands::sync::regenerate_sync_token,
+            // Undo commands
+            commands::undo::delete_goal_with_undo,
+            commands::undo::delete_habit_with_undo,
+            commands::undo::restore_from_undo,
+            commands::undo::undo_last_delete,
+            // Report commands
+            commands::reports::generate_markdown_report,
+            // Analytics commands
+            commands::analytics::get_mood_trends,
+            commands::analytics::get_category_summary,
+            commands::analytics::get_recent_activity,
+            // Overview commands
+            commands::overview::get_today_overview,
+            commands::overview::get_counts,
+            // Maintenance commands
+            commands::maintenance::flush_database,
+            commands::maintenance::rebuild_indexes,
+            commands::maintenance::factory_reset,
+            // Profile commands
+            commands::profiles::list_profiles,
+            commands::profiles::create_profile,
+            commands::profiles::switch_profile,
             // App commands
             commands::app::get_app_version,
             commands::app::get_app_info,
+            commands::app::health_check,
             commands::app::get_app_data_dir,
             commands::app::get_app_log_dir,
+            commands::app::get_storage_usage,
+            commands::app::get_recent_logs,
             commands::app::is_dev_mode,
+            commands::app::quit_app,
+            commands::app::restart_app,
+            // Updater commands
+            commands::updater::download_and_install_update,
+            commands::updater::get_update_channel,
+            commands::updater::set_update_channel,
         ])
         .build(tauri::generate_context!())
         .expect("error while running tauri application")
@@ -100,11 +215,39 @@ fn main() {
 
 /// Setup application state and system tray
 fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
+    // Initialize logging first so later setup failures are captured
+    setup_logging(app)?;
+
     // Initialize the database
-    database::init_database(app.handle())?;
+    database::init_database(app.handle()).map_err(|e| {
+        log::error!("Database initialization failed: {}", e);
+        e
+    })?;
+
+    // Cache the close behavior for the run-event handler
+    app.manage(CloseBehaviorState::default());
+    if let Err(e) = commands::settings::refresh_close_behavior(&app.state(), app.handle()) {
+        log::warn!("Failed to load close behavior: {}", e);
+    }
+
+    // Goals deleted this session, for undo_last_delete
+    app.manage(DeletedGoals::default());
+
+    // Serve LAN sync requests if enabled
+    app.manage(SyncServerState::default());
+    commands::sync::refresh_sync_server(app.handle());
+
+    // Register launch-on-login support and reconcile the stored setting with the OS
+    app.handle()
+        .plugin(tauri_plugin_autostart::init(MacosLauncher::LaunchAgent, None))?;
+
+    if let Err(e) = commands::settings::sync_autostart_setting(&app.state(), app.handle()) {
+        log::warn!("Failed to sync autostart setting: {}", e);
+    }
 
     // Setup system tray
     setup_system_tray(app)?;
+    tray::spawn_status_refresh(app.handle().clone());
 
     #[cfg(debug_assertions)]
     {
@@ -115,10 +258,37 @@ fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-/// Load tray icon from embedded resources
-fn load_tray_icon() -> Image<'static> {
-    Image::from_bytes(include_bytes!("../icons/32x32.png"))
-        .expect("Failed to load tray icon")
+/// Resolve the log level from `LOOMRA_LOG_LEVEL`, falling back to a build-based default
+fn resolve_log_level() -> log::LevelFilter {
+    let default_level = if cfg!(debug_assertions) {
+        log::LevelFilter::Debug
+    } else {
+        log::LevelFilter::Info
+    };
+
+    std::env::var("LOOMRA_LOG_LEVEL")
+        .ok()
+        .and_then(|level| level.parse().ok())
+        .unwrap_or(default_level)
+}
+
+/// Register the log plugin writing rotating files to the app log directory
+fn setup_logging(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>> {
+    app.handle().plugin(
+        tauri_plugin_log::Builder::new()
+            .targets([
+                Target::new(TargetKind::Stdout),
+                Target::new(TargetKind::LogDir {
+                    file_name: Some(commands::app::LOG_FILE_NAME.to_string()),
+                }),
+            ])
+            .level(resolve_log_level())
+            .max_file_size(5_000_000)
+            .rotation_strategy(RotationStrategy::KeepSome(5))
+            .build(),
+    )?;
+
+    Ok(())
 }
 
 /// Build the system tray with better icons
@@ -141,10 +311,10 @@ fn setup_system_tray(app: &tauri::App) -> Result<(), Box<dyn std::error::Error>>
         .build()?;
 
     // Load the default tray icon
-    let tray_icon = load_tray_icon();
+    let tray_icon = tray::load_tray_icon();
 
     // Build tray with custom icon
-    TrayIconBuilder::new()
+    TrayIconBuilder::with_id(tray::TRAY_ID)
         .menu(&tray_menu)
         .icon(tray_icon)
         .on_tray_icon_event(handle_tray_icon_event)
@@ -194,40 +364,12 @@ fn handle_tray_menu_event(app: &tauri::AppHandle, event: tauri::menu::MenuEvent)
             }
         }
         "check_updates" => {
-            check_for_updates(app.clone());
+            commands::updater::check_for_updates(app.clone());
         }
         _ => {}
     }
 }
 
-/// Trigger manual update check
-fn check_for_updates(app: tauri::AppHandle) {
-    tauri::async_runtime::spawn(async move {
-        match app.updater_builder().build() {
-            Ok(updater) => {
-                match updater.check().await {
-                    Ok(Some(update)) => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("update-available", update.version);
-                        }
-                    }
-                    Ok(None) => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            let _ = window.emit("update-not-available", ());
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("Update check failed: {}", e);
-                    }
-                }
-            }
-            Err(e) => {
-                eprintln!("Failed to build updater: {}", e);
-            }
-        }
-    });
-}
-
 /// Handle application run events
 fn handle_run_events(app: &tauri::AppHandle, event: RunEvent) {
     match event {
@@ -237,10 +379,29 @@ fn handle_run_events(app: &tauri::AppHandle, event: RunEvent) {
             ..
         } => {
             if label == "main" {
-                if let Some(window) = app.get_webview_window(&label) {
-                    let _ = window.hide();
+                let behavior = app
+                    .try_state::<CloseBehaviorState>()
+                    .map(|cache| cache.get())
+                    .unwrap_or_default();
+
+                match behavior {
+                    CloseBehavior::Tray => {
+                        if let Some(window) = app.get_webview_window(&label) {
+                            let _ = window.hide();
+                        }
+                        api.prevent_close();
+                    }
+                    CloseBehavior::Quit => {
+                        app.exit(0);
+                    }
+                    CloseBehavior::Ask => {
+                        // The frontend decides via `quit_app` or by hiding the window
+                        api.prevent_close();
+                        if let Some(window) = app.get_webview_window(&label) {
+                            let _ = window.emit("close-requested", ());
+                        }
+                    }
                 }
-                api.prevent_close();
             }
         }
         _ => {}
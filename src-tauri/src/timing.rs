@@ -0,0 +1,53 @@
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+/// Commands running longer than this are logged, unless `LOOMRA_SLOW_COMMAND_MS` overrides it
+const DEFAULT_SLOW_COMMAND_THRESHOLD: Duration = Duration::from_millis(50);
+
+/// Resolve the slow command threshold from `LOOMRA_SLOW_COMMAND_MS`, read once per run
+fn slow_command_threshold() -> Duration {
+    static THRESHOLD: OnceLock<Duration> = OnceLock::new();
+
+    *THRESHOLD.get_or_init(|| {
+        std::env::var("LOOMRA_SLOW_COMMAND_MS")
+            .ok()
+            .and_then(|ms| ms.trim().parse().ok())
+            .map(Duration::from_millis)
+            .unwrap_or(DEFAULT_SLOW_COMMAND_THRESHOLD)
+    })
+}
+
+/// Times a command from creation until it's dropped, logging it if it ran past the threshold.
+///
+/// Bind it to a named variable at the top of the command body so it lives until the command returns:
+/// `let _timer = CommandTimer::start("get_all_habits");`
+pub struct CommandTimer {
+    command: &'static str,
+    started: Instant,
+}
+
+impl CommandTimer {
+    pub fn start(command: &'static str) -> Self {
+        Self {
+            command,
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Drop for CommandTimer {
+    fn drop(&mut self) {
+        let elapsed = self.started.elapsed();
+        let threshold = slow_command_threshold();
+
+        if elapsed > threshold {
+            log::warn!(
+                target: "loomra::slow_command",
+                "slow command: command={} elapsed_ms={} threshold_ms={}",
+                self.command,
+                elapsed.as_millis(),
+                threshold.as_millis()
+            );
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use crate::commands::habit_schedule::habits_due_on;
+use crate::commands::settings::{configured_now, configured_week_start, load_settings_from_db};
+use crate::commands::tasks::tasks_due_on;
+use crate::database::{with_connection, AppState};
+use std::thread;
+use std::time::Duration;
+use tauri::{image::Image, AppHandle, Manager};
+
+/// Id of the app's single tray icon
+pub const TRAY_ID: &str = "main";
+
+/// How often the pending count is recomputed, which also picks up the date rolling over
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Badge color drawn over the icon while items are pending
+const BADGE_RGBA: [u8; 4] = [239, 68, 68, 255];
+
+/// Load tray icon from embedded resources
+pub fn load_tray_icon() -> Image<'static> {
+    Image::from_bytes(include_bytes!("../icons/32x32.png"))
+        .expect("Failed to load tray icon")
+}
+
+/// Tray icon with a dot in the top-right corner
+fn load_badged_tray_icon() -> Image<'static> {
+    let icon = load_tray_icon();
+    let (width, height) = (icon.width(), icon.height());
+    let mut rgba = icon.rgba().to_vec();
+
+    let radius = (width.min(height) / 4) as i64;
+    let (center_x, center_y) = (width as i64 - radius - 1, radius);
+
+    for y in 0..height as i64 {
+        for x in 0..width as i64 {
+            let (dx, dy) = (x - center_x, y - center_y);
+            if dx * dx + dy * dy <= radius * radius {
+                let offset = ((y * width as i64 + x) * 4) as usize;
+                rgba[offset..offset + 4].copy_from_slice(&BADGE_RGBA);
+            }
+        }
+    }
+
+    Image::new_owned(rgba, width, height)
+}
+
+fn pluralize(count: usize, noun: &str) -> String {
+    if count == 1 {
+        format!("{} {}", count, noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+/// Build the tooltip text, e.g. "3 habits, 2 tasks due today"
+fn tooltip_text(habits: usize, tasks: usize) -> String {
+    let parts: Vec<String> = [(habits, "habit"), (tasks, "task")]
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, noun)| pluralize(count, noun))
+        .collect();
+
+    if parts.is_empty() {
+        "Loomra - nothing due today".to_string()
+    } else {
+        format!("Loomra - {} due today", parts.join(", "))
+    }
+}
+
+fn try_refresh_status(app: &AppHandle) -> Result<(), String> {
+    // The tray or database may not be set up yet during startup
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return Ok(());
+    };
+    let Some(state) = app.try_state::<AppState>() else {
+        return Ok(());
+    };
+
+    let settings = load_settings_from_db(&state)?;
+    let today = configured_now(settings.as_ref()).date();
+    let week_start = configured_week_start(settings.as_ref());
+    with_connection(&state, |db| {
+        let habits = habits_due_on(db, today, week_start)?.len();
+        let tasks = tasks_due_on(db, &today.format("%Y-%m-%d").to_string())?.len();
+
+        tray.set_tooltip(Some(tooltip_text(habits, tasks)))
+            .map_err(|e| format!("Failed to set tray tooltip: {}", e))?;
+
+        let icon = if habits + tasks > 0 {
+            load_badged_tray_icon()
+        } else {
+            load_tray_icon()
+        };
+
+        tray.set_icon(Some(icon))
+            .map_err(|e| format!("Failed to set tray icon: {}", e))?;
+
+        Ok(())
+    })
+}
+
+/// Update the tray tooltip and badge with today's pending habits and tasks
+pub fn refresh_status(app: &AppHandle) {
+    if let Err(e) = try_refresh_status(app) {
+        log::warn!("Failed to refresh tray status: {}", e);
+    }
+}
+
+/// Refresh the tray status now and then periodically in the background
+pub fn spawn_status_refresh(app: AppHandle) {
+    thread::spawn(move || loop {
+        refresh_status(&app);
+        thread::sleep(REFRESH_INTERVAL);
+    });
+}
@@ -0,0 +1,81 @@
+use crate::validation::ValidationError;
+
+/// Kinds of quantity a habit can be measured in. Only units of the same kind convert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UnitFamily {
+    Volume,
+    Distance,
+    Time,
+    Count,
+}
+
+/// Known unit names with their family and size in the family's base unit
+/// (liters, meters, seconds and single repetitions)
+const UNITS: [(&str, UnitFamily, f64); 36] = [
+    ("ml", UnitFamily::Volume, 0.001),
+    ("milliliter", UnitFamily::Volume, 0.001),
+    ("milliliters", UnitFamily::Volume, 0.001),
+    ("millilitre", UnitFamily::Volume, 0.001),
+    ("millilitres", UnitFamily::Volume, 0.001),
+    ("l", UnitFamily::Volume, 1.0),
+    ("liter", UnitFamily::Volume, 1.0),
+    ("liters", UnitFamily::Volume, 1.0),
+    ("litre", UnitFamily::Volume, 1.0),
+    ("litres", UnitFamily::Volume, 1.0),
+    ("fl oz", UnitFamily::Volume, 0.029_573_529_562_5),
+    ("cup", UnitFamily::Volume, 0.236_588_236_5),
+    ("cups", UnitFamily::Volume, 0.236_588_236_5),
+    ("m", UnitFamily::Distance, 1.0),
+    ("meter", UnitFamily::Distance, 1.0),
+    ("meters", UnitFamily::Distance, 1.0),
+    ("metre", UnitFamily::Distance, 1.0),
+    ("metres", UnitFamily::Distance, 1.0),
+    ("km", UnitFamily::Distance, 1000.0),
+    ("kilometers", UnitFamily::Distance, 1000.0),
+    ("kilometres", UnitFamily::Distance, 1000.0),
+    ("mi", UnitFamily::Distance, 1609.344),
+    ("miles", UnitFamily::Distance, 1609.344),
+    ("s", UnitFamily::Time, 1.0),
+    ("sec", UnitFamily::Time, 1.0),
+    ("seconds", UnitFamily::Time, 1.0),
+    ("min", UnitFamily::Time, 60.0),
+    ("minute", UnitFamily::Time, 60.0),
+    ("minutes", UnitFamily::Time, 60.0),
+    ("h", UnitFamily::Time, 3600.0),
+    ("hr", UnitFamily::Time, 3600.0),
+    ("hour", UnitFamily::Time, 3600.0),
+    ("hours", UnitFamily::Time, 3600.0),
+    ("count", UnitFamily::Count, 1.0),
+    ("time", UnitFamily::Count, 1.0),
+    ("times", UnitFamily::Count, 1.0),
+];
+
+fn lookup(unit: &str) -> Option<(UnitFamily, f64)> {
+    let unit = unit.trim().to_lowercase();
+    UNITS
+        .iter()
+        .find(|(name, _, _)| *name == unit)
+        .map(|(_, family, size)| (*family, *size))
+}
+
+/// Convert `value` from one unit to another of the same kind, e.g. milliliters to liters.
+/// Names are matched case-insensitively, and any unit converts to itself, even one not listed.
+pub fn convert_amount(value: f64, from_unit: &str, to_unit: &str) -> Result<f64, ValidationError> {
+    if from_unit.trim().eq_ignore_ascii_case(to_unit.trim()) {
+        return Ok(value);
+    }
+
+    let (from_family, from_size) =
+        lookup(from_unit).ok_or_else(|| ValidationError::UnknownUnit(from_unit.trim().to_string()))?;
+    let (to_family, to_size) =
+        lookup(to_unit).ok_or_else(|| ValidationError::UnknownUnit(to_unit.trim().to_string()))?;
+
+    if from_family != to_family {
+        return Err(ValidationError::IncompatibleUnits {
+            from: from_unit.trim().to_string(),
+            to: to_unit.trim().to_string(),
+        });
+    }
+
+    Ok(value * from_size / to_size)
+}
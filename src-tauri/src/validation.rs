@@ -0,0 +1,223 @@
+use chrono::{NaiveDate, NaiveTime, Weekday};
+use serde_json::Value;
+use std::path::Path;
+use tauri::Url;
+
+/// Named palette entries offered by the color picker
+const PALETTE: [(&str, &str); 8] = [
+    ("blue", "#3b82f6"),
+    ("green", "#22c55e"),
+    ("purple", "#8b5cf6"),
+    ("orange", "#f97316"),
+    ("pink", "#ec4899"),
+    ("red", "#ef4444"),
+    ("yellow", "#facc15"),
+    ("gray", "#6b7280"),
+];
+
+/// Icons available in the icon selector
+const ICONS: [&str; 78] = [
+    "Check", "CheckCircle", "Circle", "Target", "Trophy", "Star", "Zap", "TrendingUp", "Medal",
+    "Award", "Rocket", "Flag", "Repeat", "Calendar", "Clock", "Hourglass", "CalendarCheck",
+    "Timer", "AlarmClock", "Heart", "Activity", "Dumbbell", "Apple", "Droplet", "Weight",
+    "Footprints", "Bike", "Brain", "Smile", "Leaf", "Sunrise", "Moon", "Sparkles", "Sun",
+    "Lightbulb", "Book", "BookOpen", "Pen", "Code", "Briefcase", "Laptop", "FileText", "Coffee",
+    "Bed", "GlassWater", "Utensils", "Brush", "ShoppingCart", "Music", "Camera", "Paintbrush",
+    "Mic", "Palette", "Guitar", "DollarSign", "PiggyBank", "Wallet", "GraduationCap", "Languages",
+    "Calculator", "Pencil", "Users", "User", "Phone", "Mail", "MessageCircle", "Flower2", "Sprout",
+    "Trees", "Car", "Home", "Globe", "Plane", "Mountain", "Bell", "Gift", "ThumbsUp",
+    "ClipboardList",
+];
+
+/// Custom error type for field validation
+#[derive(Debug, thiserror::Error)]
+pub enum ValidationError {
+    #[error("Invalid color '{0}': expected a #RRGGBB hex code or a palette name")]
+    InvalidColor(String),
+
+    #[error("Unknown icon '{0}'")]
+    InvalidIcon(String),
+
+    #[error("Invalid date '{0}', expected YYYY-MM-DD")]
+    InvalidDate(String),
+
+    #[error("Invalid time '{0}', expected HH:MM")]
+    InvalidTime(String),
+
+    #[error("Unknown weekday '{0}'")]
+    InvalidWeekday(String),
+
+    #[error("A weekday schedule needs at least one day")]
+    EmptyWeekdays,
+
+    #[error("Weekday '{0}' is listed more than once")]
+    DuplicateWeekday(String),
+
+    #[error("Invalid settings: {}", .0.join("; "))]
+    InvalidSettings(Vec<String>),
+
+    #[error("Category name cannot be empty")]
+    EmptyCategory,
+
+    #[error("Unknown unit '{0}'")]
+    UnknownUnit(String),
+
+    #[error("Invalid URL '{0}': expected an http or https link")]
+    InvalidUrl(String),
+
+    #[error("Invalid file path '{0}': expected an absolute path to an existing file or folder")]
+    InvalidFilePath(String),
+
+    #[error("Completion threshold {0} must be greater than 0 and at most 1")]
+    InvalidCompletionThreshold(f64),
+
+    #[error("Consistency decay {0} must be greater than 0 and at most 1")]
+    InvalidConsistencyDecay(f64),
+
+    #[error("Cannot convert {from} to {to}")]
+    IncompatibleUnits { from: String, to: String },
+
+    #[error("Cannot add more {table}: the limit of {limit} has been reached")]
+    LimitReached { table: &'static str, limit: u32 },
+
+    #[error("Cannot log a completion for {0}, which is in the future")]
+    FutureCompletion(String),
+
+    #[error("Cannot log a completion for {date}, before the habit started on {start_date}")]
+    CompletionBeforeStart { date: String, start_date: String },
+}
+
+impl From<ValidationError> for String {
+    fn from(err: ValidationError) -> Self {
+        err.to_string()
+    }
+}
+
+/// Normalize a color to lowercase `#rrggbb`.
+/// Accepts `#RRGGBB`, the `#RGB` shorthand, or a palette name such as "blue".
+pub fn normalize_color(color: &str) -> Result<String, ValidationError> {
+    let trimmed = color.trim();
+    let lower = trimmed.to_ascii_lowercase();
+
+    if let Some((_, hex)) = PALETTE.iter().find(|(name, _)| *name == lower) {
+        return Ok(hex.to_string());
+    }
+
+    let digits = lower
+        .strip_prefix('#')
+        .filter(|d| d.chars().all(|c| c.is_ascii_hexdigit()))
+        .ok_or_else(|| ValidationError::InvalidColor(trimmed.to_string()))?;
+
+    match digits.len() {
+        6 => Ok(format!("#{}", digits)),
+        3 => Ok(digits.chars().fold(String::from("#"), |mut acc, c| {
+            acc.push(c);
+            acc.push(c);
+            acc
+        })),
+        _ => Err(ValidationError::InvalidColor(trimmed.to_string())),
+    }
+}
+
+/// Check that an icon name is one the frontend can render
+pub fn validate_icon(icon: &str) -> Result<(), ValidationError> {
+    if ICONS.contains(&icon) {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidIcon(icon.to_string()))
+    }
+}
+
+/// Check that a habit's completion threshold is a share of its target in (0, 1]
+pub fn validate_completion_threshold(threshold: f64) -> Result<(), ValidationError> {
+    if threshold > 0.0 && threshold <= 1.0 {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidCompletionThreshold(threshold))
+    }
+}
+
+/// Check that an attachment URL is an absolute http or https link
+pub fn validate_attachment_url(url: &str) -> Result<(), ValidationError> {
+    match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => Ok(()),
+        _ => Err(ValidationError::InvalidUrl(url.to_string())),
+    }
+}
+
+/// Check that an attachment path is absolute and points at a file or folder that exists
+pub fn validate_attachment_path(path: &str) -> Result<(), ValidationError> {
+    let candidate = Path::new(path);
+    if candidate.is_absolute() && candidate.exists() {
+        Ok(())
+    } else {
+        Err(ValidationError::InvalidFilePath(path.to_string()))
+    }
+}
+
+/// Check that a completion date is no later than `latest_allowed` and not before the habit's start date.
+/// The start date may carry a time component, so only its date part is compared.
+pub fn validate_completion_date(
+    date: &str,
+    start_date: &str,
+    latest_allowed: NaiveDate,
+) -> Result<(), ValidationError> {
+    let parsed = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| ValidationError::InvalidDate(date.to_string()))?;
+
+    if parsed > latest_allowed {
+        return Err(ValidationError::FutureCompletion(date.to_string()));
+    }
+
+    let start = start_date.get(..10).unwrap_or(start_date);
+    if NaiveDate::parse_from_str(start, "%Y-%m-%d").is_ok_and(|start| parsed < start) {
+        return Err(ValidationError::CompletionBeforeStart {
+            date: date.to_string(),
+            start_date: start.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Parse a 24-hour `HH:MM` time of day
+pub fn parse_time_of_day(time: &str) -> Result<NaiveTime, ValidationError> {
+    NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| ValidationError::InvalidTime(time.to_string()))
+}
+
+/// Check that a weekday schedule, such as a "daily" frequency's value, is a non-empty
+/// list of distinct lowercase weekday names
+pub fn validate_weekdays(days: &Value) -> Result<(), ValidationError> {
+    let days = days
+        .as_array()
+        .ok_or_else(|| ValidationError::InvalidWeekday(days.to_string()))?;
+
+    if days.is_empty() {
+        return Err(ValidationError::EmptyWeekdays);
+    }
+
+    let mut seen = Vec::with_capacity(days.len());
+    for day in days {
+        let name = day
+            .as_str()
+            .ok_or_else(|| ValidationError::InvalidWeekday(day.to_string()))?;
+        let weekday = parse_weekday(name)?;
+
+        if seen.contains(&weekday) {
+            return Err(ValidationError::DuplicateWeekday(name.to_string()));
+        }
+        seen.push(weekday);
+    }
+
+    Ok(())
+}
+
+/// Parse a lowercase weekday name such as "monday"
+pub fn parse_weekday(day: &str) -> Result<Weekday, ValidationError> {
+    match day {
+        "monday" | "tuesday" | "wednesday" | "thursday" | "friday" | "saturday" | "sunday" => {
+            day.parse().map_err(|_| ValidationError::InvalidWeekday(day.to_string()))
+        }
+        _ => Err(ValidationError::InvalidWeekday(day.to_string())),
+    }
+}